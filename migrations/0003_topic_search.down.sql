@@ -0,0 +1,10 @@
+DROP VIEW v_topic_search;
+
+DROP TRIGGER trg_topic_search_vector ON topic;
+DROP FUNCTION topic_search_vector_update();
+
+DROP TRIGGER trg_topic_content_search_vector ON topic_content;
+DROP FUNCTION topic_content_search_vector_update();
+
+DROP INDEX idx_topic_content_search_vector;
+ALTER TABLE topic_content DROP COLUMN search_vector;
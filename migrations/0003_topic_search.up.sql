@@ -0,0 +1,45 @@
+-- 全文搜索
+-- 文章的全文索引向量，由标题、摘要和 Markdown 内容组成，通过触发器维护
+ALTER TABLE topic_content ADD COLUMN search_vector TSVECTOR NOT NULL DEFAULT ''::TSVECTOR;
+
+CREATE INDEX idx_topic_content_search_vector ON topic_content USING GIN (search_vector);
+
+CREATE OR REPLACE FUNCTION topic_content_search_vector_update() RETURNS TRIGGER AS $$
+BEGIN
+    SELECT
+        setweight(to_tsvector('simple', t.title), 'A') ||
+        setweight(to_tsvector('simple', t.summary), 'B') ||
+        setweight(to_tsvector('simple', NEW.md), 'C')
+    INTO NEW.search_vector
+    FROM topic AS t
+    WHERE t.id = NEW.topic_id;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_topic_content_search_vector
+    BEFORE INSERT OR UPDATE OF md ON topic_content
+    FOR EACH ROW EXECUTE PROCEDURE topic_content_search_vector_update();
+
+-- 标题或摘要变化时，重新计算内容的索引向量
+CREATE OR REPLACE FUNCTION topic_search_vector_update() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE topic_content SET md = md WHERE topic_id = NEW.id;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_topic_search_vector
+    AFTER UPDATE OF title, summary ON topic
+    FOR EACH ROW EXECUTE PROCEDURE topic_search_vector_update();
+
+-- 为已有文章生成索引向量
+UPDATE topic_content SET md = md;
+
+-- 前台全文搜索
+CREATE VIEW v_topic_search AS
+SELECT v.id,v.title,v.slug,subject_slug,COALESCE(tag_names, ARRAY[]::VARCHAR[]) AS tag_names,subject_name,t.summary,c.md,c.search_vector
+ FROM v_topic_subject_list_with_tags AS v
+INNER JOIN topic AS t on t.id=v.id
+INNER JOIN topic_content AS c ON c.topic_id=v.id
+WHERE v.is_del=false AND v.subject_is_del=false;
@@ -0,0 +1,13 @@
+-- 管理员API令牌，只保存令牌的 SHA-256 哈希
+CREATE TABLE admin_token (
+    id SERIAL PRIMARY KEY,
+    admin_id INTEGER NOT NULL REFERENCES admin(id),
+    name VARCHAR(100) NOT NULL,
+    token_hash CHAR(64) NOT NULL,
+    dateline INTEGER NOT NULL DEFAULT 0,
+    last_used INTEGER NOT NULL DEFAULT 0,
+    is_del BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE(token_hash)
+);
+
+CREATE INDEX idx_admin_token_admin_id ON admin_token (admin_id);
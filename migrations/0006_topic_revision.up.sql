@@ -0,0 +1,18 @@
+-- 文章修订历史，每次保存文章都记录一个版本
+CREATE TABLE topic_revision (
+    id BIGSERIAL PRIMARY KEY,
+    topic_id BIGINT NOT NULL REFERENCES topic(id),
+    title VARCHAR(255) NOT NULL,
+    summary VARCHAR(255) NOT NULL,
+    md VARCHAR NOT NULL,
+    tags VARCHAR NOT NULL DEFAULT '',
+    admin_id INTEGER REFERENCES admin(id),
+    dateline INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_topic_revision_topic_id ON topic_revision (topic_id);
+
+-- 为已有文章生成初始版本，操作者未知
+INSERT INTO topic_revision (topic_id, title, summary, md, tags, admin_id, dateline)
+SELECT id, title, summary, md, array_to_string(COALESCE(tag_names, ARRAY[]::VARCHAR[]), ','), NULL, EXTRACT(EPOCH FROM now())::INTEGER
+ FROM v_topic_with_md_and_tags_for_edit;
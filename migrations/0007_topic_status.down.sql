@@ -0,0 +1,79 @@
+DROP VIEW v_topic_detail;
+DROP VIEW v_topic_preview;
+CREATE VIEW v_topic_detail AS
+SELECT 
+	t.id,title,subject_id,t.slug,author,src,c.html,tt.tag_names,s.slug AS subject_slug,dateline,hit,s.name AS subject_name
+FROM topic AS t
+INNER JOIN topic_content AS c ON c.topic_id=t.id
+INNER JOIN subject AS s ON t.subject_id=s.id
+LEFT JOIN (
+	SELECT 
+		tt.topic_id,
+		array_agg(t.name) AS tag_names
+	FROM topic_tag AS tt
+	INNER JOIN tag AS t ON t.id=tt.tag_id
+	WHERE tt.is_del=false AND t.is_del=false
+	GROUP BY tt.topic_id
+) AS tt on tt.topic_id=t.id
+WHERE t.is_del = false AND s.is_del=false;
+
+DROP VIEW v_topic_with_md_and_tags_for_edit;
+CREATE VIEW v_topic_with_md_and_tags_for_edit AS
+SELECT 
+	t.id,title,subject_id,slug,summary,author,src,c.md,tt.tag_names
+FROM topic AS t
+INNER JOIN topic_content AS c ON c.topic_id=t.id
+LEFT JOIN (
+	SELECT 
+		tt.topic_id,
+		array_agg(t.name) AS tag_names
+	FROM topic_tag AS tt
+	INNER JOIN tag AS t ON t.id=tt.tag_id
+	WHERE tt.is_del=false AND t.is_del=false
+	GROUP BY tt.topic_id
+) AS tt on tt.topic_id=t.id;
+
+-- v_topic_subject_list 不能通过 CREATE OR REPLACE 删除列，需要连同依赖它的视图一起重建
+DROP VIEW v_topic_search;
+DROP VIEW v_subject_topics;
+DROP VIEW v_topic_subject_list_with_tags;
+DROP VIEW v_topic_subject_list;
+
+CREATE VIEW v_topic_subject_list AS
+    SELECT t.id, t.title, t.slug, s.name AS subject_name, s.slug AS subject_slug, t.subject_id, t.is_del, s.is_del AS subject_is_del
+    FROM topic AS t
+    INNER JOIN subject AS s
+    ON t.subject_id=s.id;
+
+CREATE VIEW v_topic_subject_list_with_tags AS
+    SELECT tsl.id,title,slug,subject_name,subject_slug,subject_id,tsl.is_del,subject_is_del
+        ,tt.tag_ids,tt.tag_names
+    FROM v_topic_subject_list AS tsl
+    LEFT JOIN (
+        SELECT
+            tt.topic_id,
+            array_agg(t.name) AS tag_names,
+            array_agg(tt.tag_id) AS tag_ids
+        FROM topic_tag AS tt
+        INNER JOIN tag AS t ON t.id=tt.tag_id
+        WHERE tt.is_del=false AND t.is_del=false
+        GROUP BY tt.topic_id
+    ) AS tt on tt.topic_id=tsl.id;
+
+CREATE VIEW v_subject_topics AS
+SELECT v.id,v.title,v.slug,subject_slug,COALESCE(tag_names, ARRAY[]::VARCHAR[]) AS tag_names,subject_name,  t.summary, t.author, t.dateline
+ FROM v_topic_subject_list_with_tags AS v
+INNER JOIN topic AS t on t.id=v.id
+INNER JOIN topic_content AS c ON c.topic_id=v.id
+WHERE v.is_del=false AND v.subject_is_del=false;
+
+CREATE VIEW v_topic_search AS
+SELECT v.id,v.title,v.slug,subject_slug,COALESCE(tag_names, ARRAY[]::VARCHAR[]) AS tag_names,subject_name,t.summary,c.md,c.search_vector
+ FROM v_topic_subject_list_with_tags AS v
+INNER JOIN topic AS t on t.id=v.id
+INNER JOIN topic_content AS c ON c.topic_id=v.id
+WHERE v.is_del=false AND v.subject_is_del=false;
+
+DROP FUNCTION topic_is_visible(SMALLINT, INTEGER);
+ALTER TABLE topic DROP COLUMN status;
+ALTER TABLE topic DROP COLUMN publish_at;
@@ -0,0 +1,68 @@
+-- 文章状态：0 草稿、1 定时发布、2 已发布。已有文章均为已发布
+ALTER TABLE topic ADD COLUMN status SMALLINT NOT NULL DEFAULT 2;
+ALTER TABLE topic ADD COLUMN publish_at INTEGER NOT NULL DEFAULT 0;
+
+-- 文章是否对前台可见：已发布，或定时发布且已到发布时间
+CREATE FUNCTION topic_is_visible(status SMALLINT, publish_at INTEGER) RETURNS BOOLEAN AS $$
+    SELECT status = 2 OR (status = 1 AND publish_at <= EXTRACT(EPOCH FROM now()))
+$$ LANGUAGE SQL STABLE;
+
+CREATE OR REPLACE VIEW v_topic_subject_list AS
+    SELECT t.id, t.title, t.slug, s.name AS subject_name, s.slug AS subject_slug, t.subject_id, t.is_del, s.is_del AS subject_is_del, t.status, t.publish_at
+    FROM topic AS t
+    INNER JOIN subject AS s
+    ON t.subject_id=s.id;
+
+CREATE OR REPLACE VIEW v_topic_with_md_and_tags_for_edit AS
+SELECT 
+	t.id,title,subject_id,slug,summary,author,src,c.md,COALESCE(tt.tag_names, ARRAY[]::VARCHAR[]) AS tag_names,t.status,t.publish_at
+FROM topic AS t
+INNER JOIN topic_content AS c ON c.topic_id=t.id
+LEFT JOIN (
+	SELECT 
+		tt.topic_id,
+		array_agg(t.name) AS tag_names
+	FROM topic_tag AS tt
+	INNER JOIN tag AS t ON t.id=tt.tag_id
+	WHERE tt.is_del=false AND t.is_del=false
+	GROUP BY tt.topic_id
+) AS tt on tt.topic_id=t.id;
+
+DROP VIEW v_subject_topics;
+CREATE VIEW v_subject_topics AS
+SELECT v.id,v.title,v.slug,subject_slug,COALESCE(tag_names, ARRAY[]::VARCHAR[]) AS tag_names,subject_name,  t.summary, t.author, t.dateline
+ FROM v_topic_subject_list_with_tags AS v
+INNER JOIN topic AS t on t.id=v.id
+INNER JOIN topic_content AS c ON c.topic_id=v.id
+WHERE v.is_del=false AND v.subject_is_del=false AND topic_is_visible(t.status, t.publish_at);
+
+DROP VIEW v_topic_search;
+CREATE VIEW v_topic_search AS
+SELECT v.id,v.title,v.slug,subject_slug,COALESCE(tag_names, ARRAY[]::VARCHAR[]) AS tag_names,subject_name,t.summary,c.md,c.search_vector
+ FROM v_topic_subject_list_with_tags AS v
+INNER JOIN topic AS t on t.id=v.id
+INNER JOIN topic_content AS c ON c.topic_id=v.id
+WHERE v.is_del=false AND v.subject_is_del=false AND topic_is_visible(t.status, t.publish_at);
+
+-- 文章预览：不过滤状态，由签名链接访问
+DROP VIEW v_topic_detail;
+CREATE VIEW v_topic_preview AS
+SELECT 
+	t.id,title,subject_id,t.slug,author,src,c.html,COALESCE(tt.tag_names, ARRAY[]::VARCHAR[]) AS tag_names,s.slug AS subject_slug,dateline,hit,s.name AS subject_name,t.is_del,s.is_del AS subject_is_del,t.status,t.publish_at
+FROM topic AS t
+INNER JOIN topic_content AS c ON c.topic_id=t.id
+INNER JOIN subject AS s ON t.subject_id=s.id
+LEFT JOIN (
+	SELECT 
+		tt.topic_id,
+		array_agg(t.name) AS tag_names
+	FROM topic_tag AS tt
+	INNER JOIN tag AS t ON t.id=tt.tag_id
+	WHERE tt.is_del=false AND t.is_del=false
+	GROUP BY tt.topic_id
+) AS tt on tt.topic_id=t.id;
+
+CREATE VIEW v_topic_detail AS
+SELECT id,title,subject_id,slug,author,src,html,tag_names,subject_slug,dateline,hit,subject_name
+ FROM v_topic_preview
+WHERE is_del = false AND subject_is_del=false AND topic_is_visible(status, publish_at);
@@ -0,0 +1,15 @@
+DROP VIEW v_topic_with_md_and_tags_for_edit;
+CREATE VIEW v_topic_with_md_and_tags_for_edit AS
+SELECT 
+	t.id,title,subject_id,slug,summary,author,src,c.md,COALESCE(tt.tag_names, ARRAY[]::VARCHAR[]) AS tag_names,t.status,t.publish_at
+FROM topic AS t
+INNER JOIN topic_content AS c ON c.topic_id=t.id
+LEFT JOIN (
+	SELECT 
+		tt.topic_id,
+		array_agg(t.name) AS tag_names
+	FROM topic_tag AS tt
+	INNER JOIN tag AS t ON t.id=tt.tag_id
+	WHERE tt.is_del=false AND t.is_del=false
+	GROUP BY tt.topic_id
+) AS tt on tt.topic_id=t.id;
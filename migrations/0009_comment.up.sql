@@ -0,0 +1,31 @@
+-- 读者评论。状态：0 待审核、1 已通过、2 垃圾评论
+CREATE TABLE comment (
+    id BIGSERIAL PRIMARY KEY,
+    topic_id BIGINT NOT NULL REFERENCES topic(id),
+    parent_id BIGINT REFERENCES comment(id),
+    nickname VARCHAR(50) NOT NULL,
+    email VARCHAR(255) NOT NULL DEFAULT '',
+    content VARCHAR NOT NULL,
+    status SMALLINT NOT NULL DEFAULT 0,
+    dateline INTEGER NOT NULL DEFAULT 0,
+    is_del BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX idx_comment_topic_id ON comment (topic_id, status);
+
+-- 后台审核列表
+CREATE VIEW v_comment_list AS
+SELECT c.id, c.topic_id, c.parent_id, c.nickname, c.email, c.content, c.status, c.dateline, c.is_del,
+    t.title AS topic_title, t.slug AS topic_slug, s.slug AS subject_slug, COALESCE(p.nickname, '') AS parent_nickname
+FROM comment AS c
+INNER JOIN topic AS t ON t.id=c.topic_id
+INNER JOIN subject AS s ON s.id=t.subject_id
+LEFT JOIN comment AS p ON p.id=c.parent_id;
+
+CREATE OR REPLACE VIEW v_subject_topics AS
+SELECT v.id,v.title,v.slug,subject_slug,COALESCE(tag_names, ARRAY[]::VARCHAR[]) AS tag_names,subject_name,  t.summary, t.author, t.dateline,
+    (SELECT COUNT(*) FROM comment AS cm WHERE cm.topic_id=v.id AND cm.status=1 AND cm.is_del=false) AS comment_count
+ FROM v_topic_subject_list_with_tags AS v
+INNER JOIN topic AS t on t.id=v.id
+INNER JOIN topic_content AS c ON c.topic_id=v.id
+WHERE v.is_del=false AND v.subject_is_del=false AND topic_is_visible(t.status, t.publish_at);
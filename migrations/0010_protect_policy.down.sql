@@ -0,0 +1,44 @@
+DROP VIEW v_topic_detail;
+DROP VIEW v_topic_preview;
+DROP VIEW v_topic_with_md_and_tags_for_edit;
+
+CREATE VIEW v_topic_preview AS
+SELECT 
+	t.id,title,subject_id,t.slug,author,src,c.html,COALESCE(tt.tag_names, ARRAY[]::VARCHAR[]) AS tag_names,s.slug AS subject_slug,dateline,hit,s.name AS subject_name,t.is_del,s.is_del AS subject_is_del,t.status,t.publish_at
+FROM topic AS t
+INNER JOIN topic_content AS c ON c.topic_id=t.id
+INNER JOIN subject AS s ON t.subject_id=s.id
+LEFT JOIN (
+	SELECT 
+		tt.topic_id,
+		array_agg(t.name) AS tag_names
+	FROM topic_tag AS tt
+	INNER JOIN tag AS t ON t.id=tt.tag_id
+	WHERE tt.is_del=false AND t.is_del=false
+	GROUP BY tt.topic_id
+) AS tt on tt.topic_id=t.id;
+
+CREATE VIEW v_topic_detail AS
+SELECT id,title,subject_id,slug,author,src,html,tag_names,subject_slug,dateline,hit,subject_name
+ FROM v_topic_preview
+WHERE is_del = false AND subject_is_del=false AND topic_is_visible(status, publish_at);
+
+CREATE VIEW v_topic_with_md_and_tags_for_edit AS
+SELECT 
+	t.id,title,subject_id,slug,summary,author,src,c.md,COALESCE(tt.tag_names, ARRAY[]::VARCHAR[]) AS tag_names,t.status,t.publish_at,t.dateline
+FROM topic AS t
+INNER JOIN topic_content AS c ON c.topic_id=t.id
+LEFT JOIN (
+	SELECT 
+		tt.topic_id,
+		array_agg(t.name) AS tag_names
+	FROM topic_tag AS tt
+	INNER JOIN tag AS t ON t.id=tt.tag_id
+	WHERE tt.is_del=false AND t.is_del=false
+	GROUP BY tt.topic_id
+) AS tt on tt.topic_id=t.id;
+
+ALTER TABLE topic DROP COLUMN protect_count;
+ALTER TABLE topic DROP COLUMN protect_mode;
+ALTER TABLE subject DROP COLUMN protect_count;
+ALTER TABLE subject DROP COLUMN protect_mode;
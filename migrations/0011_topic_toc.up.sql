@@ -0,0 +1,26 @@
+-- 文章目录，由 Markdown 渲染时生成，与 html 一起保存
+ALTER TABLE topic_content ADD COLUMN toc TEXT NOT NULL DEFAULT '';
+
+CREATE OR REPLACE VIEW v_topic_preview AS
+SELECT 
+	t.id,title,subject_id,t.slug,author,src,c.html,COALESCE(tt.tag_names, ARRAY[]::VARCHAR[]) AS tag_names,s.slug AS subject_slug,dateline,hit,s.name AS subject_name,t.is_del,s.is_del AS subject_is_del,t.status,t.publish_at,
+    CASE WHEN t.protect_mode < 0 THEN s.protect_mode ELSE t.protect_mode END AS protect_mode,
+    CASE WHEN t.protect_mode < 0 THEN s.protect_count ELSE t.protect_count END AS protect_count,
+    c.toc
+FROM topic AS t
+INNER JOIN topic_content AS c ON c.topic_id=t.id
+INNER JOIN subject AS s ON t.subject_id=s.id
+LEFT JOIN (
+	SELECT 
+		tt.topic_id,
+		array_agg(t.name) AS tag_names
+	FROM topic_tag AS tt
+	INNER JOIN tag AS t ON t.id=tt.tag_id
+	WHERE tt.is_del=false AND t.is_del=false
+	GROUP BY tt.topic_id
+) AS tt on tt.topic_id=t.id;
+
+CREATE OR REPLACE VIEW v_topic_detail AS
+SELECT id,title,subject_id,slug,author,src,html,tag_names,subject_slug,dateline,hit,subject_name,protect_mode,protect_count,toc
+ FROM v_topic_preview
+WHERE is_del = false AND subject_is_del=false AND topic_is_visible(status, publish_at);
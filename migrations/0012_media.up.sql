@@ -0,0 +1,20 @@
+-- 上传的图片和附件，文件按内容的 SHA-256 命名，相同的文件只保存一份
+CREATE TABLE media (
+    id BIGSERIAL PRIMARY KEY,
+    admin_id INTEGER NOT NULL REFERENCES admin(id),
+    hash CHAR(64) NOT NULL,
+    storage VARCHAR(20) NOT NULL,
+    path VARCHAR(255) NOT NULL,
+    thumb_path VARCHAR(255) NOT NULL DEFAULT '',
+    url VARCHAR(255) NOT NULL,
+    thumb_url VARCHAR(255) NOT NULL DEFAULT '',
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size BIGINT NOT NULL,
+    width INTEGER NOT NULL DEFAULT 0,
+    height INTEGER NOT NULL DEFAULT 0,
+    is_image BOOLEAN NOT NULL DEFAULT FALSE,
+    dateline INTEGER NOT NULL DEFAULT 0,
+    is_del BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE(hash)
+);
@@ -0,0 +1,3 @@
+DROP INDEX idx_topic_admin_id;
+ALTER TABLE topic DROP COLUMN admin_id;
+ALTER TABLE admin DROP COLUMN role;
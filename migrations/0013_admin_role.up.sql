@@ -0,0 +1,10 @@
+-- 管理员角色：1 所有者、2 编辑、3 作者、4 审核员。已有的管理员都设为所有者
+ALTER TABLE admin ADD role SMALLINT NOT NULL DEFAULT 1;
+ALTER TABLE admin ALTER role SET DEFAULT 3;
+
+-- 文章的创建者，作者只能修改自己的文章。已有文章按最早的版本记录补充
+ALTER TABLE topic ADD admin_id INTEGER REFERENCES admin(id);
+UPDATE topic SET admin_id=(
+    SELECT r.admin_id FROM topic_revision AS r WHERE r.topic_id=topic.id ORDER BY r.id ASC LIMIT 1
+);
+CREATE INDEX idx_topic_admin_id ON topic (admin_id);
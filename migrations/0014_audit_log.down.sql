@@ -0,0 +1,2 @@
+DROP VIEW v_audit_log;
+DROP TABLE audit_log;
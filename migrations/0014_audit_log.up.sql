@@ -0,0 +1,23 @@
+-- 管理操作的审计日志，与被记录的操作在同一事务中写入
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    admin_id INTEGER NOT NULL REFERENCES admin(id),
+    action VARCHAR(20) NOT NULL,
+    target_type VARCHAR(20) NOT NULL,
+    target_id BIGINT NOT NULL,
+    target_name VARCHAR(255) NOT NULL DEFAULT '',
+    before_summary TEXT NOT NULL DEFAULT '',
+    after_summary TEXT NOT NULL DEFAULT '',
+    ip VARCHAR(45) NOT NULL DEFAULT '',
+    user_agent VARCHAR(255) NOT NULL DEFAULT '',
+    dateline INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_audit_log_admin_id ON audit_log (admin_id);
+CREATE INDEX idx_audit_log_target ON audit_log (target_type, target_id);
+
+CREATE VIEW v_audit_log AS
+SELECT l.id, l.admin_id, a.username AS admin_name, l.action, l.target_type, l.target_id, l.target_name,
+    l.before_summary, l.after_summary, l.ip, l.user_agent, l.dateline
+FROM audit_log AS l
+INNER JOIN admin AS a ON a.id=l.admin_id;
@@ -0,0 +1,11 @@
+DROP VIEW v_audit_log;
+
+DELETE FROM audit_log WHERE admin_id IS NULL;
+
+ALTER TABLE audit_log ALTER COLUMN admin_id SET NOT NULL;
+
+CREATE VIEW v_audit_log AS
+SELECT l.id, l.admin_id, a.username AS admin_name, l.action, l.target_type, l.target_id, l.target_name,
+    l.before_summary, l.after_summary, l.ip, l.user_agent, l.dateline
+FROM audit_log AS l
+INNER JOIN admin AS a ON a.id=l.admin_id;
@@ -0,0 +1,10 @@
+-- 登录失败等未登录时的操作也记录到审计日志，此时没有操作者
+DROP VIEW v_audit_log;
+
+ALTER TABLE audit_log ALTER COLUMN admin_id DROP NOT NULL;
+
+CREATE VIEW v_audit_log AS
+SELECT l.id, COALESCE(l.admin_id, 0) AS admin_id, COALESCE(a.username, '') AS admin_name, l.action, l.target_type, l.target_id, l.target_name,
+    l.before_summary, l.after_summary, l.ip, l.user_agent, l.dateline
+FROM audit_log AS l
+LEFT JOIN admin AS a ON a.id=l.admin_id;
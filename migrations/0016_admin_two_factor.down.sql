@@ -0,0 +1,5 @@
+DROP TABLE setting;
+DROP TABLE admin_recovery_code;
+ALTER TABLE admin DROP COLUMN totp_last_step;
+ALTER TABLE admin DROP COLUMN totp_enabled;
+ALTER TABLE admin DROP COLUMN totp_secret;
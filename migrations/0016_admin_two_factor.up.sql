@@ -0,0 +1,20 @@
+-- 管理员的两步验证。totp_secret 为 Base32 编码的密钥，totp_last_step 为最近一次通过验证的时间步，
+-- 同一个验证码不能重复使用
+ALTER TABLE admin ADD totp_secret VARCHAR(64) NOT NULL DEFAULT '';
+ALTER TABLE admin ADD totp_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE admin ADD totp_last_step BIGINT NOT NULL DEFAULT 0;
+
+-- 两步验证的恢复码，只保存哈希，使用一次后作废
+CREATE TABLE admin_recovery_code (
+    id SERIAL PRIMARY KEY,
+    admin_id INTEGER NOT NULL REFERENCES admin(id),
+    code_hash VARCHAR(64) NOT NULL,
+    used_at INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX idx_admin_recovery_code_admin_id ON admin_recovery_code (admin_id);
+
+-- 站点设置
+CREATE TABLE setting (
+    name VARCHAR(100) PRIMARY KEY,
+    value TEXT NOT NULL DEFAULT ''
+);
@@ -0,0 +1,31 @@
+DROP VIEW v_topic_search_source;
+
+DROP VIEW v_topic_search;
+CREATE VIEW v_topic_search AS
+SELECT v.id,v.title,v.slug,subject_slug,COALESCE(tag_names, ARRAY[]::VARCHAR[]) AS tag_names,subject_name,t.summary,c.md,c.search_vector
+ FROM v_topic_subject_list_with_tags AS v
+INNER JOIN topic AS t on t.id=v.id
+INNER JOIN topic_content AS c ON c.topic_id=v.id
+WHERE v.is_del=false AND v.subject_is_del=false AND topic_is_visible(t.status, t.publish_at);
+
+DROP TRIGGER trg_topic_content_search_vector ON topic_content;
+CREATE TRIGGER trg_topic_content_search_vector
+    BEFORE INSERT OR UPDATE OF md ON topic_content
+    FOR EACH ROW EXECUTE PROCEDURE topic_content_search_vector_update();
+
+CREATE OR REPLACE FUNCTION topic_content_search_vector_update() RETURNS TRIGGER AS $$
+BEGIN
+    SELECT
+        setweight(to_tsvector('simple', t.title), 'A') ||
+        setweight(to_tsvector('simple', t.summary), 'B') ||
+        setweight(to_tsvector('simple', NEW.md), 'C')
+    INTO NEW.search_vector
+    FROM topic AS t
+    WHERE t.id = NEW.topic_id;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+UPDATE topic_content SET md = md;
+
+ALTER TABLE topic_content DROP COLUMN search_text;
@@ -0,0 +1,43 @@
+-- 全文搜索排除隐藏的内容，否则可以通过搜索结果的摘要看到
+-- 用于搜索的纯文本，由程序按照文章实际生效的隐藏策略生成，已隐藏的内容不包含在内。
+-- 为 NULL 时表示尚未生成，程序启动时补齐
+ALTER TABLE topic_content ADD COLUMN search_text TEXT;
+
+CREATE OR REPLACE FUNCTION topic_content_search_vector_update() RETURNS TRIGGER AS $$
+BEGIN
+    SELECT
+        setweight(to_tsvector('simple', t.title), 'A') ||
+        setweight(to_tsvector('simple', t.summary), 'B') ||
+        setweight(to_tsvector('simple', COALESCE(NEW.search_text, '')), 'C')
+    INTO NEW.search_vector
+    FROM topic AS t
+    WHERE t.id = NEW.topic_id;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER trg_topic_content_search_vector ON topic_content;
+CREATE TRIGGER trg_topic_content_search_vector
+    BEFORE INSERT OR UPDATE OF md, search_text ON topic_content
+    FOR EACH ROW EXECUTE PROCEDURE topic_content_search_vector_update();
+
+-- 生成纯文本之前，只索引标题和摘要
+UPDATE topic_content SET md = md;
+
+DROP VIEW v_topic_search;
+CREATE VIEW v_topic_search AS
+SELECT v.id,v.title,v.slug,subject_slug,COALESCE(tag_names, ARRAY[]::VARCHAR[]) AS tag_names,subject_name,t.summary,COALESCE(c.search_text, '') AS search_text,c.search_vector
+ FROM v_topic_subject_list_with_tags AS v
+INNER JOIN topic AS t on t.id=v.id
+INNER JOIN topic_content AS c ON c.topic_id=v.id
+WHERE v.is_del=false AND v.subject_is_del=false AND topic_is_visible(t.status, t.publish_at);
+
+-- 生成纯文本所需的 HTML 及实际生效的隐藏策略
+CREATE VIEW v_topic_search_source AS
+SELECT c.topic_id, c.html,
+    CASE WHEN t.protect_mode < 0 THEN s.protect_mode ELSE t.protect_mode END AS protect_mode,
+    CASE WHEN t.protect_mode < 0 THEN s.protect_count ELSE t.protect_count END AS protect_count,
+    t.subject_id, c.search_text IS NULL AS is_pending
+ FROM topic_content AS c
+INNER JOIN topic AS t ON t.id=c.topic_id
+INNER JOIN subject AS s ON s.id=t.subject_id;
@@ -0,0 +1,3 @@
+DROP INDEX idx_topic_content_search_text_trgm;
+DROP INDEX idx_topic_summary_trgm;
+DROP INDEX idx_topic_title_trgm;
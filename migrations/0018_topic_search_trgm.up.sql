@@ -0,0 +1,7 @@
+-- 'simple' 配置不会切分中文，连续的中文会成为一个词，搜索其中的词语时无法命中。
+-- 使用三元组索引，按子串匹配标题、摘要和正文
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_topic_title_trgm ON topic USING GIN (title gin_trgm_ops);
+CREATE INDEX idx_topic_summary_trgm ON topic USING GIN (summary gin_trgm_ops);
+CREATE INDEX idx_topic_content_search_text_trgm ON topic_content USING GIN ((COALESCE(search_text, '')) gin_trgm_ops);
@@ -0,0 +1,215 @@
+//! 打包和解包 zip、tar 格式的文章归档
+
+use std::io::{Cursor, Read, Write};
+
+use crate::{
+    error::{AppError, AppErrorType},
+    Result,
+};
+
+/// 归档格式
+#[derive(Clone, Copy)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+}
+
+impl ArchiveFormat {
+    /// 根据名称获取，不支持的格式返回`None`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "zip" => Some(ArchiveFormat::Zip),
+            "tar" => Some(ArchiveFormat::Tar),
+            _ => None,
+        }
+    }
+    /// 根据文件名获取
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let file_name = file_name.to_lowercase();
+        if file_name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if file_name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::Tar => "tar",
+        }
+    }
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::Tar => "application/x-tar",
+        }
+    }
+}
+
+/// 将文件打包。`files`为文件名和内容
+pub fn pack(format: ArchiveFormat, files: &[(String, String)]) -> Result<Vec<u8>> {
+    match format {
+        ArchiveFormat::Zip => {
+            let mut w = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for (name, content) in files {
+                w.start_file(name, options).map_err(error)?;
+                w.write_all(content.as_bytes()).map_err(error)?;
+            }
+            Ok(w.finish().map_err(error)?.into_inner())
+        }
+        ArchiveFormat::Tar => {
+            let mut b = tar::Builder::new(Vec::new());
+            for (name, content) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(crate::time::now() as u64);
+                header.set_cksum();
+                b.append_data(&mut header, name, content.as_bytes())
+                    .map_err(error)?;
+            }
+            b.into_inner().map_err(error)
+        }
+    }
+}
+
+/// 单个文件解压后的最大字节数
+pub const MAX_ENTRY_SIZE: u64 = 4 * 1024 * 1024;
+/// 所有文件解压后的最大字节数，防止压缩炸弹
+pub const MAX_TOTAL_SIZE: u64 = 32 * 1024 * 1024;
+
+/// 解包，只返回`.md`文件的文件名和内容
+pub fn unpack(format: ArchiveFormat, data: &[u8]) -> Result<Vec<(String, String)>> {
+    unpack_with_limit(format, data, MAX_ENTRY_SIZE, MAX_TOTAL_SIZE)
+}
+
+/// 解包，单个文件不能超过`entry_limit`字节，所有文件合计不能超过`total_limit`字节
+fn unpack_with_limit(
+    format: ArchiveFormat,
+    data: &[u8],
+    entry_limit: u64,
+    total_limit: u64,
+) -> Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    let mut budget = total_limit;
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(error)?;
+            for i in 0..archive.len() {
+                let file = archive.by_index(i).map_err(error)?;
+                if !file.is_file() || !is_markdown(file.name()) {
+                    continue;
+                }
+                let name = file.name().to_string();
+                let content = read_limited(file, &name, entry_limit, &mut budget)?;
+                files.push((name, content));
+            }
+        }
+        ArchiveFormat::Tar => {
+            let mut archive = tar::Archive::new(data);
+            for entry in archive.entries().map_err(error)? {
+                let entry = entry.map_err(error)?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let name = entry.path().map_err(error)?.to_string_lossy().to_string();
+                if !is_markdown(&name) {
+                    continue;
+                }
+                let content = read_limited(entry, &name, entry_limit, &mut budget)?;
+                files.push((name, content));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// 读取文件内容。不信任归档中记录的大小，最多只读取限制加一个字节来判断是否超出
+fn read_limited(
+    reader: impl Read,
+    name: &str,
+    entry_limit: u64,
+    budget: &mut u64,
+) -> Result<String> {
+    let limit = entry_limit.min(*budget);
+    let mut buf = Vec::new();
+    reader
+        .take(limit + 1)
+        .read_to_end(&mut buf)
+        .map_err(error)?;
+    let len = buf.len() as u64;
+    if len > limit {
+        let msg = if limit == entry_limit {
+            format!("{} 超过了 {} 字节的大小限制", name, entry_limit)
+        } else {
+            "归档中的文件解压后超过了总大小限制".to_string()
+        };
+        return Err(AppError::from_str(&msg, AppErrorType::Common));
+    }
+    *budget -= len;
+    String::from_utf8(buf).map_err(|_| {
+        AppError::from_str(
+            &format!("{} 不是 UTF-8 编码的文本文件", name),
+            AppErrorType::Common,
+        )
+    })
+}
+
+fn is_markdown(name: &str) -> bool {
+    // 忽略 macOS 生成的资源文件
+    name.to_lowercase().ends_with(".md") && !name.starts_with("__MACOSX/")
+}
+
+fn error(err: impl ToString) -> AppError {
+    AppError::from_err(err, AppErrorType::Common)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(sizes: &[usize]) -> Vec<(String, String)> {
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| (format!("{}.md", i), "a".repeat(size)))
+            .collect()
+    }
+
+    #[test]
+    fn unpack_within_limits() {
+        for format in [ArchiveFormat::Zip, ArchiveFormat::Tar] {
+            let data = pack(format, &files(&[10, 20])).unwrap();
+            let unpacked = unpack_with_limit(format, &data, 20, 30).unwrap();
+            assert_eq!(unpacked, files(&[10, 20]));
+        }
+    }
+
+    #[test]
+    fn unpack_rejects_large_entry() {
+        for format in [ArchiveFormat::Zip, ArchiveFormat::Tar] {
+            let data = pack(format, &files(&[10, 21])).unwrap();
+            assert!(unpack_with_limit(format, &data, 20, 100).is_err());
+        }
+    }
+
+    #[test]
+    fn unpack_rejects_large_total() {
+        for format in [ArchiveFormat::Zip, ArchiveFormat::Tar] {
+            let data = pack(format, &files(&[20, 20, 20])).unwrap();
+            assert!(unpack_with_limit(format, &data, 20, 50).is_err());
+        }
+    }
+
+    #[test]
+    fn unpack_zip_bomb() {
+        // 高度压缩的大文件，压缩后只有几 KB
+        let data = pack(ArchiveFormat::Zip, &files(&[8 * 1024 * 1024])).unwrap();
+        assert!(data.len() < 64 * 1024);
+        assert!(unpack(ArchiveFormat::Zip, &data).is_err());
+    }
+}
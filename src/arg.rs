@@ -1,5 +1,10 @@
 use serde::Deserialize;
 
+use crate::{
+    audit::{Action, Target},
+    model::CommentStatus,
+};
+
 #[derive(Deserialize, Debug)]
 pub struct SubjectBackendQueryArg {
     pub page: Option<u32>,
@@ -55,6 +60,32 @@ impl TagBackendQueryArg {
         }
     }
 }
+#[derive(Deserialize)]
+pub struct CommentBackendQueryArg {
+    pub page: Option<u32>,
+    pub keyword: Option<String>,
+    pub msg: Option<String>,
+    pub is_del: Option<bool>,
+    pub status: Option<CommentStatus>,
+}
+impl CommentBackendQueryArg {
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(0)
+    }
+    pub fn keyword(&self) -> &str {
+        match &self.keyword {
+            Some(s) => s,
+            None => "",
+        }
+    }
+    pub fn is_del(&self) -> bool {
+        self.is_del.unwrap_or(false)
+    }
+    /// 缺省显示待审核的评论
+    pub fn status(&self) -> CommentStatus {
+        self.status.unwrap_or_default()
+    }
+}
 #[derive(Deserialize, Debug)]
 pub struct BackendQueryArg {
     pub page: Option<u32>,
@@ -82,3 +113,61 @@ impl BackendQueryArg {
         }
     }
 }
+
+/// 比较文章的两个版本
+#[derive(Deserialize, Debug)]
+pub struct TopicRevisionDiffArg {
+    pub from: i64,
+    pub to: i64,
+}
+
+/// 审计日志的筛选条件，为空时不筛选
+#[derive(Deserialize, Debug, Default)]
+pub struct AuditLogArg {
+    pub page: Option<u32>,
+    pub msg: Option<String>,
+    pub admin_id: Option<i32>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub target_id: Option<i64>,
+    pub keyword: Option<String>,
+}
+impl AuditLogArg {
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(0)
+    }
+    pub fn admin_id(&self) -> i32 {
+        self.admin_id.unwrap_or(0)
+    }
+    /// 无效的值视为不筛选
+    pub fn action(&self) -> Option<Action> {
+        self.action.as_deref().and_then(Action::parse)
+    }
+    pub fn target(&self) -> Option<Target> {
+        self.target.as_deref().and_then(Target::parse)
+    }
+    /// 用于模板中标记选中的选项
+    pub fn is_action(&self, action: &Action) -> bool {
+        self.action() == Some(*action)
+    }
+    pub fn is_target(&self, target: &Target) -> bool {
+        self.target() == Some(*target)
+    }
+    pub fn target_id(&self) -> i64 {
+        self.target_id.unwrap_or(0)
+    }
+    pub fn keyword(&self) -> &str {
+        self.keyword.as_deref().unwrap_or("")
+    }
+    /// 除页码外的筛选条件，用于分页和导出的链接
+    pub fn query_string(&self) -> String {
+        format!(
+            "admin_id={}&action={}&target={}&target_id={}&keyword={}",
+            self.admin_id(),
+            self.action().map(|a| a.as_str()).unwrap_or(""),
+            self.target().map(|t| t.as_str()).unwrap_or(""),
+            self.target_id(),
+            self.keyword()
+        )
+    }
+}
@@ -0,0 +1,276 @@
+//! 管理操作的审计日志
+//!
+//! 日志与被记录的操作在同一事务中写入，操作失败回滚时日志也不会留下。
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::http::{header::USER_AGENT, HeaderMap};
+
+/// 操作者及请求来源
+#[derive(Debug, Clone)]
+pub struct Audit {
+    /// 为 0 时表示未登录，如登录失败
+    pub admin_id: i32,
+    pub ip: String,
+    pub user_agent: String,
+}
+impl Audit {
+    pub fn new(
+        admin_id: i32,
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+        trusted: &TrustedProxies,
+    ) -> Self {
+        let user_agent = headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .chars()
+            .take(255)
+            .collect();
+        Self {
+            admin_id,
+            ip: client_ip(headers, peer, trusted)
+                .map(|ip| ip.to_string())
+                .unwrap_or_default(),
+            user_agent,
+        }
+    }
+    /// 未登录的请求
+    pub fn anonymous(
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+        trusted: &TrustedProxies,
+    ) -> Self {
+        Self::new(0, headers, peer, trusted)
+    }
+}
+
+/// 受信任的反向代理，由配置中逗号分隔的地址或网段解析而来
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<(IpAddr, u8)>);
+impl TrustedProxies {
+    /// 解析`127.0.0.1,10.0.0.0/8`格式的配置，忽略无法解析的项
+    pub fn parse(s: &str) -> Self {
+        let items = s
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .filter_map(|item| {
+                let (addr, prefix) = item.split_once('/').unwrap_or((item, ""));
+                let addr: IpAddr = addr.parse().ok()?;
+                let max = if addr.is_ipv4() { 32 } else { 128 };
+                let prefix = if prefix.is_empty() {
+                    max
+                } else {
+                    prefix.parse().ok().filter(|p| *p <= max)?
+                };
+                Some((addr, prefix))
+            })
+            .collect();
+        Self(items)
+    }
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|(net, prefix)| match (net, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - *prefix as u32).unwrap_or(0);
+                u32::from(*net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - *prefix as u32).unwrap_or(0);
+                u128::from(*net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        })
+    }
+}
+
+/// 客户端的 IP。只有来自受信任的反向代理时才使用`X-Forwarded-For`或`X-Real-IP`中的地址
+pub fn client_ip(
+    headers: &HeaderMap,
+    peer: Option<SocketAddr>,
+    trusted: &TrustedProxies,
+) -> Option<IpAddr> {
+    let peer = peer.map(|addr| addr.ip());
+    if !peer.map(|ip| trusted.contains(ip)).unwrap_or(false) {
+        return peer;
+    }
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    // 每层代理都把它看到的地址追加在末尾，从后往前跳过受信任的代理，第一个不受信任的才是客户端。
+    // 更前面的地址由客户端自己填写，不可信
+    if let Some(forwarded) = header("x-forwarded-for") {
+        let mut last = None;
+        for ip in forwarded
+            .rsplit(',')
+            .map(|value| value.trim().parse::<IpAddr>())
+        {
+            match ip {
+                Ok(ip) if trusted.contains(ip) => last = Some(ip),
+                Ok(ip) => return Some(ip),
+                Err(_) => break,
+            }
+        }
+        if last.is_some() {
+            return last;
+        }
+    }
+    header("x-real-ip")
+        .and_then(|value| value.trim().parse().ok())
+        .or(peer)
+}
+
+/// 操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    /// 修改管理员的角色
+    SetRole,
+    /// 修改管理员的密码
+    ChangePassword,
+    /// 登录失败，记录尝试的用户名
+    LoginFailed,
+    /// 解除登录锁定
+    Unlock,
+    EnableTwoFactor,
+    DisableTwoFactor,
+    /// 重新渲染所有文章，对象 ID 为 0
+    Rerender,
+}
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Create,
+        Action::Update,
+        Action::Delete,
+        Action::Restore,
+        Action::SetRole,
+        Action::ChangePassword,
+        Action::LoginFailed,
+        Action::Unlock,
+        Action::EnableTwoFactor,
+        Action::DisableTwoFactor,
+        Action::Rerender,
+    ];
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Create => "create",
+            Action::Update => "update",
+            Action::Delete => "delete",
+            Action::Restore => "restore",
+            Action::SetRole => "set_role",
+            Action::ChangePassword => "change_password",
+            Action::LoginFailed => "login_failed",
+            Action::Unlock => "unlock",
+            Action::EnableTwoFactor => "enable_2fa",
+            Action::DisableTwoFactor => "disable_2fa",
+            Action::Rerender => "rerender",
+        }
+    }
+    pub fn parse(s: &str) -> Option<Self> {
+        Action::ALL.into_iter().find(|action| action.as_str() == s)
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Create => "添加",
+            Action::Update => "修改",
+            Action::Delete => "删除",
+            Action::Restore => "恢复",
+            Action::SetRole => "修改角色",
+            Action::ChangePassword => "修改密码",
+            Action::LoginFailed => "登录失败",
+            Action::Unlock => "解除锁定",
+            Action::EnableTwoFactor => "启用两步验证",
+            Action::DisableTwoFactor => "关闭两步验证",
+            Action::Rerender => "重新渲染",
+        }
+    }
+}
+
+/// 操作对象的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Subject,
+    Tag,
+    Topic,
+    Admin,
+    /// 站点设置，对象 ID 为 0，名称为设置项
+    Setting,
+}
+impl Target {
+    pub const ALL: [Target; 5] = [
+        Target::Subject,
+        Target::Tag,
+        Target::Topic,
+        Target::Admin,
+        Target::Setting,
+    ];
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Target::Subject => "subject",
+            Target::Tag => "tag",
+            Target::Topic => "topic",
+            Target::Admin => "admin",
+            Target::Setting => "setting",
+        }
+    }
+    pub fn parse(s: &str) -> Option<Self> {
+        Target::ALL.into_iter().find(|target| target.as_str() == s)
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Target::Subject => "专题",
+            Target::Tag => "标签",
+            Target::Topic => "文章",
+            Target::Admin => "账号",
+            Target::Setting => "设置",
+        }
+    }
+}
+
+/// 一条日志。`before`和`after`是操作前后的摘要，通常为只包含关键字段的 JSON
+pub struct Entry {
+    pub action: Action,
+    pub target: Target,
+    pub target_id: i64,
+    pub target_name: String,
+    pub before: String,
+    pub after: String,
+}
+impl Entry {
+    pub fn new(action: Action, target: Target, target_id: i64, target_name: &str) -> Self {
+        Self {
+            action,
+            target,
+            target_id,
+            target_name: target_name.chars().take(255).collect(),
+            before: String::new(),
+            after: String::new(),
+        }
+    }
+    pub fn before(self, before: impl ToString) -> Self {
+        Self {
+            before: before.to_string(),
+            ..self
+        }
+    }
+    pub fn after(self, after: impl ToString) -> Self {
+        Self {
+            after: after.to_string(),
+            ..self
+        }
+    }
+    /// 删除和恢复的摘要
+    pub fn is_del(self, is_del: bool) -> Self {
+        self.before(serde_json::json!({ "is_del": !is_del }))
+            .after(serde_json::json!({ "is_del": is_del }))
+    }
+}
@@ -1,32 +1,366 @@
+//! 前台数据的缓存
+//!
+//! 数据序列化为 JSON 后保存在 redis 中，每个键有各自的有效期和标签：
+//!
+//! - 同一进程内多个请求同时未命中同一个键时，只有一个请求查询数据库，其余的等待其结果
+//! - 内容修改后按标签清除相关的键，不必等到过期
+//! - 按缓存的名称统计命中和未命中的次数，显示在后台首页
+//!
+//! redis 出错时直接查询数据库，不影响访问。
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
 use redis::Client;
+use serde::{de::DeserializeOwned, Serialize};
 
-use crate::rdb;
+use crate::{db::pagination::Pagination, model::TopicPlacement, rdb, Result};
 
 const PREFIX: &str = "axum_rs:cache:";
-const EXPIRED: usize = 10;
+/// 列表的有效期（秒）
+const LIST_TTL: usize = 300;
+/// 文章详情的有效期（秒）
+const DETAIL_TTL: usize = 600;
+/// 订阅源的有效期（秒）
+const FEED_TTL: usize = 600;
+/// 站点地图的有效期（秒）
+const SITEMAP_TTL: usize = 3600;
+/// 标签下的键的集合的有效期（秒），不能短于任何键的有效期
+const TAG_TTL: usize = 86400;
 
-pub fn gen_name(key: &str) -> String {
-    format!("{}{}", PREFIX, key)
+/// 显示了专题信息的缓存，修改专题后清除
+pub const TAG_SUBJECTS: &str = "subjects";
+/// 显示了标签信息的缓存，修改标签后清除
+pub const TAG_TAGS: &str = "tags";
+/// 包含全站文章的缓存，任何文章修改后都清除
+const TAG_TOPICS: &str = "topics";
+
+/// 包含专题下文章的缓存
+fn of_subject(slug: &str) -> String {
+    format!("subject:{}", slug)
+}
+/// 包含标签下文章的缓存
+fn of_tag(name: &str) -> String {
+    format!("tag:{}", name)
+}
+fn tag_key(tag: &str) -> String {
+    format!("{}tag:{}", PREFIX, tag)
 }
 
-pub async fn write(client: &Client, key: &str, value: &str) {
-    let key = gen_name(key);
-    match rdb::set(&client, &key, value, EXPIRED).await {
-        Err(err) => {
-            tracing::error!("{:?}", err);
+/// 缓存的键，包括有效期和标签
+pub struct Key {
+    /// 缓存的名称，用于统计
+    name: &'static str,
+    key: String,
+    ttl: usize,
+    tags: Vec<String>,
+}
+impl Key {
+    fn new(name: &'static str, id: &str, ttl: usize, tags: Vec<String>) -> Self {
+        Self {
+            name,
+            key: format!("{}{}:{}", PREFIX, name, id),
+            ttl,
+            tags,
         }
-        _ => {}
-    };
+    }
+    /// 专题列表
+    pub fn subject_index(page: u32) -> Self {
+        Self::new(
+            "subject_index",
+            &page.to_string(),
+            LIST_TTL,
+            vec![TAG_SUBJECTS.to_string()],
+        )
+    }
+    /// 专题下的文章列表
+    pub fn subject_topics(slug: &str, page: u32) -> Self {
+        Self::new(
+            "subject_topics",
+            &format!("{}:{}", slug, page),
+            LIST_TTL,
+            vec![
+                TAG_SUBJECTS.to_string(),
+                TAG_TAGS.to_string(),
+                of_subject(slug),
+            ],
+        )
+    }
+    /// 专题的订阅源
+    pub fn subject_feed(format: &str, slug: &str) -> Self {
+        Self::new(
+            "subject_feed",
+            &format!("{}:{}", format, slug),
+            FEED_TTL,
+            vec![
+                TAG_SUBJECTS.to_string(),
+                TAG_TAGS.to_string(),
+                of_subject(slug),
+            ],
+        )
+    }
+    /// 标签列表
+    pub fn tag_index() -> Self {
+        Self::new("tag_index", "all", LIST_TTL, vec![TAG_TAGS.to_string()])
+    }
+    /// 标签下的文章列表
+    pub fn tag_topics(name: &str, page: u32) -> Self {
+        Self::new(
+            "tag_topics",
+            &format!("{}:{}", name, page),
+            LIST_TTL,
+            vec![TAG_SUBJECTS.to_string(), TAG_TAGS.to_string(), of_tag(name)],
+        )
+    }
+    /// 标签的订阅源
+    pub fn tag_feed(format: &str, name: &str) -> Self {
+        Self::new(
+            "tag_feed",
+            &format!("{}:{}", format, name),
+            FEED_TTL,
+            vec![TAG_SUBJECTS.to_string(), TAG_TAGS.to_string(), of_tag(name)],
+        )
+    }
+    /// 文章详情。文章修改后直接删除
+    pub fn topic_detail(subject_slug: &str, slug: &str) -> Self {
+        Self::new(
+            "topic_detail",
+            &format!("{}:{}", subject_slug, slug),
+            DETAIL_TTL,
+            vec![TAG_SUBJECTS.to_string(), TAG_TAGS.to_string()],
+        )
+    }
+    /// 全站订阅源
+    pub fn site_feed(format: &str) -> Self {
+        Self::new(
+            "site_feed",
+            format,
+            FEED_TTL,
+            vec![
+                TAG_SUBJECTS.to_string(),
+                TAG_TAGS.to_string(),
+                TAG_TOPICS.to_string(),
+            ],
+        )
+    }
+    /// 站点地图，`name`为`index`或子站点地图的文件名
+    pub fn sitemap(name: &str) -> Self {
+        Self::new(
+            "sitemap",
+            name,
+            SITEMAP_TTL,
+            vec![
+                TAG_SUBJECTS.to_string(),
+                TAG_TAGS.to_string(),
+                TAG_TOPICS.to_string(),
+            ],
+        )
+    }
 }
 
-pub async fn read(client: &Client, key: &str) -> Option<String> {
-    let key = gen_name(key);
-    let value = rdb::get(&client, &key).await;
-    match value {
-        Ok(value) => value,
-        Err(err) => {
-            tracing::error!("{:?}", err);
-            None
+/// 分页的缓存值，用于限制页码
+pub trait Paged {
+    fn total_pages(&self) -> i64;
+}
+impl<T> Paged for Pagination<T> {
+    fn total_pages(&self) -> i64 {
+        self.total_pages
+    }
+}
+/// 带有所属专题或标签的分页
+impl<S, T> Paged for (S, Pagination<T>) {
+    fn total_pages(&self) -> i64 {
+        self.1.total_pages
+    }
+}
+
+/// 命中和未命中的次数
+#[derive(Default, Clone, Copy)]
+pub struct Stat {
+    pub hits: u64,
+    pub misses: u64,
+}
+impl Stat {
+    /// 命中率，如`87.5%`
+    pub fn hit_rate(&self) -> String {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return "-".to_string();
+        }
+        format!("{:.1}%", self.hits as f64 * 100.0 / total as f64)
+    }
+}
+
+pub struct Cache {
+    client: Client,
+    /// 正在查询数据库的键，同一个键的请求依次获取锁
+    inflight: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    stats: Mutex<BTreeMap<&'static str, Stat>>,
+}
+impl Cache {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            inflight: Mutex::new(HashMap::new()),
+            stats: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// 读取缓存，未命中时调用`compute`查询并写入缓存。`compute`出错时不写入，
+    /// 因此不存在的专题、标签和文章不会产生键
+    pub async fn get_or_compute<T, F, Fut>(&self, key: &Key, compute: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        match self.read(key).await {
+            Ok(Some(value)) => {
+                self.record(key.name, true);
+                return Ok(value);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                tracing::error!("读取缓存失败：{:?}", err);
+                self.record(key.name, false);
+                return compute().await;
+            }
+        }
+        let lock = self.lock_of(&key.key);
+        let guard = lock.lock().await;
+        // 等待期间其它请求可能已经写入
+        let result = match self.read(key).await {
+            Ok(Some(value)) => {
+                self.record(key.name, true);
+                Ok(value)
+            }
+            _ => {
+                self.record(key.name, false);
+                match compute().await {
+                    Ok(value) => {
+                        self.write(key, &value).await;
+                        Ok(value)
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        };
+        drop(guard);
+        self.release(&key.key, lock);
+        result
+    }
+
+    /// 读取分页列表的缓存，返回实际的页码和列表。
+    /// 先读取第一页，确认列表存在并得到总页数；超出范围的页码改为最后一页，以免任意的页码产生大量的键
+    pub async fn get_page<T, K, F, Fut>(&self, key: K, page: u32, compute: F) -> Result<(u32, T)>
+    where
+        T: Serialize + DeserializeOwned + Paged,
+        K: Fn(u32) -> Key,
+        F: Fn(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let first = self.get_or_compute(&key(0), || compute(0)).await?;
+        let last_page = (first.total_pages() - 1).max(0) as u32;
+        let page = page.min(last_page);
+        if page == 0 {
+            return Ok((0, first));
+        }
+        let value = self.get_or_compute(&key(page), || compute(page)).await?;
+        Ok((page, value))
+    }
+
+    async fn read<T: DeserializeOwned>(&self, key: &Key) -> Result<Option<T>> {
+        let data = rdb::get(&self.client, &key.key).await?;
+        // 数据结构改变后无法解析的，视为未命中
+        Ok(data.and_then(|data| serde_json::from_str(&data).ok()))
+    }
+
+    async fn write<T: Serialize>(&self, key: &Key, value: &T) {
+        let data = match serde_json::to_string(value) {
+            Ok(data) => data,
+            Err(err) => {
+                tracing::error!("序列化缓存失败：{:?}", err);
+                return;
+            }
+        };
+        let tag_keys: Vec<String> = key.tags.iter().map(|tag| tag_key(tag)).collect();
+        if let Err(err) =
+            rdb::set_tagged(&self.client, &key.key, &data, key.ttl, &tag_keys, TAG_TTL).await
+        {
+            tracing::error!("写入缓存失败：{:?}", err);
+        }
+    }
+
+    fn lock_of(&self, key: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut inflight = self.inflight.lock().unwrap();
+        inflight.entry(key.to_string()).or_default().clone()
+    }
+
+    /// 没有其它请求等待时，移除键的锁
+    fn release(&self, key: &str, lock: Arc<tokio::sync::Mutex<()>>) {
+        let mut inflight = self.inflight.lock().unwrap();
+        // 一个引用在`inflight`中，另一个是`lock`
+        if Arc::strong_count(&lock) <= 2 {
+            inflight.remove(key);
+        }
+    }
+
+    fn record(&self, name: &'static str, hit: bool) {
+        let mut stats = self.stats.lock().unwrap();
+        let stat = stats.entry(name).or_default();
+        if hit {
+            stat.hits += 1;
+        } else {
+            stat.misses += 1;
+        }
+    }
+
+    /// 各缓存自启动以来的命中情况，按名称排序
+    pub fn stats(&self) -> Vec<(&'static str, Stat)> {
+        let stats = self.stats.lock().unwrap();
+        stats.iter().map(|(name, stat)| (*name, *stat)).collect()
+    }
+
+    /// 清除带有任一标签的缓存
+    pub async fn purge_tags(&self, tags: &[&str]) {
+        if let Err(err) = self.try_purge(tags, Vec::new()).await {
+            tracing::error!("清除缓存失败：{:?}", err);
+        }
+    }
+
+    /// 清除所有缓存，所有的键都带有专题或标签的标签
+    pub async fn purge_all(&self) {
+        self.purge_tags(&[TAG_SUBJECTS, TAG_TAGS]).await;
+    }
+
+    /// 文章变化后清除相关的缓存：文章详情、所属专题和标签的文章列表及订阅源、包含全站文章的缓存。
+    /// 修改可能改变文章的固定链接、专题和标签，需要同时传入修改前后的
+    pub async fn purge_topic(&self, placements: &[TopicPlacement]) {
+        let mut tags = vec![TAG_TOPICS.to_string()];
+        // 保存文章时可能创建了新的标签
+        let mut keys = vec![Key::tag_index().key];
+        for placement in placements {
+            keys.push(Key::topic_detail(&placement.subject_slug, &placement.slug).key);
+            tags.push(of_subject(&placement.subject_slug));
+            tags.extend(placement.tag_names.iter().map(|name| of_tag(name)));
+        }
+        let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+        if let Err(err) = self.try_purge(&tags, keys).await {
+            tracing::error!("清除缓存失败：{:?}", err);
+        }
+    }
+
+    async fn try_purge(&self, tags: &[&str], mut keys: Vec<String>) -> Result<()> {
+        for tag in tags {
+            let tag_key = tag_key(tag);
+            keys.append(&mut rdb::smembers(&self.client, &tag_key).await?);
+            keys.push(tag_key);
         }
+        keys.sort();
+        keys.dedup();
+        rdb::del_all(&self.client, &keys).await
     }
 }
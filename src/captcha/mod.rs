@@ -0,0 +1,267 @@
+//! 人机验证
+//!
+//! 通过[`CaptchaProvider`]统一 hCaptcha、reCAPTCHA、Turnstile 等服务商，
+//! 具体使用哪个服务商由配置决定。
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::CaptchaConfig,
+    error::{AppError, AppErrorType},
+    Result,
+};
+
+mod hcaptcha;
+mod noop;
+mod recaptcha;
+mod turnstile;
+
+pub use self::hcaptcha::HCaptcha;
+pub use self::noop::Noop;
+pub use self::recaptcha::ReCaptcha;
+pub use self::turnstile::Turnstile;
+
+/// 人机验证服务商
+#[async_trait]
+pub trait CaptchaProvider: Send + Sync {
+    /// 服务商名称，前端据此选择调用方式
+    fn name(&self) -> &'static str;
+    /// 前端使用的 site key
+    fn site_key(&self) -> &str;
+    /// 前端需要载入的脚本，加载完成后回调`axumCaptchaOnload`
+    fn script_url(&self) -> Option<String>;
+    /// 校验前端提交的令牌
+    async fn verify(&self, response: &str) -> Result<bool>;
+
+    /// 渲染前端组件所需的数据
+    fn widget(&self) -> Widget {
+        Widget {
+            name: self.name(),
+            site_key: self.site_key().to_string(),
+            script_url: self.script_url(),
+        }
+    }
+}
+
+/// 前端组件所需的数据，见`templates/captcha.html`
+pub struct Widget {
+    pub name: &'static str,
+    pub site_key: String,
+    pub script_url: Option<String>,
+}
+
+/// 根据配置创建服务商
+pub fn from_config(cfg: &CaptchaConfig) -> Result<Arc<dyn CaptchaProvider>> {
+    let verify_url = |default: &str| {
+        if cfg.verify_url.is_empty() {
+            default.to_string()
+        } else {
+            cfg.verify_url.clone()
+        }
+    };
+    let site_key = cfg.site_key.clone();
+    let secret_key = cfg.secret_key.clone();
+    // 校验接口没有响应时不能一直等待
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(cfg.timeout))
+        .build()
+        .map_err(|err| AppError::from_err(err, AppErrorType::HttpError))?;
+    let provider: Arc<dyn CaptchaProvider> = match cfg.provider.as_str() {
+        "hcaptcha" => Arc::new(HCaptcha::new(
+            site_key,
+            secret_key,
+            verify_url(hcaptcha::VERIFY_URL),
+            client,
+        )),
+        "recaptcha" => Arc::new(ReCaptcha::v2(
+            site_key,
+            secret_key,
+            verify_url(recaptcha::VERIFY_URL),
+            client,
+        )),
+        "recaptcha_v3" => Arc::new(ReCaptcha::v3(
+            site_key,
+            secret_key,
+            verify_url(recaptcha::VERIFY_URL),
+            client,
+            cfg.min_score,
+        )),
+        "turnstile" => Arc::new(Turnstile::new(
+            site_key,
+            secret_key,
+            verify_url(turnstile::VERIFY_URL),
+            client,
+        )),
+        "noop" => {
+            tracing::warn!("人机验证已关闭，所有请求都将通过验证");
+            Arc::new(Noop)
+        }
+        name => {
+            return Err(AppError::from_str(
+                &format!("不支持的人机验证服务商：{}", name),
+                AppErrorType::Common,
+            ))
+        }
+    };
+    Ok(provider)
+}
+
+#[derive(Serialize)]
+struct VerifyRequest<'a> {
+    secret: &'a str,
+    response: &'a str,
+}
+
+/// 各服务商 siteverify 接口的响应
+#[derive(Deserialize)]
+struct VerifyResponse {
+    success: bool,
+    /// reCAPTCHA v3 的分数
+    #[serde(default)]
+    score: Option<f32>,
+}
+
+/// 调用 siteverify 接口。hCaptcha、reCAPTCHA 和 Turnstile 的接口格式相同
+async fn site_verify(
+    client: &reqwest::Client,
+    verify_url: &str,
+    secret: &str,
+    response: &str,
+) -> Result<VerifyResponse> {
+    let req = VerifyRequest { secret, response };
+    let res = client
+        .post(verify_url)
+        .form(&req)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|err| {
+            tracing::error!(" POST {:?}", err);
+            AppError::from_err(err, AppErrorType::HttpError)
+        })?;
+    let res = res.text().await.map_err(|err| {
+        tracing::error!("TEXT {:?}", err);
+        AppError::from_err(err, AppErrorType::HttpError)
+    })?;
+    tracing::debug!("{:?}", res);
+    serde_json::from_str(&res).map_err(|err| {
+        tracing::error!(" DESERIALIZE {:?}", err);
+        AppError::from_err(err, AppErrorType::HttpError)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::{extract::Form, http::StatusCode, routing::post, Router};
+
+    use super::*;
+
+    /// 启动模拟的 siteverify 接口，返回其地址。`secret`和`response`不正确时返回验证失败
+    async fn stub(status: StatusCode, body: &'static str, delay: Duration) -> String {
+        let app = Router::new().route(
+            "/siteverify",
+            post(
+                move |Form(form): Form<HashMap<String, String>>| async move {
+                    tokio::time::sleep(delay).await;
+                    let valid = form.get("secret").map(String::as_str) == Some("secret")
+                        && form.get("response").map(String::as_str) == Some("token");
+                    if valid {
+                        (status, body)
+                    } else {
+                        (status, r#"{"success":false}"#)
+                    }
+                },
+            ),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+        format!("http://{}/siteverify", addr)
+    }
+
+    fn provider(name: &str, verify_url: String) -> Arc<dyn CaptchaProvider> {
+        from_config(&CaptchaConfig {
+            provider: name.to_string(),
+            site_key: "site".to_string(),
+            secret_key: "secret".to_string(),
+            verify_url,
+            min_score: 0.5,
+            timeout: 1,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn verify_success() {
+        let url = stub(StatusCode::OK, r#"{"success":true}"#, Duration::ZERO).await;
+        for name in ["hcaptcha", "recaptcha", "turnstile"] {
+            assert!(provider(name, url.clone()).verify("token").await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_failure() {
+        let url = stub(StatusCode::OK, r#"{"success":true}"#, Duration::ZERO).await;
+        for name in ["hcaptcha", "recaptcha", "turnstile"] {
+            assert!(!provider(name, url.clone()).verify("wrong").await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_recaptcha_v3_score() {
+        let url = stub(
+            StatusCode::OK,
+            r#"{"success":true,"score":0.9}"#,
+            Duration::ZERO,
+        )
+        .await;
+        assert!(provider("recaptcha_v3", url).verify("token").await.unwrap());
+        let url = stub(
+            StatusCode::OK,
+            r#"{"success":true,"score":0.1}"#,
+            Duration::ZERO,
+        )
+        .await;
+        assert!(!provider("recaptcha_v3", url).verify("token").await.unwrap());
+        let url = stub(StatusCode::OK, r#"{"success":true}"#, Duration::ZERO).await;
+        assert!(!provider("recaptcha_v3", url).verify("token").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn verify_error_status() {
+        let url = stub(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            r#"{"success":true}"#,
+            Duration::ZERO,
+        )
+        .await;
+        assert!(provider("hcaptcha", url).verify("token").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn verify_timeout() {
+        let url = stub(
+            StatusCode::OK,
+            r#"{"success":true}"#,
+            Duration::from_secs(3),
+        )
+        .await;
+        let started = std::time::Instant::now();
+        assert!(provider("hcaptcha", url).verify("token").await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn noop_always_passes() {
+        assert!(provider("noop", "".to_string()).verify("").await.unwrap());
+    }
+}
@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+
+use super::{site_verify, CaptchaProvider};
+use crate::Result;
+
+pub const VERIFY_URL: &str = "https://www.google.com/recaptcha/api/siteverify";
+
+/// Google reCAPTCHA。v2 使用隐藏式组件；v3 没有组件，根据分数判断
+pub struct ReCaptcha {
+    site_key: String,
+    secret_key: String,
+    verify_url: String,
+    /// 仅 v3 有效，低于该分数视为验证失败
+    min_score: Option<f32>,
+    client: reqwest::Client,
+}
+impl ReCaptcha {
+    pub fn v2(
+        site_key: String,
+        secret_key: String,
+        verify_url: String,
+        client: reqwest::Client,
+    ) -> Self {
+        Self {
+            site_key,
+            secret_key,
+            verify_url,
+            min_score: None,
+            client,
+        }
+    }
+    pub fn v3(
+        site_key: String,
+        secret_key: String,
+        verify_url: String,
+        client: reqwest::Client,
+        min_score: f32,
+    ) -> Self {
+        Self {
+            min_score: Some(min_score),
+            ..Self::v2(site_key, secret_key, verify_url, client)
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaProvider for ReCaptcha {
+    fn name(&self) -> &'static str {
+        match self.min_score {
+            Some(_) => "recaptcha_v3",
+            None => "recaptcha",
+        }
+    }
+    fn site_key(&self) -> &str {
+        &self.site_key
+    }
+    fn script_url(&self) -> Option<String> {
+        let render = match self.min_score {
+            Some(_) => self.site_key.as_str(),
+            None => "explicit",
+        };
+        Some(format!(
+            "https://www.google.com/recaptcha/api.js?render={}&onload=axumCaptchaOnload",
+            render
+        ))
+    }
+    async fn verify(&self, response: &str) -> Result<bool> {
+        let res = site_verify(&self.client, &self.verify_url, &self.secret_key, response).await?;
+        let valid = match (self.min_score, res.score) {
+            (Some(min_score), Some(score)) => res.success && score >= min_score,
+            (Some(_), None) => false,
+            (None, _) => res.success,
+        };
+        Ok(valid)
+    }
+}
@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+
+use super::{site_verify, CaptchaProvider};
+use crate::Result;
+
+pub const VERIFY_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+
+/// Cloudflare Turnstile
+pub struct Turnstile {
+    site_key: String,
+    secret_key: String,
+    verify_url: String,
+    client: reqwest::Client,
+}
+impl Turnstile {
+    pub fn new(
+        site_key: String,
+        secret_key: String,
+        verify_url: String,
+        client: reqwest::Client,
+    ) -> Self {
+        Self {
+            site_key,
+            secret_key,
+            verify_url,
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaProvider for Turnstile {
+    fn name(&self) -> &'static str {
+        "turnstile"
+    }
+    fn site_key(&self) -> &str {
+        &self.site_key
+    }
+    fn script_url(&self) -> Option<String> {
+        Some(
+            "https://challenges.cloudflare.com/turnstile/v0/api.js?render=explicit&onload=axumCaptchaOnload"
+                .to_string(),
+        )
+    }
+    async fn verify(&self, response: &str) -> Result<bool> {
+        let res = site_verify(&self.client, &self.verify_url, &self.secret_key, response).await?;
+        Ok(res.success)
+    }
+}
@@ -0,0 +1,176 @@
+//! 管理员账号命令，用于初始化系统和找回账号
+
+use std::io::{self, BufRead, Write};
+
+use clap::Subcommand;
+use tokio_postgres::Client;
+
+use crate::{
+    config::Config,
+    db::admin,
+    error::{AppError, AppErrorType},
+    form::CreateAdmin,
+    login_guard,
+    model::Admin,
+    password,
+    role::Role,
+    session, Result,
+};
+
+#[derive(Subcommand)]
+pub enum Action {
+    /// 添加管理员
+    Create {
+        username: String,
+        /// 密码，未指定时从标准输入读取
+        #[arg(long)]
+        password: Option<String>,
+        /// 设为系统账号
+        #[arg(long)]
+        sys: bool,
+        /// 角色：owner、editor、author、moderator
+        #[arg(long, default_value = "owner")]
+        role: Role,
+    },
+    /// 重置密码
+    Passwd {
+        username: String,
+        /// 新密码，未指定时从标准输入读取
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// 设置角色，并注销其所有会话使新角色立即生效
+    SetRole { username: String, role: Role },
+    /// 切换是否为系统账号
+    ToggleSys { username: String },
+    /// 列出所有管理员
+    List,
+    /// 注销管理员的所有会话
+    RevokeSessions { username: String },
+    /// 解除登录失败导致的锁定
+    Unlock { username: String },
+    /// 关闭两步验证，用于丢失验证器和恢复码的管理员
+    ResetTwoFactor { username: String },
+}
+
+pub async fn run(cfg: Config, action: Action) -> Result<()> {
+    let pool = cfg
+        .pg
+        .create_pool(None, tokio_postgres::NoTls)
+        .map_err(|err| AppError::from_err(err, AppErrorType::Config))?;
+    let mut client = pool.get().await.map_err(AppError::from)?;
+    match action {
+        Action::Create {
+            username,
+            password,
+            sys,
+            role,
+        } => {
+            let password = read_password(password)?;
+            let id = admin::create(
+                &mut client,
+                CreateAdmin {
+                    username: username.clone(),
+                    password: password::hash(&password)?,
+                    re_password: "".to_string(),
+                    role,
+                },
+                None,
+            )
+            .await?
+            .id;
+            if sys {
+                admin::set_sys(&client, id, true).await?;
+            }
+            println!("已添加管理员 {}（#{}，{}）", username, id, role.name());
+        }
+        Action::Passwd { username, password } => {
+            let item = find(&client, &username).await?;
+            let password = read_password(password)?;
+            admin::reset_password(&client, item.id, &password::hash(&password)?).await?;
+            // 旧密码登录的会话全部失效
+            let rdc = redis::Client::open(cfg.redis.dsn.as_str()).map_err(AppError::from)?;
+            session::revoke_all(&rdc, &cfg.session, item.id).await?;
+            println!("已重置 {} 的密码", username);
+        }
+        Action::SetRole { username, role } => {
+            let item = find(&client, &username).await?;
+            admin::set_role(&mut client, item.id, role, None).await?;
+            let rdc = redis::Client::open(cfg.redis.dsn.as_str()).map_err(AppError::from)?;
+            session::revoke_all(&rdc, &cfg.session, item.id).await?;
+            println!("已将 {} 的角色设为{}", username, role.name());
+        }
+        Action::ToggleSys { username } => {
+            let item = find(&client, &username).await?;
+            admin::set_sys(&client, item.id, !item.is_sys).await?;
+            if item.is_sys {
+                println!("{} 已不再是系统账号", username);
+            } else {
+                println!("{} 已设为系统账号", username);
+            }
+        }
+        Action::List => {
+            for item in admin::all(&client).await? {
+                let mut flags = Vec::new();
+                if item.is_sys {
+                    flags.push("系统账号");
+                }
+                if item.is_del {
+                    flags.push("已删除");
+                }
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    item.id,
+                    item.username,
+                    item.role().name(),
+                    flags.join(",")
+                );
+            }
+        }
+        Action::RevokeSessions { username } => {
+            let item = find(&client, &username).await?;
+            let rdc = redis::Client::open(cfg.redis.dsn.as_str()).map_err(AppError::from)?;
+            let revoked = session::revoke_all(&rdc, &cfg.session, item.id).await?;
+            println!("已注销 {} 的 {} 个会话", username, revoked);
+        }
+        Action::Unlock { username } => {
+            let rdc = redis::Client::open(cfg.redis.dsn.as_str()).map_err(AppError::from)?;
+            login_guard::unlock(&rdc, &username).await?;
+            println!("已解除 {} 的登录锁定", username);
+        }
+        Action::ResetTwoFactor { username } => {
+            let item = find(&client, &username).await?;
+            admin::disable_two_factor(&mut client, item.id, None).await?;
+            println!("已关闭 {} 的两步验证", username);
+        }
+    }
+    Ok(())
+}
+
+/// 按用户名查找管理员，包括已删除的
+async fn find(client: &Client, username: &str) -> Result<Admin> {
+    admin::find_by_condition(client, "username=$1", &[&username]).await
+}
+
+/// 未通过参数指定密码时，从标准输入读取
+fn read_password(password: Option<String>) -> Result<String> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            print!("请输入密码：");
+            io::stdout()
+                .flush()
+                .map_err(|err| AppError::from_err(err, AppErrorType::Common))?;
+            let mut line = String::new();
+            io::stdin()
+                .lock()
+                .read_line(&mut line)
+                .map_err(|err| AppError::from_err(err, AppErrorType::Common))?;
+            line.trim_end_matches(&['\r', '\n'][..]).to_string()
+        }
+    };
+    if password.is_empty() {
+        return Err(AppError::from_str("请输入密码", AppErrorType::Common));
+    }
+    Ok(password)
+}
@@ -0,0 +1,77 @@
+//! 数据库迁移命令
+
+use clap::Subcommand;
+
+use crate::{config::Config, error::AppError, migrate, Result};
+
+#[derive(Subcommand)]
+pub enum Action {
+    /// 执行所有尚未执行的迁移
+    Up,
+    /// 查看迁移状态
+    Status,
+    /// 回滚最近执行的迁移
+    Down {
+        /// 回滚的迁移数量
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// 将指定版本及之前的迁移标记为已执行，用于此前手动执行SQL脚本创建的数据库
+    Baseline {
+        /// 数据库当前对应的迁移版本
+        version: i32,
+    },
+}
+
+pub async fn run(cfg: Config, action: Action) -> Result<()> {
+    let pool = cfg
+        .pg
+        .create_pool(None, tokio_postgres::NoTls)
+        .map_err(|err| AppError::from_err(err, crate::error::AppErrorType::Config))?;
+    let mut client = pool.get().await.map_err(AppError::from)?;
+    match action {
+        Action::Up => {
+            let applied = migrate::up(&mut client).await?;
+            if applied.is_empty() {
+                println!("数据库结构已是最新");
+            }
+            for m in applied {
+                println!("已执行 {}", m.name);
+            }
+        }
+        Action::Status => {
+            let (list, unknown) = migrate::status(&client).await?;
+            for s in list {
+                let state = match &s.applied {
+                    Some(applied) => format!("已执行于 {}", applied.applied_at()),
+                    None => "未执行".to_string(),
+                };
+                let reversible = if s.migration.is_reversible() {
+                    ""
+                } else {
+                    "（不可回滚）"
+                };
+                println!("{}\t{}{}", s.migration.name, state, reversible);
+            }
+            for m in unknown {
+                println!("{}\t已执行于 {}（本程序未知）", m.name, m.applied_at());
+            }
+        }
+        Action::Down { steps } => {
+            let reverted = migrate::down(&mut client, steps).await?;
+            if reverted.is_empty() {
+                println!("没有可回滚的迁移");
+            }
+            for m in reverted {
+                println!("已回滚 {}", m.name);
+            }
+        }
+        Action::Baseline { version } => {
+            let marked = migrate::baseline(&client, version).await?;
+            for m in marked {
+                println!("已标记 {}", m.name);
+            }
+        }
+    }
+    Ok(())
+}
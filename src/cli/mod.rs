@@ -0,0 +1,40 @@
+//! 命令行
+
+use clap::{Parser, Subcommand};
+
+use crate::{config::Config, Result};
+
+pub mod admin;
+pub mod migrate;
+
+#[derive(Parser)]
+#[command(version, about = "axum.rs网站系统")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// 启动Web服务（默认）
+    Serve,
+    /// 数据库迁移
+    Migrate {
+        #[command(subcommand)]
+        action: migrate::Action,
+    },
+    /// 管理员账号
+    Admin {
+        #[command(subcommand)]
+        action: admin::Action,
+    },
+}
+
+/// 执行`serve`以外的子命令
+pub async fn run(cfg: Config, command: Command) -> Result<()> {
+    match command {
+        Command::Serve => Ok(()),
+        Command::Migrate { action } => migrate::run(cfg, action).await,
+        Command::Admin { action } => admin::run(cfg, action).await,
+    }
+}
@@ -5,12 +5,27 @@ use serde::Deserialize;
 use crate::{error::AppError, Result};
 
 /// Web配置
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct WebConfig {
     ///  web服务监听地址
     pub addr: String,
     /// 安全key
     pub secret_key: String,
+    /// 网站的访问地址，如`https://axum.rs`，用于生成订阅源等需要完整URL的场景
+    pub site_url: String,
+    /// 受信任的反向代理，只有来自这些地址的请求才使用`X-Forwarded-For`中的客户端地址。
+    /// 多个地址之间用英文逗号分隔，支持`10.0.0.0/8`格式的网段
+    #[serde(default = "default_trusted_proxies")]
+    pub trusted_proxies: String,
+}
+fn default_trusted_proxies() -> String {
+    "127.0.0.1,::1".to_string()
+}
+impl WebConfig {
+    /// 根据路径生成完整的URL
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.site_url.trim_end_matches('/'), path)
+    }
 }
 
 #[derive(Deserialize)]
@@ -21,17 +36,213 @@ pub struct RedisConfig {
 pub struct SessionConfig {
     pub prefix: String,
     pub id_name: String,
+    /// 闲置超时的秒数，每次访问后台都会重新计时
     pub expired: usize,
+    /// 会话的最长有效期（秒），到期后无论是否活跃都需要重新登录，也是 Cookie 的`Max-Age`
+    #[serde(default = "default_max_lifetime")]
+    pub max_lifetime: usize,
+    /// Cookie 是否只通过 HTTPS 发送。本地使用 HTTP 开发时设为`false`
+    #[serde(default = "default_secure")]
+    pub secure: bool,
+}
+fn default_max_lifetime() -> usize {
+    7 * 24 * 3600
 }
+fn default_secure() -> bool {
+    true
+}
+/// 人机验证配置
 #[derive(Deserialize, Clone)]
-pub struct HCaptchaConfig {
+pub struct CaptchaConfig {
+    /// 服务商：`hcaptcha`、`recaptcha`、`recaptcha_v3`、`turnstile`，或不进行验证的`noop`
+    pub provider: String,
+    #[serde(default)]
     pub site_key: String,
+    #[serde(default)]
     pub secret_key: String,
+    /// 校验接口的地址，为空时使用服务商的官方地址
+    #[serde(default)]
+    pub verify_url: String,
+    /// reCAPTCHA v3 的最低分数，低于该分数视为验证失败
+    #[serde(default = "default_min_score")]
+    pub min_score: f32,
+    /// 校验接口的超时时间（秒）
+    #[serde(default = "default_captcha_timeout")]
+    pub timeout: u64,
+}
+fn default_min_score() -> f32 {
+    0.5
+}
+fn default_captcha_timeout() -> u64 {
+    5
 }
+
+/// 登录防暴力破解配置
 #[derive(Deserialize, Clone)]
-pub struct ReCaptchaConfig {
-    pub site_key: String,
-    pub secret_key: String,
+pub struct LoginGuardConfig {
+    /// 统计失败次数的时间窗口（秒）
+    #[serde(default = "default_login_window")]
+    pub window: usize,
+    /// 时间窗口内同一用户名允许失败的次数，超过后锁定该用户名
+    #[serde(default = "default_max_user_failures")]
+    pub max_user_failures: usize,
+    /// 时间窗口内同一 IP 允许失败的次数，超过后锁定该 IP
+    #[serde(default = "default_max_ip_failures")]
+    pub max_ip_failures: usize,
+    /// 锁定的秒数
+    #[serde(default = "default_lockout")]
+    pub lockout: usize,
+}
+impl Default for LoginGuardConfig {
+    fn default() -> Self {
+        Self {
+            window: default_login_window(),
+            max_user_failures: default_max_user_failures(),
+            max_ip_failures: default_max_ip_failures(),
+            lockout: default_lockout(),
+        }
+    }
+}
+fn default_login_window() -> usize {
+    900
+}
+fn default_max_user_failures() -> usize {
+    5
+}
+fn default_max_ip_failures() -> usize {
+    20
+}
+fn default_lockout() -> usize {
+    900
+}
+
+/// 按 IP 限制请求频率的配置
+///
+/// 使用令牌桶：桶中最多有`capacity`个令牌，每个请求消耗一个，每分钟补充`per_minute`个。
+/// `capacity`为 0 时不限制
+#[derive(Deserialize, Clone)]
+pub struct RateLimitConfig {
+    /// 登录，包括输入两步验证的验证码
+    #[serde(default = "default_login_capacity")]
+    pub login_capacity: u32,
+    #[serde(default = "default_login_per_minute")]
+    pub login_per_minute: u32,
+    /// 获取文章中受保护的内容
+    #[serde(default = "default_protected_content_capacity")]
+    pub protected_content_capacity: u32,
+    #[serde(default = "default_protected_content_per_minute")]
+    pub protected_content_per_minute: u32,
+    /// 发表评论
+    #[serde(default = "default_comment_capacity")]
+    pub comment_capacity: u32,
+    #[serde(default = "default_comment_per_minute")]
+    pub comment_per_minute: u32,
+}
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            login_capacity: default_login_capacity(),
+            login_per_minute: default_login_per_minute(),
+            protected_content_capacity: default_protected_content_capacity(),
+            protected_content_per_minute: default_protected_content_per_minute(),
+            comment_capacity: default_comment_capacity(),
+            comment_per_minute: default_comment_per_minute(),
+        }
+    }
+}
+fn default_login_capacity() -> u32 {
+    10
+}
+fn default_login_per_minute() -> u32 {
+    5
+}
+fn default_protected_content_capacity() -> u32 {
+    20
+}
+fn default_protected_content_per_minute() -> u32 {
+    10
+}
+fn default_comment_capacity() -> u32 {
+    5
+}
+fn default_comment_per_minute() -> u32 {
+    2
+}
+
+/// robots.txt 配置
+#[derive(Deserialize, Clone, Default)]
+pub struct RobotsConfig {
+    /// 除`/admin`和`/login`外，额外禁止抓取的路径，多个路径之间用英文逗号分隔
+    #[serde(default)]
+    pub disallow: String,
+}
+
+/// 渲染结果的 HTML 过滤配置，默认只允许 Markdown 渲染所需的标签和属性
+#[derive(Deserialize, Clone, Default)]
+pub struct SanitizeConfig {
+    /// 额外允许的标签，多个标签之间用英文逗号分隔，如`video,source`
+    #[serde(default)]
+    pub tags: String,
+    /// 额外允许的属性，格式为`标签:属性`，标签为`*`时对所有标签有效，如`video:controls,*:data-id`
+    #[serde(default)]
+    pub attributes: String,
+    /// 允许通过 iframe 嵌入的域名，如`player.bilibili.com`，为空时不允许 iframe
+    #[serde(default)]
+    pub iframe_hosts: String,
+}
+
+/// 上传文件配置
+#[derive(Deserialize, Clone)]
+pub struct UploadConfig {
+    /// 存储方式，目前支持`local`
+    #[serde(default = "default_upload_storage")]
+    pub storage: String,
+    /// 本地存储的目录
+    #[serde(default = "default_upload_dir")]
+    pub dir: String,
+    /// 访问上传文件的URL前缀，本地存储时应指向`dir`所在的静态目录
+    #[serde(default = "default_upload_base_url")]
+    pub base_url: String,
+    /// 单个文件的最大字节数
+    #[serde(default = "default_upload_max_size")]
+    pub max_size: usize,
+    /// 缩略图的最大宽度和高度
+    #[serde(default = "default_upload_thumb_size")]
+    pub thumb_size: u32,
+}
+fn default_upload_storage() -> String {
+    "local".to_string()
+}
+fn default_upload_dir() -> String {
+    "static/upload".to_string()
+}
+fn default_upload_base_url() -> String {
+    "/static/upload".to_string()
+}
+fn default_upload_max_size() -> usize {
+    10 * 1024 * 1024
+}
+fn default_upload_thumb_size() -> u32 {
+    320
+}
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            storage: default_upload_storage(),
+            dir: default_upload_dir(),
+            base_url: default_upload_base_url(),
+            max_size: default_upload_max_size(),
+            thumb_size: default_upload_thumb_size(),
+        }
+    }
+}
+
+/// 数据库迁移配置
+#[derive(Deserialize, Default)]
+pub struct MigrateConfig {
+    /// 启动时自动执行尚未执行的迁移。为`false`时，数据库结构不是最新则拒绝启动
+    #[serde(default)]
+    pub auto: bool,
 }
 
 /// 配置
@@ -43,8 +254,19 @@ pub struct Config {
     pub pg: deadpool_postgres::Config,
     pub redis: RedisConfig,
     pub session: SessionConfig,
-    pub hcaptcha: HCaptchaConfig,
-    pub recaptcha: ReCaptchaConfig,
+    #[serde(default)]
+    pub login_guard: LoginGuardConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    pub captcha: CaptchaConfig,
+    #[serde(default)]
+    pub robots: RobotsConfig,
+    #[serde(default)]
+    pub migrate: MigrateConfig,
+    #[serde(default)]
+    pub sanitize: SanitizeConfig,
+    #[serde(default)]
+    pub upload: UploadConfig,
 }
 
 impl Config {
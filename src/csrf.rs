@@ -0,0 +1,25 @@
+//! 后台的 CSRF 令牌
+//!
+//! 令牌由会话 ID 签名得到，每个会话一个，会话 ID 更换后原令牌随之失效，不需要额外保存。
+//! 表单通过隐藏字段`_csrf`提交，AJAX 请求通过`X-CSRF-Token`请求头提交。
+
+use crate::sign;
+
+/// 表单字段名
+pub const FIELD: &str = "_csrf";
+/// 请求头名
+pub const HEADER: &str = "x-csrf-token";
+
+/// 会话的令牌
+pub fn token(secret_key: &str, session_id: &str) -> String {
+    sign::sign(secret_key, &msg(session_id))
+}
+
+/// 验证令牌
+pub fn verify(secret_key: &str, session_id: &str, token: &str) -> bool {
+    !token.is_empty() && sign::verify(secret_key, &msg(session_id), token)
+}
+
+fn msg(session_id: &str) -> String {
+    format!("csrf:{}", session_id)
+}
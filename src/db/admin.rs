@@ -1,34 +1,73 @@
+use serde_json::json;
 use tokio_postgres::types::ToSql;
-use tokio_postgres::Client;
+use tokio_postgres::{Client, GenericClient};
 
 use crate::{
+    audit::{Action, Audit, Entry, Target},
     error::AppError,
     form::{CreateAdmin, UpdateAdmin},
     model::{Admin, AdminID},
+    role::Role,
+    time::now,
     Result,
 };
 
 use super::{pagination::Pagination, select_stmt::SelectStmt, PAGE_SIZE};
 
-pub async fn create(client: &Client, ca: CreateAdmin) -> Result<AdminID> {
+const FIELDS: &str = "id, username, password, is_sys, is_del, role, totp_secret, totp_enabled";
+
+/// 添加管理员。命令行的操作没有操作者，`audit`为`None`，不记录审计日志
+pub async fn create(
+    client: &mut Client,
+    ca: CreateAdmin,
+    audit: Option<&Audit>,
+) -> Result<AdminID> {
     let sql = "SELECT COUNT(*) FROM admin WHERE username=$1";
     let c = super::count(client, sql, &[&ca.username]).await?;
     if c > 0 {
         return Err(AppError::is_exists("同名的管理员已存在"));
     }
-    let sql = "INSERT INTO admin (username, password) VALUES ($1, $2) RETURNING id";
-    Ok(super::query_one(
-        client,
+    // 出错时事务在 drop 时自动回滚
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let sql = "INSERT INTO admin (username, password, role) VALUES ($1, $2, $3) RETURNING id";
+    let id: AdminID = super::query_one(
+        &tx,
         sql,
-        &[&ca.username, &ca.password],
+        &[&ca.username, &ca.password, &ca.role.code()],
         Some("添加管理员失败"),
     )
-    .await?)
+    .await?;
+    if let Some(audit) = audit {
+        let entry = Entry::new(Action::Create, Target::Admin, id.id as i64, &ca.username)
+            .after(json!({ "username": ca.username, "role": ca.role.as_str() }));
+        super::audit_log::create(&tx, audit, &entry).await?;
+    }
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(id)
 }
-pub async fn del_or_restore(client: &Client, id: i32, is_del_opt: bool) -> Result<()> {
+/// 删除或恢复管理员，返回受影响的行数。系统账号不会被删除
+pub async fn del_or_restore(
+    client: &mut Client,
+    id: i32,
+    is_del_opt: bool,
+    audit: &Audit,
+) -> Result<u64> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let item = find_by_id_in(&tx, id).await?;
     let sql = "UPDATE admin SET is_del = $1 WHERE id=$2 AND is_sys=false";
-    super::execute(client, sql, &[&is_del_opt, &id]).await?;
-    Ok(())
+    let rows = super::execute(&tx, sql, &[&is_del_opt, &id]).await?;
+    // 系统账号不会被删除，也就不需要记录
+    if rows > 0 {
+        let action = if is_del_opt {
+            Action::Delete
+        } else {
+            Action::Restore
+        };
+        let entry = Entry::new(action, Target::Admin, id as i64, &item.username).is_del(is_del_opt);
+        super::audit_log::create(&tx, audit, &entry).await?;
+    }
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(rows)
 }
 
 pub async fn find_by_condition(
@@ -38,7 +77,7 @@ pub async fn find_by_condition(
 ) -> Result<Admin> {
     let sql = SelectStmt::builder()
         .table("admin")
-        .fields("id, username, password, is_sys, is_del")
+        .fields(FIELDS)
         .condition(Some(condition))
         .limit(Some(1))
         .build();
@@ -50,6 +89,11 @@ pub async fn find(client: &Client, username: &str) -> Result<Admin> {
 pub async fn find_by_id(client: &Client, id: i32) -> Result<Admin> {
     find_by_condition(client, "id=$1", &[&id]).await
 }
+/// 在事务中获取管理员，用于记录操作前的状态
+async fn find_by_id_in(client: &impl GenericClient, id: i32) -> Result<Admin> {
+    let sql = format!("SELECT {} FROM admin WHERE id=$1", FIELDS);
+    super::query_one(client, &sql, &[&id], Some("不存在的管理员")).await
+}
 
 pub async fn select(
     client: &Client,
@@ -59,7 +103,7 @@ pub async fn select(
 ) -> Result<Pagination<Vec<Admin>>> {
     let sql = SelectStmt::builder()
         .table("admin")
-        .fields("id,username,password,is_sys,is_del")
+        .fields(FIELDS)
         .condition(condition)
         .order(Some("id DESC"))
         .limit(Some(PAGE_SIZE))
@@ -72,7 +116,149 @@ pub async fn select(
         .build();
     Ok(super::select(client, &sql, &count_sql, args, page).await?)
 }
-pub async fn update(client: &Client, ua: UpdateAdmin) -> Result<u64> {
+/// 修改密码。日志中不记录密码的哈希
+pub async fn update(client: &mut Client, ua: UpdateAdmin, audit: &Audit) -> Result<u64> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let item = find_by_id_in(&tx, ua.id).await?;
     let sql = "UPDATE admin SET password = $1 WHERE id=$2";
-    super::execute(client, sql, &[&ua.new_password, &ua.id]).await
+    let rows = super::execute(&tx, sql, &[&ua.new_password, &ua.id]).await?;
+    let entry = Entry::new(
+        Action::ChangePassword,
+        Target::Admin,
+        ua.id as i64,
+        &item.username,
+    );
+    super::audit_log::create(&tx, audit, &entry).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(rows)
+}
+
+/// 所有管理员，包括已删除的
+pub async fn all(client: &Client) -> Result<Vec<Admin>> {
+    let sql = SelectStmt::builder()
+        .table("admin")
+        .fields(FIELDS)
+        .order(Some("id ASC"))
+        .build();
+    super::query(client, &sql, &[]).await
+}
+/// 重置密码。`password`为哈希后的密码
+pub async fn reset_password(client: &Client, id: i32, password: &str) -> Result<u64> {
+    let sql = "UPDATE admin SET password = $1 WHERE id=$2";
+    super::execute(client, sql, &[&password, &id]).await
+}
+/// 设置是否为系统账号
+pub async fn set_sys(client: &Client, id: i32, is_sys: bool) -> Result<u64> {
+    let sql = "UPDATE admin SET is_sys = $1 WHERE id=$2";
+    super::execute(client, sql, &[&is_sys, &id]).await
+}
+/// 设置角色。命令行的操作`audit`为`None`，不记录审计日志
+pub async fn set_role(
+    client: &mut Client,
+    id: i32,
+    role: Role,
+    audit: Option<&Audit>,
+) -> Result<u64> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let item = find_by_id_in(&tx, id).await?;
+    let sql = "UPDATE admin SET role = $1 WHERE id=$2";
+    let rows = super::execute(&tx, sql, &[&role.code(), &id]).await?;
+    if let Some(audit) = audit {
+        let entry = Entry::new(Action::SetRole, Target::Admin, id as i64, &item.username)
+            .before(json!({ "role": item.role().as_str() }))
+            .after(json!({ "role": role.as_str() }));
+        super::audit_log::create(&tx, audit, &entry).await?;
+    }
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(rows)
+}
+
+/// 启用两步验证，同时生成新的恢复码。`step`为确认时验证码所在的时间步，`code_hashes`为恢复码的哈希
+pub async fn enable_two_factor(
+    client: &mut Client,
+    id: i32,
+    secret: &str,
+    step: i64,
+    code_hashes: &[String],
+    audit: &Audit,
+) -> Result<()> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let item = find_by_id_in(&tx, id).await?;
+    let sql = "UPDATE admin SET totp_secret=$1, totp_enabled=true, totp_last_step=$2 WHERE id=$3";
+    super::execute(&tx, sql, &[&secret, &step, &id]).await?;
+    replace_recovery_codes(&tx, id, code_hashes).await?;
+    let entry = Entry::new(
+        Action::EnableTwoFactor,
+        Target::Admin,
+        id as i64,
+        &item.username,
+    );
+    super::audit_log::create(&tx, audit, &entry).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(())
+}
+/// 关闭两步验证并删除恢复码。命令行的操作`audit`为`None`，不记录审计日志
+pub async fn disable_two_factor(client: &mut Client, id: i32, audit: Option<&Audit>) -> Result<()> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let item = find_by_id_in(&tx, id).await?;
+    let sql = "UPDATE admin SET totp_secret='', totp_enabled=false, totp_last_step=0 WHERE id=$1";
+    super::execute(&tx, sql, &[&id]).await?;
+    replace_recovery_codes(&tx, id, &[]).await?;
+    if let Some(audit) = audit {
+        let entry = Entry::new(
+            Action::DisableTwoFactor,
+            Target::Admin,
+            id as i64,
+            &item.username,
+        );
+        super::audit_log::create(&tx, audit, &entry).await?;
+    }
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(())
+}
+/// 重新生成恢复码，之前的恢复码全部作废
+pub async fn regenerate_recovery_codes(
+    client: &mut Client,
+    id: i32,
+    code_hashes: &[String],
+    audit: &Audit,
+) -> Result<()> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let item = find_by_id_in(&tx, id).await?;
+    replace_recovery_codes(&tx, id, code_hashes).await?;
+    let entry = Entry::new(Action::Update, Target::Admin, id as i64, &item.username)
+        .after(json!({ "recovery_codes": code_hashes.len() }));
+    super::audit_log::create(&tx, audit, &entry).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(())
+}
+async fn replace_recovery_codes(
+    client: &impl GenericClient,
+    admin_id: i32,
+    code_hashes: &[String],
+) -> Result<()> {
+    let sql = "DELETE FROM admin_recovery_code WHERE admin_id=$1";
+    super::execute(client, sql, &[&admin_id]).await?;
+    let sql = "INSERT INTO admin_recovery_code (admin_id, code_hash) VALUES ($1, $2)";
+    for code_hash in code_hashes {
+        super::execute(client, sql, &[&admin_id, code_hash]).await?;
+    }
+    Ok(())
+}
+/// 记录通过验证的时间步。不大于上次记录的时间步时返回`false`，即验证码已经使用过
+pub async fn use_totp_step(client: &Client, id: i32, step: i64) -> Result<bool> {
+    let sql = "UPDATE admin SET totp_last_step=$1 WHERE id=$2 AND totp_last_step<$1";
+    let rows = super::execute(client, sql, &[&step, &id]).await?;
+    Ok(rows > 0)
+}
+/// 使用恢复码，使用后作废。恢复码不存在或已经使用过时返回`false`
+pub async fn use_recovery_code(client: &Client, id: i32, code_hash: &str) -> Result<bool> {
+    let sql = "UPDATE admin_recovery_code SET used_at=$1 WHERE admin_id=$2 AND code_hash=$3 AND used_at=0";
+    let rows = super::execute(client, sql, &[&now(), &id, &code_hash]).await?;
+    Ok(rows > 0)
+}
+/// 未使用的恢复码数量
+pub async fn recovery_code_count(client: &Client, id: i32) -> Result<i64> {
+    let sql = "SELECT COUNT(*) FROM admin_recovery_code WHERE admin_id=$1 AND used_at=0";
+    super::count(client, sql, &[&id]).await
 }
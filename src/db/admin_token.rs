@@ -0,0 +1,93 @@
+//! 管理员API令牌
+
+use tokio_postgres::Client;
+
+use crate::{
+    model::{AdminToken, AdminTokenID},
+    time::now,
+    Result,
+};
+
+use super::{execute, pagination::Pagination, query_one, select_stmt::SelectStmt, PAGE_SIZE};
+
+const FIELDS: &str = "id, admin_id, name, dateline, last_used, is_del";
+
+/// 创建令牌。`token_hash` 为令牌的哈希值
+pub async fn create(
+    client: &Client,
+    admin_id: i32,
+    name: &str,
+    token_hash: &str,
+) -> Result<AdminTokenID> {
+    let sql = "INSERT INTO admin_token (admin_id, name, token_hash, dateline) VALUES ($1, $2, $3, $4) RETURNING id";
+    query_one(
+        client,
+        sql,
+        &[&admin_id, &name, &token_hash, &now()],
+        Some("创建令牌失败"),
+    )
+    .await
+}
+
+/// 分页显示指定管理员的令牌
+pub async fn select(
+    client: &Client,
+    admin_id: i32,
+    is_del: bool,
+    keyword: &str,
+    page: u32,
+) -> Result<Pagination<Vec<AdminToken>>> {
+    let condition = "admin_id=$1 AND is_del=$2 AND name ILIKE $3";
+    let sql = SelectStmt::builder()
+        .table("admin_token")
+        .fields(FIELDS)
+        .condition(Some(condition))
+        .order(Some("id DESC"))
+        .limit(Some(PAGE_SIZE))
+        .offset(Some(page * PAGE_SIZE as u32))
+        .build();
+    let count_sql = SelectStmt::builder()
+        .table("admin_token")
+        .fields("COUNT(*)")
+        .condition(Some(condition))
+        .build();
+    super::select(
+        client,
+        &sql,
+        &count_sql,
+        &[&admin_id, &is_del, &keyword],
+        page,
+    )
+    .await
+}
+
+/// 根据令牌的哈希值查找有效的令牌。令牌所属的管理员被删除时，令牌同样失效
+pub async fn find_by_hash(client: &Client, token_hash: &str) -> Result<AdminToken> {
+    let sql = SelectStmt::builder()
+        .table("admin_token")
+        .fields(FIELDS)
+        .condition(Some("token_hash=$1 AND is_del=false AND admin_id IN (SELECT id FROM admin WHERE is_del=false)"))
+        .limit(Some(1))
+        .build();
+    query_one(client, &sql, &[&token_hash], Some("无效的令牌")).await
+}
+
+/// 记录令牌的最后使用时间
+pub async fn touch(client: &Client, id: i32) -> Result<u64> {
+    execute(
+        client,
+        "UPDATE admin_token SET last_used=$1 WHERE id=$2",
+        &[&now(), &id],
+    )
+    .await
+}
+
+/// 吊销令牌。只能吊销自己的令牌
+pub async fn revoke(client: &Client, id: i32, admin_id: i32) -> Result<u64> {
+    execute(
+        client,
+        "UPDATE admin_token SET is_del=true WHERE id=$1 AND admin_id=$2",
+        &[&id, &admin_id],
+    )
+    .await
+}
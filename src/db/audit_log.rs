@@ -0,0 +1,78 @@
+use tokio_postgres::{types::ToSql, Client, GenericClient};
+
+use crate::{
+    audit::{Audit, Entry},
+    model::AuditLog,
+    time::now,
+    Result,
+};
+
+use super::{pagination::Pagination, select_stmt::SelectStmt, PAGE_SIZE};
+
+/// CSV 导出的最大条数
+const EXPORT_LIMIT: u32 = 10000;
+
+const FIELDS: &str = "id,admin_id,admin_name,action,target_type,target_id,target_name,before_summary,after_summary,ip,user_agent,dateline";
+
+/// 写入日志，应与被记录的操作使用同一个事务。未登录的操作者`admin_id`保存为`NULL`
+pub async fn create(client: &impl GenericClient, audit: &Audit, entry: &Entry) -> Result<u64> {
+    let sql = "INSERT INTO audit_log (admin_id, action, target_type, target_id, target_name, before_summary, after_summary, ip, user_agent, dateline) VALUES (NULLIF($1, 0), $2, $3, $4, $5, $6, $7, $8, $9, $10)";
+    super::execute(
+        client,
+        sql,
+        &[
+            &audit.admin_id,
+            &entry.action.as_str(),
+            &entry.target.as_str(),
+            &entry.target_id,
+            &entry.target_name,
+            &entry.before,
+            &entry.after,
+            &audit.ip,
+            &audit.user_agent,
+            &now(),
+        ],
+    )
+    .await
+}
+
+pub async fn select(
+    client: &Client,
+    condition: Option<&str>,
+    args: &[&(dyn ToSql + Sync)],
+    page: u32,
+) -> Result<Pagination<Vec<AuditLog>>> {
+    let sql = SelectStmt::builder()
+        .table("v_audit_log")
+        .fields(FIELDS)
+        .condition(condition)
+        .order(Some("id DESC"))
+        .limit(Some(PAGE_SIZE))
+        .offset(Some(page * PAGE_SIZE as u32))
+        .build();
+    let count_sql = SelectStmt::builder()
+        .table("v_audit_log")
+        .fields("COUNT(*)")
+        .condition(condition)
+        .build();
+    super::select(client, &sql, &count_sql, args, page).await
+}
+
+/// 用于导出，最多返回最近的[`EXPORT_LIMIT`]条
+pub async fn all(
+    client: &Client,
+    condition: Option<&str>,
+    args: &[&(dyn ToSql + Sync)],
+) -> Result<Vec<AuditLog>> {
+    let sql = format!(
+        "{} LIMIT {}",
+        SelectStmt::builder()
+            .table("v_audit_log")
+            .fields(FIELDS)
+            .condition(condition)
+            .order(Some("id DESC"))
+            .build(),
+        EXPORT_LIMIT
+    );
+    super::query(client, &sql, args).await
+}
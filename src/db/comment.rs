@@ -0,0 +1,201 @@
+use std::collections::{HashMap, HashSet};
+
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Client;
+
+use crate::{
+    error::AppError,
+    form::CreateComment,
+    model::{Comment, CommentID, CommentList, CommentNode, CommentStatus, TopicID, TopicPath},
+    time::now,
+    Result,
+};
+
+use super::{
+    execute, pagination::Pagination, query, query_one, select_stmt::SelectStmt, PAGE_SIZE,
+};
+
+/// 获取前台可见的文章的访问路径
+pub async fn find_topic_path(client: &Client, topic_id: i64) -> Result<TopicPath> {
+    let sql = SelectStmt::builder()
+        .table("v_subject_topics")
+        .fields("subject_slug,slug")
+        .condition(Some("id=$1"))
+        .limit(Some(1))
+        .build();
+    query_one(client, &sql, &[&topic_id], Some("没有符合条件的文章")).await
+}
+
+/// 提交评论，需审核后才会显示
+pub async fn create(client: &Client, cc: &CreateComment) -> Result<CommentID> {
+    let parent_id = cc.parent_id();
+    if let Some(parent_id) = parent_id {
+        let n = super::count(
+            client,
+            "SELECT COUNT(*) FROM comment WHERE id=$1 AND topic_id=$2 AND status=$3 AND is_del=false",
+            &[&parent_id, &cc.topic_id, &CommentStatus::Approved.code()],
+        )
+        .await?;
+        if n == 0 {
+            return Err(AppError::not_found("回复的评论不存在"));
+        }
+    }
+    let sql = "INSERT INTO comment (topic_id, parent_id, nickname, email, content, status, dateline) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id";
+    query_one(
+        client,
+        sql,
+        &[
+            &cc.topic_id,
+            &parent_id,
+            &cc.nickname.trim(),
+            &cc.email.trim(),
+            &cc.content.trim(),
+            &CommentStatus::Pending.code(),
+            &now(),
+        ],
+        Some("提交评论失败"),
+    )
+    .await
+}
+
+/// 获取文章已通过审核的评论，按回复关系排序。
+/// 所回复的评论不可见时，该回复作为顶层评论显示
+pub async fn approved(client: &Client, topic_id: i64) -> Result<Vec<CommentNode>> {
+    let sql = SelectStmt::builder()
+        .table("comment")
+        .fields("id,parent_id,nickname,content,dateline")
+        .condition(Some("topic_id=$1 AND status=$2 AND is_del=false"))
+        .order(Some("id ASC"))
+        .build();
+    let list: Vec<Comment> =
+        query(client, &sql, &[&topic_id, &CommentStatus::Approved.code()]).await?;
+    Ok(thread(list))
+}
+
+/// 按深度优先的顺序排列评论。父评论不在列表中的作为顶层评论
+fn thread(list: Vec<Comment>) -> Vec<CommentNode> {
+    let ids: HashSet<i64> = list.iter().map(|c| c.id).collect();
+    let total = list.len();
+    let mut roots = Vec::new();
+    let mut children: HashMap<i64, Vec<Comment>> = HashMap::new();
+    for c in list {
+        match c.parent_id.filter(|id| ids.contains(id)) {
+            Some(parent_id) => children.entry(parent_id).or_default().push(c),
+            None => roots.push(c),
+        }
+    }
+    let mut nodes = Vec::with_capacity(total);
+    let mut stack: Vec<(Comment, usize)> = roots.into_iter().rev().map(|c| (c, 0)).collect();
+    while let Some((comment, depth)) = stack.pop() {
+        if let Some(replies) = children.remove(&comment.id) {
+            stack.extend(replies.into_iter().rev().map(|c| (c, depth + 1)));
+        }
+        nodes.push(CommentNode { comment, depth });
+    }
+    nodes
+}
+
+pub async fn select(
+    client: &Client,
+    condition: Option<&str>,
+    args: &[&(dyn ToSql + Sync)],
+    page: u32,
+) -> Result<Pagination<Vec<CommentList>>> {
+    let sql = SelectStmt::builder()
+        .table("v_comment_list")
+        .fields("id,topic_id,parent_id,nickname,email,content,status,dateline,is_del,topic_title,topic_slug,subject_slug,parent_nickname")
+        .condition(condition)
+        .order(Some("id DESC"))
+        .limit(Some(PAGE_SIZE))
+        .offset(Some(page * PAGE_SIZE as u32))
+        .build();
+    let count_sql = SelectStmt::builder()
+        .table("v_comment_list")
+        .fields("COUNT(*)")
+        .condition(condition)
+        .build();
+    super::select(client, &sql, &count_sql, args, page).await
+}
+
+/// 评论所属的文章，审核评论后据此清除文章的缓存
+pub async fn topic_ids(client: &Client, ids: &[i64]) -> Result<Vec<i64>> {
+    let list: Vec<TopicID> = query(
+        client,
+        "SELECT DISTINCT topic_id AS id FROM comment WHERE id=ANY($1)",
+        &[&ids],
+    )
+    .await?;
+    Ok(list.into_iter().map(|t| t.id).collect())
+}
+
+/// 修改评论的审核状态
+pub async fn set_status(client: &Client, ids: &[i64], status: CommentStatus) -> Result<u64> {
+    execute(
+        client,
+        "UPDATE comment SET status=$1 WHERE id=ANY($2)",
+        &[&status.code(), &ids],
+    )
+    .await
+}
+
+/// 删除评论
+pub async fn del(client: &Client, ids: &[i64]) -> Result<u64> {
+    execute(
+        client,
+        "UPDATE comment SET is_del=true WHERE id=ANY($1)",
+        &[&ids],
+    )
+    .await
+}
+
+pub async fn restore(client: &Client, id: i64) -> Result<u64> {
+    super::restore(client, "comment", &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: i64, parent_id: Option<i64>) -> Comment {
+        Comment {
+            id,
+            parent_id,
+            nickname: String::new(),
+            content: String::new(),
+            dateline: 0,
+        }
+    }
+
+    #[test]
+    fn thread_depth_first() {
+        let list = vec![
+            comment(1, None),
+            comment(2, None),
+            comment(3, Some(1)),
+            comment(4, Some(2)),
+            comment(5, Some(3)),
+            comment(6, Some(1)),
+            // 父评论未通过审核
+            comment(7, Some(100)),
+        ];
+        let nodes: Vec<(i64, usize)> = thread(list)
+            .iter()
+            .map(|n| (n.comment.id, n.depth))
+            .collect();
+        assert_eq!(
+            nodes,
+            vec![(1, 0), (3, 1), (5, 2), (6, 1), (2, 0), (4, 1), (7, 0)]
+        );
+    }
+
+    #[test]
+    fn thread_long_chain() {
+        let n = 20_000;
+        let list = (1..=n)
+            .map(|id| comment(id, if id == 1 { None } else { Some(id - 1) }))
+            .collect();
+        let nodes = thread(list);
+        assert_eq!(nodes.len(), n as usize);
+        assert_eq!(nodes.last().map(|n| n.depth), Some(n as usize - 1));
+    }
+}
@@ -0,0 +1,82 @@
+use tokio_postgres::{types::ToSql, Client};
+
+use crate::{
+    db::pagination::Pagination,
+    media::NewMedia,
+    model::{Media, MediaID},
+    time::now,
+    Result,
+};
+
+use super::{query, query_one, select_stmt::SelectStmt, PAGE_SIZE};
+
+const FIELDS: &str =
+    "id,url,thumb_url,file_name,content_type,size,width,height,is_image,dateline,is_del";
+
+/// 根据内容哈希查找文件，包括已删除的
+pub async fn find_by_hash(client: &Client, hash: &str) -> Result<Option<Media>> {
+    let sql = SelectStmt::builder()
+        .table("media")
+        .fields(FIELDS)
+        .condition(Some("hash=$1"))
+        .limit(Some(1))
+        .build();
+    let list: Vec<Media> = query(client, &sql, &[&hash]).await?;
+    Ok(list.into_iter().next())
+}
+
+pub async fn create(client: &Client, m: &NewMedia) -> Result<MediaID> {
+    query_one(
+        client,
+        "INSERT INTO media (admin_id, hash, storage, path, thumb_path, url, thumb_url, file_name, content_type, size, width, height, is_image, dateline) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14) RETURNING id",
+        &[
+            &m.admin_id,
+            &m.hash,
+            &m.storage,
+            &m.path,
+            &m.thumb_path,
+            &m.url,
+            &m.thumb_url,
+            &m.file_name,
+            &m.content_type,
+            &m.size,
+            &m.width,
+            &m.height,
+            &m.is_image,
+            &now(),
+        ],
+        Some("上传文件失败"),
+    )
+    .await
+}
+
+pub async fn select(
+    client: &Client,
+    condition: Option<&str>,
+    args: &[&(dyn ToSql + Sync)],
+    page: u32,
+) -> Result<Pagination<Vec<Media>>> {
+    let sql = SelectStmt::builder()
+        .table("media")
+        .fields(FIELDS)
+        .condition(condition)
+        .order(Some("id DESC"))
+        .limit(Some(PAGE_SIZE))
+        .offset(Some(page * PAGE_SIZE as u32))
+        .build();
+    let count_sql = SelectStmt::builder()
+        .table("media")
+        .fields("COUNT(*)")
+        .condition(condition)
+        .build();
+    super::select(client, &sql, &count_sql, args, page).await
+}
+
+/// 删除媒体记录，文件仍然保留，已经引用它的文章不受影响
+pub async fn del(client: &Client, id: i64) -> Result<u64> {
+    super::del(client, "media", &id).await
+}
+
+pub async fn restore(client: &Client, id: i64) -> Result<u64> {
+    super::restore(client, "media", &id).await
+}
@@ -0,0 +1,112 @@
+//! 数据库迁移记录
+
+use tokio_postgres::Client;
+
+use crate::{error::AppError, model::SchemaMigration, time::now, Result};
+
+/// 迁移使用的会话级咨询锁的键
+const LOCK_KEY: i64 = 0x0061_7875_6d2d_7273;
+
+/// 获取迁移锁，多个实例同时启动时只有一个执行迁移，其它实例等待其完成。
+/// 每个迁移在独立的事务中执行，所以使用会话级的锁而不是事务级的锁
+pub async fn lock(client: &Client) -> Result<()> {
+    client
+        .execute("SELECT pg_advisory_lock($1)", &[&LOCK_KEY])
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// 释放迁移锁
+pub async fn unlock(client: &Client) -> Result<()> {
+    client
+        .execute("SELECT pg_advisory_unlock($1)", &[&LOCK_KEY])
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// 创建迁移记录表
+pub async fn init(client: &Client) -> Result<()> {
+    let exists = super::count(
+        client,
+        "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema=current_schema() AND table_name='schema_migrations'",
+        &[],
+    )
+    .await?;
+    if exists > 0 {
+        return Ok(());
+    }
+    client
+        .batch_execute(
+            "CREATE TABLE schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name VARCHAR(100) NOT NULL,
+                applied_at INTEGER NOT NULL DEFAULT 0
+            )",
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// 已执行的迁移，按版本升序
+pub async fn applied(client: &Client) -> Result<Vec<SchemaMigration>> {
+    super::query(
+        client,
+        "SELECT version, name, applied_at FROM schema_migrations ORDER BY version ASC",
+        &[],
+    )
+    .await
+}
+
+/// 在事务中执行迁移脚本，并写入迁移记录
+pub async fn apply(client: &mut Client, version: i32, name: &str, sql: &str) -> Result<()> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    if let Err(err) = tx.batch_execute(sql).await {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(AppError::from(err));
+    };
+    if let Err(err) = super::execute(
+        &tx,
+        "INSERT INTO schema_migrations (version, name, applied_at) VALUES ($1, $2, $3)",
+        &[&version, &name, &now()],
+    )
+    .await
+    {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(err);
+    };
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(())
+}
+
+/// 在事务中执行回滚脚本，并删除迁移记录
+pub async fn revert(client: &mut Client, version: i32, sql: &str) -> Result<()> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    if let Err(err) = tx.batch_execute(sql).await {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(AppError::from(err));
+    };
+    if let Err(err) = super::execute(
+        &tx,
+        "DELETE FROM schema_migrations WHERE version=$1",
+        &[&version],
+    )
+    .await
+    {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(err);
+    };
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(())
+}
+
+/// 只写入迁移记录而不执行脚本，用于由旧版 SQL 脚本创建的数据库
+pub async fn mark(client: &Client, version: i32, name: &str) -> Result<u64> {
+    super::execute(
+        client,
+        "INSERT INTO schema_migrations (version, name, applied_at) VALUES ($1, $2, $3) ON CONFLICT(version) DO NOTHING",
+        &[&version, &name, &now()],
+    )
+    .await
+}
@@ -6,11 +6,19 @@ use tokio_pg_mapper::FromTokioPostgresRow;
 use self::pagination::Pagination;
 
 pub mod admin;
+pub mod admin_token;
+pub mod audit_log;
+pub mod comment;
+pub mod media;
+pub mod migration;
 pub mod pagination;
 pub mod select_stmt;
+pub mod setting;
+pub mod sitemap;
 pub mod subject;
 pub mod tag;
 pub mod topic;
+pub mod topic_revision;
 
 /// 默认分页大小
 const PAGE_SIZE: u8 = 30;
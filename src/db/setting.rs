@@ -0,0 +1,32 @@
+//! 站点设置
+
+use serde_json::json;
+use tokio_postgres::{Client, GenericClient};
+
+use crate::{
+    audit::{Action, Audit, Entry, Target},
+    error::AppError,
+    model::Setting,
+    Result,
+};
+
+/// 读取设置，不存在时返回`None`
+pub async fn get(client: &impl GenericClient, name: &str) -> Result<Option<String>> {
+    let sql = "SELECT name, value FROM setting WHERE name=$1";
+    let item: Option<Setting> = super::query(client, sql, &[&name]).await?.pop();
+    Ok(item.map(|item| item.value))
+}
+
+/// 保存设置，并记录修改前后的值
+pub async fn set(client: &mut Client, name: &str, value: &str, audit: &Audit) -> Result<()> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let before = get(&tx, name).await?;
+    let sql = "INSERT INTO setting (name, value) VALUES ($1, $2) ON CONFLICT (name) DO UPDATE SET value=EXCLUDED.value";
+    super::execute(&tx, sql, &[&name, &value]).await?;
+    let entry = Entry::new(Action::Update, Target::Setting, 0, name)
+        .before(json!({ name: before }))
+        .after(json!({ name: value }));
+    super::audit_log::create(&tx, audit, &entry).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(())
+}
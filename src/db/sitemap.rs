@@ -0,0 +1,34 @@
+//! 站点地图
+
+use tokio_postgres::Client;
+
+use crate::{model::SitemapUrl, Result};
+
+/// 首页，其最后修改时间为最新文章的时间
+pub async fn home(client: &Client) -> Result<SitemapUrl> {
+    let sql = "SELECT '/' AS loc, COALESCE(MAX(dateline), 0) AS lastmod FROM v_subject_topics";
+    super::query_one(client, sql, &[], None).await
+}
+
+/// 所有未删除的专题，其最后修改时间为专题内最新文章的时间
+pub async fn subjects(client: &Client) -> Result<Vec<SitemapUrl>> {
+    let sql = "SELECT '/subject/' || s.slug AS loc, COALESCE(MAX(v.dateline), 0) AS lastmod FROM subject AS s LEFT JOIN v_subject_topics AS v ON v.subject_slug=s.slug WHERE s.is_del=false GROUP BY s.id, s.slug ORDER BY s.id ASC";
+    super::query(client, sql, &[]).await
+}
+
+/// 所有未删除的标签，其最后修改时间为标签内最新文章的时间
+pub async fn tags(client: &Client) -> Result<Vec<SitemapUrl>> {
+    let sql = "SELECT '/tag/' || g.name AS loc, COALESCE(MAX(v.dateline), 0) AS lastmod FROM tag AS g LEFT JOIN v_subject_topics AS v ON g.name = ANY(v.tag_names) WHERE g.is_del=false GROUP BY g.id, g.name ORDER BY g.id ASC";
+    super::query(client, sql, &[]).await
+}
+
+/// 统计前台可见的文章数
+pub async fn count_topics(client: &Client) -> Result<i64> {
+    super::count(client, "SELECT COUNT(*) FROM v_subject_topics", &[]).await
+}
+
+/// 前台可见的文章
+pub async fn topics(client: &Client, offset: i64, limit: i64) -> Result<Vec<SitemapUrl>> {
+    let sql = "SELECT '/topic/' || subject_slug || '/' || slug AS loc, dateline AS lastmod FROM v_subject_topics ORDER BY id ASC LIMIT $1 OFFSET $2";
+    super::query(client, sql, &[&limit, &offset]).await
+}
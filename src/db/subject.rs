@@ -1,11 +1,13 @@
 //! 主题数据库操作
 
+use crate::audit::{Action, Audit, Entry, Target};
 use crate::error::AppError;
 use crate::form::{CreateSubject, UpdateSubject};
-use crate::model::{Subject, SubjectID, SubjectList};
+use crate::model::{ProtectMode, Subject, SubjectID, SubjectList};
 use crate::Result;
+use serde_json::{json, Value};
 use tokio_postgres::types::ToSql;
-use tokio_postgres::Client;
+use tokio_postgres::{Client, GenericClient};
 
 use super::pagination::Pagination;
 use super::select_stmt::SelectStmt;
@@ -87,7 +89,7 @@ pub async fn select_with_summary(
 ) -> Result<Pagination<Vec<Subject>>> {
     let sql = SelectStmt::builder()
         .table(TABLE_NAME)
-        .fields("id, name, slug, is_del, summary")
+        .fields("id, name, slug, is_del, summary, protect_mode, protect_count")
         .condition(condition)
         .order(Some("id DESC"))
         .limit(Some(PAGE_SIZE))
@@ -115,7 +117,7 @@ pub async fn find(
 ) -> Result<Subject> {
     let sql = SelectStmt::builder()
         .table(TABLE_NAME)
-        .fields("id, name, slug,summary,is_del")
+        .fields("id, name, slug,summary,is_del, protect_mode, protect_count")
         .condition(condition)
         .limit(Some(1))
         .build();
@@ -141,27 +143,68 @@ pub async fn slug_is_exists(client: &Client, slug: &str) -> Result<bool> {
     is_exists(client, Some("slug=$1"), &[&slug]).await
 }
 
+/// 用于审计日志的摘要
+fn summary(name: &str, slug: &str, protect_mode: ProtectMode, protect_count: i16) -> Value {
+    json!({
+        "name": name,
+        "slug": slug,
+        "protect_mode": protect_mode.as_str(),
+        "protect_count": protect_count,
+    })
+}
+
+/// 在事务中获取主题，用于记录操作前的状态
+async fn find_by_id(client: &impl GenericClient, id: i32) -> Result<Subject> {
+    let sql = SelectStmt::builder()
+        .table(TABLE_NAME)
+        .fields("id, name, slug,summary,is_del, protect_mode, protect_count")
+        .condition(Some("id=$1"))
+        .limit(Some(1))
+        .build();
+    super::query_one(client, &sql, &[&id], Some("没有找到符合条件的主题")).await
+}
+
 /// 创建主题。返回新创建的主题的ID，或包含[`AppError`]的错误信息
 ///
 /// # 参数
 ///
 /// * `client` - 数据库连接对象
 /// * `cs` - 输入的主题信息
-pub async fn create(client: &Client, cs: &CreateSubject) -> Result<SubjectID> {
+/// * `audit` - 操作者，用于审计日志
+pub async fn create(client: &mut Client, cs: &CreateSubject, audit: &Audit) -> Result<SubjectID> {
     if slug_is_exists(client, &cs.slug).await? {
         return Err(AppError::is_exists(&format!(
             "主题的固定链接 '{}' 已存在",
             &cs.slug
         )));
     };
-    let sql = "INSERT INTO subject (name, slug, summary) VALUES ($1, $2, $3) RETURNING id";
-    Ok(super::query_one(
-        client,
+    // 出错时事务在 drop 时自动回滚
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let protect_mode = cs.protect_mode.for_subject();
+    let protect_count = cs.protect_count.max(0);
+    let sql = "INSERT INTO subject (name, slug, summary, protect_mode, protect_count) VALUES ($1, $2, $3, $4, $5) RETURNING id";
+    let id: SubjectID = super::query_one(
+        &tx,
         sql,
-        &[&cs.name, &cs.slug, &cs.summary],
+        &[
+            &cs.name,
+            &cs.slug,
+            &cs.summary,
+            &protect_mode.code(),
+            &protect_count,
+        ],
         Some("插入主题失败"),
     )
-    .await?)
+    .await?;
+    let entry = Entry::new(Action::Create, Target::Subject, id.id as i64, &cs.name).after(summary(
+        &cs.name,
+        &cs.slug,
+        protect_mode,
+        protect_count,
+    ));
+    super::audit_log::create(&tx, audit, &entry).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(id)
 }
 
 /// 更新主题。返回更新结果，或包含[`AppError`]的错误信息
@@ -170,19 +213,44 @@ pub async fn create(client: &Client, cs: &CreateSubject) -> Result<SubjectID> {
 ///
 /// * `client` - 数据库连接对象
 /// * `us` - 输入的主题信息
-pub async fn update(client: &Client, us: &UpdateSubject) -> Result<bool> {
+/// * `audit` - 操作者，用于审计日志
+pub async fn update(client: &mut Client, us: &UpdateSubject, audit: &Audit) -> Result<bool> {
     if is_exists(client, Some("slug=$1 AND id<>$2"), &[&us.slug, &us.id]).await? {
         return Err(AppError::is_exists(&format!(
             "主题的固定链接 '{}' 已存在",
             &us.slug
         )));
     }
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let before = find_by_id(&tx, us.id).await?;
+    let protect_mode = us.protect_mode.for_subject();
+    let protect_count = us.protect_count.max(0);
     let result = execute(
-        client,
-        "UPDATE subject SET name=$1, slug=$2, summary=$3 WHERE id=$4",
-        &[&us.name, &us.slug, &us.summary, &us.id],
+        &tx,
+        "UPDATE subject SET name=$1, slug=$2, summary=$3, protect_mode=$4, protect_count=$5 WHERE id=$6",
+        &[
+            &us.name,
+            &us.slug,
+            &us.summary,
+            &protect_mode.code(),
+            &protect_count,
+            &us.id,
+        ],
     )
     .await?;
+    let entry = Entry::new(Action::Update, Target::Subject, us.id as i64, &us.name)
+        .before(summary(
+            &before.name,
+            &before.slug,
+            before.protect_mode(),
+            before.protect_count,
+        ))
+        .after(summary(&us.name, &us.slug, protect_mode, protect_count));
+    super::audit_log::create(&tx, audit, &entry).await?;
+    if before.protect_mode() != protect_mode || before.protect_count != protect_count {
+        super::topic::refresh_search_text(&tx, "subject_id=$1", &[&us.id]).await?;
+    }
+    tx.commit().await.map_err(AppError::from)?;
     match result {
         ref updated if *updated == 1 => Ok(true),
         _ => Ok(false),
@@ -196,13 +264,29 @@ pub async fn update(client: &Client, us: &UpdateSubject) -> Result<bool> {
 /// * `client` - 数据库连接对象
 /// * `id` - 要操作的主题ID
 /// * `is_del_opt` - 是否为删除操作
-async fn del_or_restore(client: &Client, id: i32, is_del_opt: bool) -> Result<bool> {
+/// * `audit` - 操作者，用于审计日志
+async fn del_or_restore(
+    client: &mut Client,
+    id: i32,
+    is_del_opt: bool,
+    audit: &Audit,
+) -> Result<bool> {
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let item = find_by_id(&tx, id).await?;
     let result = execute(
-        client,
+        &tx,
         "UPDATE subject SET is_del=$1 WHERE id=$2",
         &[&is_del_opt, &id],
     )
     .await?;
+    let action = if is_del_opt {
+        Action::Delete
+    } else {
+        Action::Restore
+    };
+    let entry = Entry::new(action, Target::Subject, id as i64, &item.name).is_del(is_del_opt);
+    super::audit_log::create(&tx, audit, &entry).await?;
+    tx.commit().await.map_err(AppError::from)?;
     match result {
         ref updated if *updated == 1 => Ok(true),
         _ => Ok(false),
@@ -215,8 +299,9 @@ async fn del_or_restore(client: &Client, id: i32, is_del_opt: bool) -> Result<bo
 ///
 /// * `client` - 数据库连接对象
 /// * `id` - 要操作的主题ID
-pub async fn delete(client: &Client, id: i32) -> Result<bool> {
-    del_or_restore(client, id, true).await
+/// * `audit` - 操作者，用于审计日志
+pub async fn delete(client: &mut Client, id: i32, audit: &Audit) -> Result<bool> {
+    del_or_restore(client, id, true, audit).await
 }
 
 /// 恢复主题。返回操作结果，或包含[`AppError`]的错误信息
@@ -225,8 +310,9 @@ pub async fn delete(client: &Client, id: i32) -> Result<bool> {
 ///
 /// * `client` - 数据库连接对象
 /// * `id` - 要操作的主题ID
-pub async fn restore(client: &Client, id: i32) -> Result<bool> {
-    del_or_restore(client, id, false).await
+/// * `audit` - 操作者，用于审计日志
+pub async fn restore(client: &mut Client, id: i32, audit: &Audit) -> Result<bool> {
+    del_or_restore(client, id, false, audit).await
 }
 
 pub async fn all(client: &Client) -> Result<Vec<SubjectList>> {
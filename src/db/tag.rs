@@ -1,7 +1,9 @@
+use serde_json::json;
 use tokio_postgres::types::ToSql;
-use tokio_postgres::Client;
+use tokio_postgres::{Client, GenericClient};
 
 use crate::{
+    audit::{Action, Audit, Entry, Target},
     error::AppError,
     form::{CreateTag, UpdateTag},
     model::{Tag, TagID},
@@ -67,25 +69,64 @@ pub async fn is_exists(
 pub async fn name_is_exists(client: &Client, name: &str) -> Result<bool> {
     is_exists(client, &format!("name=$1"), &[&name]).await
 }
-pub async fn del(client: &Client, id: i32) -> Result<u64> {
-    super::del(client, "tag", &id).await
+/// 在事务中获取标签，用于记录操作前的状态
+async fn find_by_id(client: &impl GenericClient, id: i32) -> Result<Tag> {
+    query_one(
+        client,
+        "SELECT id,name,is_del FROM tag WHERE id=$1",
+        &[&id],
+        Some("没有找到符合条件的标签"),
+    )
+    .await
 }
-pub async fn restore(client: &Client, id: i32) -> Result<u64> {
-    super::restore(client, "tag", &id).await
+async fn del_or_restore(client: &mut Client, id: i32, is_del: bool, audit: &Audit) -> Result<u64> {
+    // 出错时事务在 drop 时自动回滚
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let item = find_by_id(&tx, id).await?;
+    let rows = super::del_or_restore(&tx, "tag", &id, is_del).await?;
+    let action = if is_del {
+        Action::Delete
+    } else {
+        Action::Restore
+    };
+    let entry = Entry::new(action, Target::Tag, id as i64, &item.name).is_del(is_del);
+    super::audit_log::create(&tx, audit, &entry).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(rows)
 }
-pub async fn create(client: &Client, ct: &CreateTag) -> Result<TagID> {
+pub async fn del(client: &mut Client, id: i32, audit: &Audit) -> Result<u64> {
+    del_or_restore(client, id, true, audit).await
+}
+pub async fn restore(client: &mut Client, id: i32, audit: &Audit) -> Result<u64> {
+    del_or_restore(client, id, false, audit).await
+}
+pub async fn create(client: &mut Client, ct: &CreateTag, audit: &Audit) -> Result<TagID> {
     if name_is_exists(client, &ct.name).await? {
         return Err(AppError::is_exists("同名的标签已存在"));
     }
+    let tx = client.transaction().await.map_err(AppError::from)?;
     let sql = "INSERT INTO tag (name, is_del) VALUES ($1, false) RETURNING id";
-    Ok(query_one(client, sql, &[&ct.name], Some("创建标签失败")).await?)
+    let id: TagID = query_one(&tx, sql, &[&ct.name], Some("创建标签失败")).await?;
+    let entry = Entry::new(Action::Create, Target::Tag, id.id as i64, &ct.name)
+        .after(json!({ "name": ct.name }));
+    super::audit_log::create(&tx, audit, &entry).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(id)
 }
-pub async fn update(client: &Client, ut: &UpdateTag) -> Result<u64> {
+pub async fn update(client: &mut Client, ut: &UpdateTag, audit: &Audit) -> Result<u64> {
     if is_exists(client, "name=$1 AND id<>$2", &[&ut.name, &ut.id]).await? {
         return Err(AppError::is_exists("同名的标签已存在"));
     }
+    let tx = client.transaction().await.map_err(AppError::from)?;
+    let before = find_by_id(&tx, ut.id).await?;
     let sql = "UPDATE tag SET name =$1 WHERE id=$2";
-    execute(client, sql, &[&ut.name, &ut.id]).await
+    let rows = execute(&tx, sql, &[&ut.name, &ut.id]).await?;
+    let entry = Entry::new(Action::Update, Target::Tag, ut.id as i64, &ut.name)
+        .before(json!({ "name": before.name }))
+        .after(json!({ "name": ut.name }));
+    super::audit_log::create(&tx, audit, &entry).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(rows)
 }
 pub async fn all(client: &Client) -> Result<Vec<Tag>> {
     let sql = SelectStmt::builder()
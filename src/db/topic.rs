@@ -1,20 +1,31 @@
 use crate::{
+    audit::{Action, Audit, Entry, Target},
     error::AppError,
     form::{CreateTopic, UpdateTopic},
     model::{
-        SubjectTopicWithTagsAndTopicSummary, TagID, TopicDetail, TopicID, TopicSubjectListView,
-        TopicWithMdAndTagsForEdit,
+        SubjectTopicWithTagsAndTopicSummary, TagID, TopicContent, TopicDetail, TopicHit, TopicID,
+        TopicOwner, TopicPlacement, TopicSearchResult, TopicSearchSource, TopicStatus,
+        TopicSubjectListView, TopicTitle, TopicWithMdAndTagsForEdit,
     },
+    search,
     time::now,
     Result,
 };
+use serde_json::{json, Value};
 use tokio_postgres::types::ToSql;
-use tokio_postgres::Client;
+use tokio_postgres::{Client, GenericClient};
 
 use super::{pagination::Pagination, query_one, select_stmt::SelectStmt, PAGE_SIZE};
 
-/// 创建新的文章
-pub async fn create(client: &mut Client, ct: &CreateTopic, html: &str) -> Result<TopicID> {
+/// 创建新的文章，并记录初始版本
+pub async fn create(
+    client: &mut Client,
+    ct: &CreateTopic,
+    html: &str,
+    toc: &str,
+    audit: &Audit,
+) -> Result<TopicID> {
+    let publish_at = ct.publish_at()?;
     let tx = client.transaction().await.map_err(AppError::from)?;
     // 是否存在
     match super::count(
@@ -36,14 +47,19 @@ pub async fn create(client: &mut Client, ct: &CreateTopic, html: &str) -> Result
     };
 
     let now = now();
-    let topic_id: TopicID = match super::query_one(&tx, "INSERT INTO topic (title, subject_id, slug, summary, author,  dateline, src) VALUES ($1, $2, $3, $4, $5, $6,$7 ) RETURNING id",&[
+    let topic_id: TopicID = match super::query_one(&tx, "INSERT INTO topic (title, subject_id, slug, summary, author,  dateline, src, status, publish_at, protect_mode, protect_count, admin_id) VALUES ($1, $2, $3, $4, $5, $6,$7, $8, $9, $10, $11, $12) RETURNING id",&[
         &ct.title,
         &ct.subject_id,
         &ct.slug,
         &ct.summary,
         &ct.author,
-        &now,
+        &ct.dateline.unwrap_or(now),
         &ct.src,
+        &ct.status.code(),
+        &publish_at,
+        &ct.protect_mode.code(),
+        &ct.protect_count.max(0),
+        &audit.admin_id,
     ] , Some("插入文章失败")).await
     {
         Ok(s) => s,
@@ -53,7 +69,7 @@ pub async fn create(client: &mut Client, ct: &CreateTopic, html: &str) -> Result
     };
 
     // 内容
-    match super::execute(&tx, "INSERT INTO topic_content (topic_id, md, html) VALUES ($1, $2, $3) ON CONFLICT(topic_id) DO UPDATE SET md=EXCLUDED.md,html=EXCLUDED.html",& [&topic_id.id, &ct.md, &html]).await {
+    match super::execute(&tx, "INSERT INTO topic_content (topic_id, md, html, toc) VALUES ($1, $2, $3, $4) ON CONFLICT(topic_id) DO UPDATE SET md=EXCLUDED.md,html=EXCLUDED.html,toc=EXCLUDED.toc",& [&topic_id.id, &ct.md, &html, &toc]).await {
         Ok(_) => {},
         Err(err) => {
             tx.rollback().await.map_err(AppError::from)?;
@@ -61,6 +77,27 @@ pub async fn create(client: &mut Client, ct: &CreateTopic, html: &str) -> Result
             }
     };
 
+    if let Err(err) = refresh_search_text(&tx, "topic_id=$1", &[&topic_id.id]).await {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(err);
+    };
+
+    // 版本
+    if let Err(err) = super::topic_revision::create(
+        &tx,
+        topic_id.id,
+        &ct.title,
+        &ct.summary,
+        &ct.md,
+        &ct.tags,
+        audit.admin_id,
+    )
+    .await
+    {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(err);
+    };
+
     // tag
     if !ct.tags.is_empty() {
         let tags = ct.tags.split(',').collect::<Vec<&str>>();
@@ -84,10 +121,46 @@ pub async fn create(client: &mut Client, ct: &CreateTopic, html: &str) -> Result
             };
         }
     }
+
+    // 审计日志
+    let entry = Entry::new(Action::Create, Target::Topic, topic_id.id, &ct.title).after(summary(
+        &ct.title,
+        &ct.slug,
+        ct.subject_id,
+        &ct.author,
+        ct.status,
+        &ct.tags,
+        &ct.md,
+    ));
+    if let Err(err) = super::audit_log::create(&tx, audit, &entry).await {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(err);
+    };
     tx.commit().await.map_err(AppError::from)?;
     Ok(topic_id)
 }
 
+/// 用于审计日志的摘要。内容只记录长度，完整的内容见文章的版本记录
+fn summary(
+    title: &str,
+    slug: &str,
+    subject_id: i32,
+    author: &str,
+    status: TopicStatus,
+    tags: &str,
+    md: &str,
+) -> Value {
+    json!({
+        "title": title,
+        "slug": slug,
+        "subject_id": subject_id,
+        "author": author,
+        "status": status.as_str(),
+        "tags": tags,
+        "md_length": md.chars().count(),
+    })
+}
+
 /// 分页显示文章
 pub async fn select(
     client: &Client,
@@ -97,7 +170,7 @@ pub async fn select(
 ) -> Result<Pagination<Vec<TopicSubjectListView>>> {
     let sql = SelectStmt::builder()
         .table("v_topic_subject_list")
-        .fields("id,title,slug,subject_name,subject_slug,subject_id,is_del,subject_is_del")
+        .fields("id,title,slug,subject_name,subject_slug,subject_id,is_del,subject_is_del,status,publish_at")
         .condition(condition)
         .order(Some("id DESC"))
         .limit(Some(PAGE_SIZE))
@@ -119,7 +192,7 @@ pub async fn select_with_summary(
 ) -> Result<Pagination<Vec<SubjectTopicWithTagsAndTopicSummary>>> {
     let sql = SelectStmt::builder()
         .table("v_subject_topics")
-        .fields("id,title,slug,subject_slug,tag_names,summary,subject_name")
+        .fields("id,title,slug,subject_slug,tag_names,summary,subject_name,author,dateline,comment_count")
         .condition(condition)
         .order(order)
         .limit(Some(PAGE_SIZE))
@@ -133,9 +206,66 @@ pub async fn select_with_summary(
     super::select(client, &sql, &count_sql, args, page).await
 }
 
+/// 全文搜索文章，按相关度排序。
+/// `simple`分词不会切分中文，因此同时按子串匹配标题、摘要和正文，只按子串命中的文章排在后面
+pub async fn search(
+    client: &Client,
+    keyword: &str,
+    page: u32,
+) -> Result<Pagination<Vec<TopicSearchResult>>> {
+    let table = "v_topic_search, plainto_tsquery('simple', $1) AS query";
+    let condition =
+        "(search_vector @@ query OR title ILIKE $2 OR summary ILIKE $2 OR search_text ILIKE $2)";
+    let fields = format!(
+        "id,title,slug,subject_slug,subject_name,tag_names,CASE WHEN search_vector @@ query THEN ts_headline('simple', summary || ' ' || search_text, query, '{}') ELSE substr(summary || ' ' || search_text, GREATEST(strpos(lower(summary || ' ' || search_text), lower($1)) - {}, 1), {}) END AS headline,ts_rank(search_vector, query) AS rank",
+        search::headline_options(),
+        search::SNIPPET_BEFORE,
+        search::SNIPPET_LEN,
+    );
+    let sql = SelectStmt::builder()
+        .table(table)
+        .fields(&fields)
+        .condition(Some(condition))
+        .order(Some("rank DESC, id DESC"))
+        .limit(Some(PAGE_SIZE))
+        .offset(Some(page * PAGE_SIZE as u32))
+        .build();
+    let count_sql = SelectStmt::builder()
+        .table(table)
+        .fields("COUNT(*)")
+        .condition(Some(condition))
+        .build();
+    let pattern = search::like_pattern(keyword);
+    let mut list: Pagination<Vec<TopicSearchResult>> =
+        super::select(client, &sql, &count_sql, &[&keyword, &pattern], page).await?;
+    for item in list.data.iter_mut() {
+        item.headline = search::mark_keyword(&item.headline, keyword);
+    }
+    Ok(list)
+}
+
 /// 删除或还原文章
-pub async fn del_or_restore(client: &mut Client, id: i64, is_del: bool) -> Result<(u64, u64)> {
+pub async fn del_or_restore(
+    client: &mut Client,
+    id: i64,
+    is_del: bool,
+    audit: &Audit,
+) -> Result<(u64, u64)> {
     let tx = client.transaction().await.map_err(AppError::from)?;
+    let item: TopicTitle = match super::query_one(
+        &tx,
+        "SELECT id, title FROM topic WHERE id=$1",
+        &[&id],
+        Some("没有找到符合条件的文章"),
+    )
+    .await
+    {
+        Ok(s) => s,
+        Err(err) => {
+            tx.rollback().await.map_err(AppError::from)?;
+            return Err(err);
+        }
+    };
     let topic_rows = match super::execute(
         &tx,
         "UPDATE topic SET is_del=$1 WHERE id=$2",
@@ -163,6 +293,16 @@ pub async fn del_or_restore(client: &mut Client, id: i64, is_del: bool) -> Resul
         }
     };
 
+    let action = if is_del {
+        Action::Delete
+    } else {
+        Action::Restore
+    };
+    let entry = Entry::new(action, Target::Topic, id, &item.title).is_del(is_del);
+    if let Err(err) = super::audit_log::create(&tx, audit, &entry).await {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(err);
+    };
     tx.commit().await.map_err(AppError::from)?;
     Ok((topic_rows, topic_tag_rows))
 }
@@ -171,14 +311,31 @@ pub async fn del_or_restore(client: &mut Client, id: i64, is_del: bool) -> Resul
 pub async fn find_to_edit(client: &Client, id: i64) -> Result<TopicWithMdAndTagsForEdit> {
     let sql = SelectStmt::builder()
         .table("v_topic_with_md_and_tags_for_edit")
-        .fields("id,title,subject_id,slug,summary,author,md,tag_names,src")
+        .fields("id,title,subject_id,slug,summary,author,md,tag_names,src,status,publish_at,dateline,protect_mode,protect_count")
         .condition(Some("id=$1"))
         .limit(Some(1))
         .build();
     query_one(client, &sql, &[&id], Some("没有找到符合条件的文章")).await
 }
-/// 修改文章
-pub async fn update(client: &mut Client, ut: &UpdateTopic, html: &str) -> Result<bool> {
+/// 文章的创建者
+pub async fn find_owner(client: &Client, id: i64) -> Result<TopicOwner> {
+    query_one(
+        client,
+        "SELECT id, admin_id FROM topic WHERE id=$1",
+        &[&id],
+        Some("没有找到符合条件的文章"),
+    )
+    .await
+}
+/// 修改文章，并记录新的版本
+pub async fn update(
+    client: &mut Client,
+    ut: &UpdateTopic,
+    html: &str,
+    toc: &str,
+    audit: &Audit,
+) -> Result<bool> {
+    let publish_at = ut.publish_at()?;
     let tx = client.transaction().await.map_err(AppError::from)?;
     // 是否存在
     match super::count(
@@ -199,13 +356,34 @@ pub async fn update(client: &mut Client, ut: &UpdateTopic, html: &str) -> Result
         _ => {}
     };
 
-    if let Err(err) = super::execute(&tx, "UPDATE topic SET title=$1, subject_id=$2, slug=$3, summary=$4, author=$5, src=$6 WHERE id=$7", &[
+    // 修改前的状态，用于审计日志
+    let before: TopicWithMdAndTagsForEdit = match super::query_one(
+        &tx,
+        "SELECT id,title,subject_id,slug,summary,author,md,tag_names,src,status,publish_at,dateline,protect_mode,protect_count FROM v_topic_with_md_and_tags_for_edit WHERE id=$1",
+        &[&ut.id],
+        Some("没有找到符合条件的文章"),
+    )
+    .await
+    {
+        Ok(s) => s,
+        Err(err) => {
+            tx.rollback().await.map_err(AppError::from)?;
+            return Err(err);
+        }
+    };
+
+    if let Err(err) = super::execute(&tx, "UPDATE topic SET title=$1, subject_id=$2, slug=$3, summary=$4, author=$5, src=$6, status=$7, publish_at=$8, protect_mode=$9, protect_count=$10, dateline=COALESCE($11, dateline) WHERE id=$12", &[
         &ut.title,
         &ut.subject_id,
         &ut.slug,
         &ut.summary,
         &ut.author,
         &ut.src,
+        &ut.status.code(),
+        &publish_at,
+        &ut.protect_mode.code(),
+        &ut.protect_count.max(0),
+        &ut.dateline,
         &ut.id,
     ]).await {
         tx.rollback().await.map_err(AppError::from)?;
@@ -214,8 +392,29 @@ pub async fn update(client: &mut Client, ut: &UpdateTopic, html: &str) -> Result
     // 内容
     if let Err(err) = super::execute(
         &tx,
-        "UPDATE topic_content SET  md=$1, html=$2 WHERE topic_id=$3",
-        &[&ut.md, &html, &ut.id],
+        "UPDATE topic_content SET  md=$1, html=$2, toc=$3 WHERE topic_id=$4",
+        &[&ut.md, &html, &toc, &ut.id],
+    )
+    .await
+    {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(err);
+    };
+    // 隐藏策略可能有变化
+    if let Err(err) = refresh_search_text(&tx, "topic_id=$1", &[&ut.id]).await {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Err(err);
+    };
+
+    // 版本
+    if let Err(err) = super::topic_revision::create(
+        &tx,
+        ut.id,
+        &ut.title,
+        &ut.summary,
+        &ut.md,
+        &ut.tags,
+        audit.admin_id,
     )
     .await
     {
@@ -256,25 +455,147 @@ pub async fn update(client: &mut Client, ut: &UpdateTopic, html: &str) -> Result
             };
         }
     }
-    tx.commit().await.map_err(AppError::from)?;
-    Ok(true)
-}
 
-pub async fn detail(client: &mut Client, subject_slug: &str, slug: &str) -> Result<TopicDetail> {
-    let tx = client.transaction().await.map_err(AppError::from)?;
-    let result:TopicDetail = match super::query_one(&tx, "SELECT id,title,subject_id,slug,author,src,html,tag_names,subject_slug,dateline,hit,subject_name FROM v_topic_detail WHERE subject_slug=$1 AND slug=$2", &[&subject_slug, &slug], Some("没有符合条件的文章")).await {
-        Ok(s) => s,
-        Err(err) => {
-            tx.rollback().await.map_err(AppError::from)?;
-            return Err(err);
-        }
-    };
-    if let Err(err) =
-        super::execute(&tx, "UPDATE topic SET hit=hit+1 WHERE id=$1", &[&result.id]).await
-    {
+    // 审计日志
+    let entry = Entry::new(Action::Update, Target::Topic, ut.id, &ut.title)
+        .before(summary(
+            &before.title,
+            &before.slug,
+            before.subject_id,
+            &before.author,
+            TopicStatus::from_code(before.status),
+            &before.tags(),
+            &before.md,
+        ))
+        .after(summary(
+            &ut.title,
+            &ut.slug,
+            ut.subject_id,
+            &ut.author,
+            ut.status,
+            &ut.tags,
+            &ut.md,
+        ));
+    if let Err(err) = super::audit_log::create(&tx, audit, &entry).await {
         tx.rollback().await.map_err(AppError::from)?;
         return Err(err);
     };
     tx.commit().await.map_err(AppError::from)?;
-    Ok(result)
+    Ok(true)
+}
+
+/// 前台显示的文章详情，不增加浏览次数
+pub async fn detail(client: &Client, subject_slug: &str, slug: &str) -> Result<TopicDetail> {
+    query_one(client, "SELECT id,title,subject_id,slug,author,src,html,tag_names,subject_slug,dateline,hit,subject_name,protect_mode,protect_count,toc FROM v_topic_detail WHERE subject_slug=$1 AND slug=$2", &[&subject_slug, &slug], Some("没有符合条件的文章")).await
+}
+
+/// 根据ID获取前台显示的文章详情，已删除、未发布的文章视为不存在
+pub async fn detail_by_id(client: &Client, id: i64) -> Result<TopicDetail> {
+    query_one(client, "SELECT id,title,subject_id,slug,author,src,html,tag_names,subject_slug,dateline,hit,subject_name,protect_mode,protect_count,toc FROM v_topic_detail WHERE id=$1", &[&id], Some("没有符合条件的文章")).await
+}
+
+/// 增加一次浏览，返回新的浏览次数
+pub async fn hit(client: &Client, id: i64) -> Result<i32> {
+    let result: TopicHit = query_one(
+        client,
+        "UPDATE topic SET hit=hit+1 WHERE id=$1 RETURNING hit",
+        &[&id],
+        Some("没有符合条件的文章"),
+    )
+    .await?;
+    Ok(result.hit)
+}
+
+/// 文章的固定链接、所属的专题和标签。包括已删除的文章和标签，删除、还原文章后也能据此清除缓存
+pub async fn placement(client: &Client, id: i64) -> Result<TopicPlacement> {
+    query_one(
+        client,
+        "SELECT t.id,t.slug,s.slug AS subject_slug,ARRAY(SELECT g.name FROM topic_tag AS tt INNER JOIN tag AS g ON g.id=tt.tag_id WHERE tt.topic_id=t.id) AS tag_names FROM topic AS t INNER JOIN subject AS s ON s.id=t.subject_id WHERE t.id=$1",
+        &[&id],
+        Some("没有找到符合条件的文章"),
+    )
+    .await
+}
+
+/// 预览文章，不论其状态如何，也不增加浏览次数
+pub async fn preview(client: &Client, id: i64) -> Result<TopicDetail> {
+    let sql = SelectStmt::builder()
+        .table("v_topic_preview")
+        .fields("id,title,subject_id,slug,author,src,html,tag_names,subject_slug,dateline,hit,subject_name,protect_mode,protect_count,toc")
+        .condition(Some("id=$1"))
+        .limit(Some(1))
+        .build();
+    query_one(client, &sql, &[&id], Some("没有符合条件的文章")).await
+}
+
+/// 获取专题下所有未删除的文章，用于导出
+pub async fn find_to_edit_by_subject(
+    client: &Client,
+    subject_id: i32,
+) -> Result<Vec<TopicWithMdAndTagsForEdit>> {
+    let sql = SelectStmt::builder()
+        .table("v_topic_with_md_and_tags_for_edit")
+        .fields("id,title,subject_id,slug,summary,author,md,tag_names,src,status,publish_at,dateline,protect_mode,protect_count")
+        .condition(Some("subject_id=$1 AND id IN (SELECT id FROM topic WHERE is_del=false)"))
+        .order(Some("id ASC"))
+        .build();
+    super::query(client, &sql, &[&subject_id]).await
+}
+
+/// 根据专题和固定链接查找文章的ID
+pub async fn find_id_by_slug(client: &Client, subject_id: i32, slug: &str) -> Result<Option<i64>> {
+    let ids: Vec<TopicID> = super::query(
+        client,
+        "SELECT id FROM topic WHERE subject_id=$1 AND slug=$2",
+        &[&subject_id, &slug],
+    )
+    .await?;
+    Ok(ids.first().map(|t| t.id))
+}
+
+/// 获取所有文章的内容，用于重新渲染
+pub async fn all_contents(client: &Client) -> Result<Vec<TopicContent>> {
+    super::query(
+        client,
+        "SELECT topic_id,md,html,toc FROM topic_content ORDER BY topic_id",
+        &[],
+    )
+    .await
+}
+
+/// 更新渲染结果，Markdown 没有变化，所以不记录新的版本
+pub async fn update_html(client: &Client, topic_id: i64, html: &str, toc: &str) -> Result<u64> {
+    let rows = super::execute(
+        client,
+        "UPDATE topic_content SET html=$1, toc=$2 WHERE topic_id=$3",
+        &[&html, &toc, &topic_id],
+    )
+    .await?;
+    refresh_search_text(client, "topic_id=$1", &[&topic_id]).await?;
+    Ok(rows)
+}
+
+/// 根据 HTML 和实际生效的隐藏策略重新生成用于全文搜索的纯文本，返回处理的文章数。
+/// 文章内容、文章或专题的隐藏策略变化后都需要重新生成
+pub async fn refresh_search_text(
+    client: &impl GenericClient,
+    condition: &str,
+    args: &[&(dyn ToSql + Sync)],
+) -> Result<usize> {
+    let sql = SelectStmt::builder()
+        .table("v_topic_search_source")
+        .fields("topic_id,html,protect_mode,protect_count")
+        .condition(Some(condition))
+        .build();
+    let list: Vec<TopicSearchSource> = super::query(client, &sql, args).await?;
+    for item in list.iter() {
+        let text = search::text(item.topic_id, &item.html, item.protect_policy());
+        super::execute(
+            client,
+            "UPDATE topic_content SET search_text=$1 WHERE topic_id=$2",
+            &[&text, &item.topic_id],
+        )
+        .await?;
+    }
+    Ok(list.len())
 }
@@ -0,0 +1,65 @@
+//! 文章修订历史
+
+use tokio_postgres::{Client, GenericClient};
+
+use crate::{
+    model::{TopicRevision, TopicRevisionList},
+    time::now,
+    Result,
+};
+
+use super::{execute, pagination::Pagination, query_one, select_stmt::SelectStmt, PAGE_SIZE};
+
+/// 记录一个版本。由[`super::topic`]在保存文章的事务中调用
+pub(super) async fn create<C>(
+    client: &C,
+    topic_id: i64,
+    title: &str,
+    summary: &str,
+    md: &str,
+    tags: &str,
+    admin_id: i32,
+) -> Result<u64>
+where
+    C: GenericClient,
+{
+    execute(
+        client,
+        "INSERT INTO topic_revision (topic_id, title, summary, md, tags, admin_id, dateline) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        &[&topic_id, &title, &summary, &md, &tags, &admin_id, &now()],
+    )
+    .await
+}
+
+/// 分页显示文章的版本，最新的在前
+pub async fn select(
+    client: &Client,
+    topic_id: i64,
+    page: u32,
+) -> Result<Pagination<Vec<TopicRevisionList>>> {
+    let sql = SelectStmt::builder()
+        .table("topic_revision AS r LEFT JOIN admin AS a ON a.id=r.admin_id")
+        .fields("r.id,r.topic_id,r.title,a.username AS admin_name,r.dateline")
+        .condition(Some("r.topic_id=$1"))
+        .order(Some("r.id DESC"))
+        .limit(Some(PAGE_SIZE))
+        .offset(Some(page * PAGE_SIZE as u32))
+        .build();
+    let count_sql = SelectStmt::builder()
+        .table("topic_revision")
+        .fields("COUNT(*)")
+        .condition(Some("topic_id=$1"))
+        .build();
+    super::select(client, &sql, &count_sql, &[&topic_id], page).await
+}
+
+/// 获取文章的指定版本
+pub async fn find(client: &Client, topic_id: i64, id: i64) -> Result<TopicRevision> {
+    let sql = SelectStmt::builder()
+        .table("topic_revision")
+        .fields("id,topic_id,title,summary,md,tags,admin_id,dateline")
+        .condition(Some("topic_id=$1 AND id=$2"))
+        .limit(Some(1))
+        .build();
+    query_one(client, &sql, &[&topic_id, &id], Some("没有找到该版本")).await
+}
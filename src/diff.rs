@@ -0,0 +1,68 @@
+//! 文本的行差异
+
+use similar::{ChangeTag, TextDiff};
+
+/// 每个差异片段保留的上下文行数
+const CONTEXT_LINES: usize = 3;
+
+/// 差异中的一行
+pub struct DiffLine {
+    pub tag: ChangeTag,
+    /// 在旧文本中的行号，从1开始
+    pub old_no: Option<usize>,
+    /// 在新文本中的行号，从1开始
+    pub new_no: Option<usize>,
+    pub text: String,
+}
+impl DiffLine {
+    pub fn sign(&self) -> &'static str {
+        match self.tag {
+            ChangeTag::Delete => "-",
+            ChangeTag::Insert => "+",
+            ChangeTag::Equal => " ",
+        }
+    }
+    pub fn css_class(&self) -> &'static str {
+        match self.tag {
+            ChangeTag::Delete => "diff-delete",
+            ChangeTag::Insert => "diff-insert",
+            ChangeTag::Equal => "diff-equal",
+        }
+    }
+    pub fn old_no(&self) -> String {
+        line_no(self.old_no)
+    }
+    pub fn new_no(&self) -> String {
+        line_no(self.new_no)
+    }
+}
+
+fn line_no(no: Option<usize>) -> String {
+    match no {
+        Some(no) => no.to_string(),
+        None => "".to_string(),
+    }
+}
+
+/// 按行比较两段文本，返回带上下文的差异片段。文本相同时返回空列表
+pub fn lines(old: &str, new: &str) -> Vec<Vec<DiffLine>> {
+    let diff = TextDiff::from_lines(old, new);
+    diff.grouped_ops(CONTEXT_LINES)
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| DiffLine {
+                    tag: change.tag(),
+                    old_no: change.old_index().map(|i| i + 1),
+                    new_no: change.new_index().map(|i| i + 1),
+                    text: change
+                        .value()
+                        .trim_end_matches(&['\r', '\n'][..])
+                        .to_string(),
+                })
+                .collect()
+        })
+        .collect()
+}
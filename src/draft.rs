@@ -0,0 +1,48 @@
+//! 文章编辑器的自动保存
+//!
+//! 草稿保存在 redis 中，按管理员和文章ID区分，新建文章的ID为0。
+//! 文章保存成功后删除对应的草稿。
+
+use redis::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::AppError, rdb, time::now, Result};
+
+const PREFIX: &str = "axum_rs:draft:";
+/// 草稿的保存时间（秒）
+const EXPIRED: usize = 7 * 24 * 3600;
+
+#[derive(Serialize, Deserialize)]
+pub struct Draft {
+    pub md: String,
+    /// 保存时间
+    pub dateline: i32,
+}
+
+fn key(admin_id: i32, topic_id: i64) -> String {
+    format!("{}{}:{}", PREFIX, admin_id, topic_id)
+}
+
+/// 保存草稿，返回保存时间
+pub async fn save(client: &Client, admin_id: i32, topic_id: i64, md: &str) -> Result<i32> {
+    let draft = Draft {
+        md: md.to_string(),
+        dateline: now(),
+    };
+    let value = serde_json::to_string(&draft).map_err(AppError::from)?;
+    rdb::set(client, &key(admin_id, topic_id), &value, EXPIRED).await?;
+    Ok(draft.dateline)
+}
+
+pub async fn get(client: &Client, admin_id: i32, topic_id: i64) -> Result<Option<Draft>> {
+    match rdb::get(client, &key(admin_id, topic_id)).await? {
+        Some(value) => serde_json::from_str(&value)
+            .map(Some)
+            .map_err(AppError::from),
+        None => Ok(None),
+    }
+}
+
+pub async fn remove(client: &Client, admin_id: i32, topic_id: i64) -> Result<()> {
+    rdb::del(client, &key(admin_id, topic_id)).await
+}
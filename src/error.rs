@@ -4,7 +4,9 @@ use askama::Template;
 use axum::{
     http::StatusCode,
     response::{Html, IntoResponse, Response},
+    Json,
 };
+use serde_json::json;
 
 use crate::html::err::ErrTemplate;
 
@@ -20,6 +22,12 @@ pub enum AppErrorType {
     /// 模板
     Template,
     AuthError,
+    /// 没有权限
+    Forbidden,
+    /// 请求过于频繁
+    TooManyRequests,
+    /// 请求内容过大
+    PayloadTooLarge,
     RedisError,
     HttpError,
     JsonError,
@@ -30,6 +38,28 @@ pub enum AppErrorType {
     Common,
 }
 
+impl AppErrorType {
+    /// 错误代码。API 以此区分错误类型，一经发布不应修改
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppErrorType::DbError => "DB_ERROR",
+            AppErrorType::NotFound => "NOT_FOUND",
+            AppErrorType::IsExists => "IS_EXISTS",
+            AppErrorType::Template => "TEMPLATE_ERROR",
+            AppErrorType::AuthError => "AUTH_ERROR",
+            AppErrorType::Forbidden => "FORBIDDEN",
+            AppErrorType::TooManyRequests => "TOO_MANY_REQUESTS",
+            AppErrorType::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            AppErrorType::RedisError => "REDIS_ERROR",
+            AppErrorType::HttpError => "HTTP_ERROR",
+            AppErrorType::JsonError => "JSON_ERROR",
+            AppErrorType::ProtectedContentError => "PROTECTED_CONTENT_ERROR",
+            AppErrorType::Config => "CONFIG_ERROR",
+            AppErrorType::Common => "COMMON_ERROR",
+        }
+    }
+}
+
 /// 应用错误
 #[derive(Debug)]
 pub struct AppError {
@@ -87,8 +117,20 @@ impl AppError {
     pub fn auth_error(msg: &str) -> Self {
         Self::from_str(msg, AppErrorType::AuthError)
     }
-    fn status_code(&self) -> StatusCode {
+    pub fn forbidden(msg: &str) -> Self {
+        Self::from_str(msg, AppErrorType::Forbidden)
+    }
+    pub fn too_many_requests(msg: &str) -> Self {
+        Self::from_str(msg, AppErrorType::TooManyRequests)
+    }
+    pub fn payload_too_large(msg: &str) -> Self {
+        Self::from_str(msg, AppErrorType::PayloadTooLarge)
+    }
+    pub fn status_code(&self) -> StatusCode {
         match self.error_type {
+            AppErrorType::Forbidden => StatusCode::FORBIDDEN,
+            AppErrorType::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            AppErrorType::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
             AppErrorType::NotFound => StatusCode::NOT_FOUND,
             AppErrorType::DbError => StatusCode::INTERNAL_SERVER_ERROR,
             AppErrorType::Template => StatusCode::INTERNAL_SERVER_ERROR,
@@ -96,6 +138,27 @@ impl AppError {
             _ => StatusCode::BAD_REQUEST,
         }
     }
+    /// 展示给用户的错误信息
+    pub fn message(&self) -> String {
+        match self {
+            AppError {
+                message: Some(msg), ..
+            } => msg.clone(),
+            AppError {
+                error_type: AppErrorType::DbError,
+                ..
+            } => "数据库操作失败".to_string(),
+            AppError {
+                error_type: AppErrorType::NotFound,
+                ..
+            } => "没有找到".to_string(),
+            AppError {
+                error_type: AppErrorType::Template,
+                ..
+            } => "模板渲染出错".to_string(),
+            _ => "发生错误".to_string(),
+        }
+    }
 }
 impl std::error::Error for AppError {}
 impl std::fmt::Display for AppError {
@@ -142,25 +205,8 @@ impl From<config::ConfigError> for AppError {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let status_code = (&self).status_code();
-        let msg = match self {
-            AppError {
-                message: Some(msg), ..
-            } => msg.clone(),
-            AppError {
-                error_type: AppErrorType::DbError,
-                ..
-            } => "数据库操作失败".to_string(),
-            AppError {
-                error_type: AppErrorType::NotFound,
-                ..
-            } => "没有找到".to_string(),
-            AppError {
-                error_type: AppErrorType::Template,
-                ..
-            } => "模板渲染出错".to_string(),
-            _ => "发生错误".to_string(),
-        };
+        let status_code = self.status_code();
+        let msg = self.message();
         let tmpl = ErrTemplate {
             err: msg.to_string(),
         };
@@ -168,3 +214,27 @@ impl IntoResponse for AppError {
         (status_code, Html(msg)).into_response()
     }
 }
+
+/// 以JSON格式响应的应用错误，用于API
+#[derive(Debug)]
+pub struct ApiError(pub AppError);
+
+impl From<AppError> for ApiError {
+    fn from(err: AppError) -> Self {
+        Self(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status_code = match self.0.error_type {
+            AppErrorType::AuthError => StatusCode::UNAUTHORIZED,
+            _ => self.0.status_code(),
+        };
+        let body = json!({
+            "code": self.0.error_type.code(),
+            "message": self.0.message(),
+        });
+        (status_code, Json(body)).into_response()
+    }
+}
@@ -0,0 +1,189 @@
+//! 订阅源(Atom/RSS)
+
+use chrono::{TimeZone, Utc};
+
+use crate::{
+    config::WebConfig,
+    html::escape,
+    md::{self, Sanitizer},
+    model::SubjectTopicWithTagsAndTopicSummary,
+};
+
+/// 网站名称
+const SITE_NAME: &str = "AXUM中文网";
+
+/// 订阅源格式
+#[derive(Clone, Copy)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    /// 名称，用于生成缓存的键
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "atom",
+            FeedFormat::Rss => "rss",
+        }
+    }
+    /// 响应的`Content-Type`
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+            FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+        }
+    }
+}
+
+/// 订阅源
+pub struct Feed<'a> {
+    /// 标题
+    pub title: String,
+    /// 对应的页面路径，如`/subject/foo`
+    pub path: String,
+    /// 订阅源自身的路径，如`/subject/foo/feed.xml`
+    pub feed_path: String,
+    /// 条目
+    pub items: &'a [SubjectTopicWithTagsAndTopicSummary],
+}
+
+impl<'a> Feed<'a> {
+    /// 生成订阅源的标题
+    pub fn title(name: Option<&str>) -> String {
+        match name {
+            Some(name) => format!("{} - {}", name, SITE_NAME),
+            None => SITE_NAME.to_string(),
+        }
+    }
+
+    /// 按指定格式生成XML
+    pub fn render(&self, format: FeedFormat, cfg: &WebConfig, sanitizer: &Sanitizer) -> String {
+        match format {
+            FeedFormat::Atom => self.atom(cfg, sanitizer),
+            FeedFormat::Rss => self.rss(cfg, sanitizer),
+        }
+    }
+
+    /// 最后更新时间，即最新条目的时间
+    fn updated(&self) -> i64 {
+        self.items
+            .iter()
+            .map(|item| item.dateline as i64)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 生成Atom格式
+    fn atom(&self, cfg: &WebConfig, sanitizer: &Sanitizer) -> String {
+        let mut out = String::new();
+        out.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        out.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+        out.push_str(&format!("<title>{}</title>", escape(&self.title)));
+        out.push_str(&format!(
+            r#"<link href="{}" rel="self" type="application/atom+xml"/>"#,
+            escape(&cfg.url(&self.feed_path))
+        ));
+        out.push_str(&format!(
+            r#"<link href="{}" rel="alternate" type="text/html"/>"#,
+            escape(&cfg.url(&self.path))
+        ));
+        out.push_str(&format!("<id>{}</id>", escape(&cfg.url(&self.path))));
+        out.push_str(&format!("<updated>{}</updated>", rfc3339(self.updated())));
+        for item in self.items {
+            let url = cfg.url(&topic_path(item));
+            out.push_str("<entry>");
+            out.push_str(&format!("<title>{}</title>", escape(&item.title)));
+            out.push_str(&format!(
+                r#"<link href="{}" rel="alternate" type="text/html"/>"#,
+                escape(&url)
+            ));
+            out.push_str(&format!("<id>{}</id>", escape(&url)));
+            out.push_str(&format!(
+                "<published>{}</published>",
+                rfc3339(item.dateline as i64)
+            ));
+            out.push_str(&format!(
+                "<updated>{}</updated>",
+                rfc3339(item.dateline as i64)
+            ));
+            out.push_str(&format!(
+                "<author><name>{}</name></author>",
+                escape(&item.author)
+            ));
+            for tag in item.tag_names.iter() {
+                out.push_str(&format!(r#"<category term="{}"/>"#, escape(tag)));
+            }
+            out.push_str(&format!(
+                r#"<summary type="html">{}</summary>"#,
+                escape(&md::to_html(&item.summary, sanitizer))
+            ));
+            out.push_str("</entry>");
+        }
+        out.push_str("</feed>");
+        out
+    }
+
+    /// 生成RSS 2.0格式
+    fn rss(&self, cfg: &WebConfig, sanitizer: &Sanitizer) -> String {
+        let mut out = String::new();
+        out.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        out.push_str(r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/">"#);
+        out.push_str("<channel>");
+        out.push_str(&format!("<title>{}</title>", escape(&self.title)));
+        out.push_str(&format!("<link>{}</link>", escape(&cfg.url(&self.path))));
+        out.push_str(&format!(
+            "<description>{}</description>",
+            escape(&self.title)
+        ));
+        out.push_str(&format!(
+            r#"<atom:link href="{}" rel="self" type="application/rss+xml"/>"#,
+            escape(&cfg.url(&self.feed_path))
+        ));
+        out.push_str(&format!(
+            "<lastBuildDate>{}</lastBuildDate>",
+            rfc2822(self.updated())
+        ));
+        for item in self.items {
+            let url = cfg.url(&topic_path(item));
+            out.push_str("<item>");
+            out.push_str(&format!("<title>{}</title>", escape(&item.title)));
+            out.push_str(&format!("<link>{}</link>", escape(&url)));
+            out.push_str(&format!(
+                r#"<guid isPermaLink="true">{}</guid>"#,
+                escape(&url)
+            ));
+            out.push_str(&format!(
+                "<dc:creator>{}</dc:creator>",
+                escape(&item.author)
+            ));
+            out.push_str(&format!(
+                "<pubDate>{}</pubDate>",
+                rfc2822(item.dateline as i64)
+            ));
+            for tag in item.tag_names.iter() {
+                out.push_str(&format!("<category>{}</category>", escape(tag)));
+            }
+            out.push_str(&format!(
+                "<description>{}</description>",
+                escape(&md::to_html(&item.summary, sanitizer))
+            ));
+            out.push_str("</item>");
+        }
+        out.push_str("</channel></rss>");
+        out
+    }
+}
+
+/// 文章的路径
+fn topic_path(item: &SubjectTopicWithTagsAndTopicSummary) -> String {
+    format!("/topic/{}/{}", item.subject_slug, item.slug)
+}
+
+fn rfc3339(ts: i64) -> String {
+    Utc.timestamp(ts, 0).to_rfc3339()
+}
+
+fn rfc2822(ts: i64) -> String {
+    Utc.timestamp(ts, 0).to_rfc2822()
+}
@@ -1,17 +1,36 @@
 use serde::Deserialize;
 
+use crate::{
+    error::{AppError, AppErrorType},
+    model::{ProtectMode, TopicStatus},
+    role::Role,
+    time, Result,
+};
+
 #[derive(Deserialize, Debug)]
 pub struct CreateSubject {
     pub name: String,
     pub slug: String,
     pub summary: String,
+    /// 缺省为随机隐藏段落
+    #[serde(default)]
+    pub protect_mode: ProtectMode,
+    /// 随机隐藏的段落数，0 表示自动
+    #[serde(default)]
+    pub protect_count: i16,
 }
 #[derive(Deserialize)]
 pub struct UpdateSubject {
+    /// 通过 API 修改时，ID 来自路径
+    #[serde(default)]
     pub id: i32,
     pub name: String,
     pub slug: String,
     pub summary: String,
+    #[serde(default)]
+    pub protect_mode: ProtectMode,
+    #[serde(default)]
+    pub protect_count: i16,
 }
 #[derive(Deserialize)]
 pub struct CreateTag {
@@ -19,6 +38,8 @@ pub struct CreateTag {
 }
 #[derive(Deserialize)]
 pub struct UpdateTag {
+    /// 通过 API 修改时，ID 来自路径
+    #[serde(default)]
     pub id: i32,
     pub name: String,
 }
@@ -33,10 +54,32 @@ pub struct CreateTopic {
     pub author: String,
     pub md: String,
     pub tags: String,
+    /// 缺省为已发布
+    #[serde(default)]
+    pub status: TopicStatus,
+    /// 定时发布的时间，格式为`2022-01-01T08:00`
+    #[serde(default)]
+    pub publish_at: String,
+    /// 缺省使用专题的设置
+    #[serde(default)]
+    pub protect_mode: ProtectMode,
+    /// 随机隐藏的段落数，0 表示自动
+    #[serde(default)]
+    pub protect_count: i16,
+    /// 导入时 Front Matter 中的发布时间，不从表单读取
+    #[serde(skip)]
+    pub dateline: Option<i32>,
+}
+impl CreateTopic {
+    pub fn publish_at(&self) -> Result<i32> {
+        publish_at(self.status, &self.publish_at)
+    }
 }
 
 #[derive(Deserialize)]
 pub struct UpdateTopic {
+    /// 通过 API 修改时，ID 来自路径
+    #[serde(default)]
     pub id: i64,
     pub title: String,
     pub subject_id: i32,
@@ -46,18 +89,123 @@ pub struct UpdateTopic {
     pub author: String,
     pub md: String,
     pub tags: String,
+    /// 缺省为已发布
+    #[serde(default)]
+    pub status: TopicStatus,
+    /// 定时发布的时间，格式为`2022-01-01T08:00`
+    #[serde(default)]
+    pub publish_at: String,
+    /// 缺省使用专题的设置
+    #[serde(default)]
+    pub protect_mode: ProtectMode,
+    /// 随机隐藏的段落数，0 表示自动
+    #[serde(default)]
+    pub protect_count: i16,
+    /// 导入时 Front Matter 中的发布时间，不从表单读取
+    #[serde(skip)]
+    pub dateline: Option<i32>,
+}
+impl UpdateTopic {
+    pub fn publish_at(&self) -> Result<i32> {
+        publish_at(self.status, &self.publish_at)
+    }
+}
+/// 编辑器的实时预览
+#[derive(Deserialize)]
+pub struct PreviewTopic {
+    pub md: String,
+}
+/// 编辑器自动保存的草稿，新建文章时`topic_id`为0
+#[derive(Deserialize)]
+pub struct SaveDraft {
+    #[serde(default)]
+    pub topic_id: i64,
+    pub md: String,
+}
+#[derive(Deserialize)]
+pub struct CreateAdminToken {
+    pub name: String,
 }
 #[derive(Deserialize)]
 pub struct AdminLogin {
     pub username: String,
     pub password: String,
-    pub hcaptcha_response: String,
+    pub captcha_response: String,
 }
 #[derive(Deserialize)]
 pub struct GetProctedContent {
-    pub id: String,
+    pub topic_id: i64,
+    /// 以下为[`crate::protect::Token`]的内容
+    pub ids: String,
+    pub expires: i32,
+    pub sig: String,
+    pub response: String,
+    /// 在预览页面获取时，为预览链接的签名，见[`crate::preview`]
+    #[serde(default)]
+    pub preview_expires: i32,
+    #[serde(default)]
+    pub preview_sig: String,
+}
+
+/// 读者提交的评论
+#[derive(Deserialize)]
+pub struct CreateComment {
+    pub topic_id: i64,
+    /// 回复的评论，0 表示不是回复
+    #[serde(default)]
+    pub parent_id: i64,
+    pub nickname: String,
+    #[serde(default)]
+    pub email: String,
+    pub content: String,
     pub response: String,
-    pub hc:bool,
+}
+impl CreateComment {
+    pub fn parent_id(&self) -> Option<i64> {
+        if self.parent_id > 0 {
+            Some(self.parent_id)
+        } else {
+            None
+        }
+    }
+    /// 检查昵称、邮箱和内容的长度
+    pub fn validate(&self) -> Result<()> {
+        let nickname_len = self.nickname.trim().chars().count();
+        if nickname_len == 0 || nickname_len > 30 {
+            return Err(AppError::from_str(
+                "昵称不能为空，且不能超过30个字符",
+                AppErrorType::Common,
+            ));
+        }
+        if self.email.len() > 255 {
+            return Err(AppError::from_str("邮箱太长", AppErrorType::Common));
+        }
+        let content_len = self.content.trim().chars().count();
+        if content_len == 0 || content_len > 2000 {
+            return Err(AppError::from_str(
+                "评论内容不能为空，且不能超过2000个字符",
+                AppErrorType::Common,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 批量审核评论
+#[derive(Deserialize)]
+pub struct BatchComment {
+    /// 以逗号分隔的评论ID
+    pub ids: String,
+    /// `approve`、`reject` 或 `del`
+    pub action: String,
+}
+impl BatchComment {
+    pub fn ids(&self) -> Vec<i64> {
+        self.ids
+            .split(',')
+            .filter_map(|id| id.trim().parse().ok())
+            .collect()
+    }
 }
 
 #[derive(Deserialize)]
@@ -65,6 +213,26 @@ pub struct CreateAdmin {
     pub username: String,
     pub password: String,
     pub re_password: String,
+    #[serde(default)]
+    pub role: Role,
+}
+#[derive(Deserialize)]
+pub struct SetAdminRole {
+    pub role: Role,
+}
+/// 两步验证的验证码，也可以是恢复码
+#[derive(Deserialize)]
+pub struct TwoFactorCode {
+    pub code: String,
+}
+#[derive(Deserialize)]
+pub struct DisableTwoFactor {
+    pub password: String,
+    pub code: String,
+}
+#[derive(Deserialize)]
+pub struct RequireTwoFactor {
+    pub required: bool,
 }
 #[derive(Deserialize)]
 pub struct UpdateAdmin {
@@ -82,3 +250,15 @@ pub struct ChangeAdminPassword {
     pub new_password: String,
     pub re_password: String,
 }
+
+/// 解析发布时间。定时发布必须指定有效的时间
+fn publish_at(status: TopicStatus, publish_at: &str) -> Result<i32> {
+    match (status, time::parse_local(publish_at)) {
+        (_, Some(ts)) => Ok(ts),
+        (TopicStatus::Scheduled, None) => Err(AppError::from_str(
+            "请输入有效的发布时间",
+            AppErrorType::Common,
+        )),
+        (_, None) => Ok(0),
+    }
+}
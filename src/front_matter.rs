@@ -0,0 +1,197 @@
+//! 带 Front Matter 的 Markdown 文件与文章之间的转换
+//!
+//! 支持以`---`包围的 YAML 和以`+++`包围的 TOML，导出时使用 YAML。
+
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{AppError, AppErrorType},
+    form::{CreateTopic, UpdateTopic},
+    model::{ProtectMode, TopicStatus, TopicWithMdAndTagsForEdit},
+    time, Result,
+};
+
+const YAML_DELIMITER: &str = "---";
+const TOML_DELIMITER: &str = "+++";
+
+/// Front Matter
+#[derive(Serialize, Deserialize)]
+pub struct FrontMatter {
+    pub title: String,
+    /// 专题的固定链接
+    #[serde(default)]
+    pub subject: String,
+    pub slug: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub src: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 发布时间，如`2022-01-01 08:00:00`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dateline: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<TopicStatus>,
+    /// 定时发布的时间，如`2022-01-01 08:00:00`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<String>,
+}
+
+/// 带 Front Matter 的 Markdown 文件
+pub struct Document {
+    pub front_matter: FrontMatter,
+    pub md: String,
+}
+
+impl Document {
+    /// 解析 Markdown 文件
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim_start_matches('\u{feff}');
+        let delimiter = match text.lines().next().map(|line| line.trim_end()) {
+            Some(YAML_DELIMITER) => YAML_DELIMITER,
+            Some(TOML_DELIMITER) => TOML_DELIMITER,
+            _ => return Err(error("缺少 Front Matter")),
+        };
+        let rest = text[delimiter.len()..].trim_start_matches(&['\r', '\n'][..]);
+        let (head, md) = split_at_delimiter(rest, delimiter)
+            .ok_or_else(|| error("Front Matter 没有结束标记"))?;
+        let front_matter: FrontMatter = if delimiter == YAML_DELIMITER {
+            serde_yaml::from_str(head).map_err(|err| error(&err.to_string()))?
+        } else {
+            toml::from_str(head).map_err(|err| error(&err.to_string()))?
+        };
+        if front_matter.title.trim().is_empty() || front_matter.slug.trim().is_empty() {
+            return Err(error("Front Matter 中必须包含 title 和 slug"));
+        }
+        Ok(Self {
+            front_matter,
+            md: md.trim_start_matches(&['\r', '\n'][..]).to_string(),
+        })
+    }
+
+    /// 生成带 YAML Front Matter 的 Markdown 文件
+    pub fn render(&self) -> Result<String> {
+        let head = serde_yaml::to_string(&self.front_matter)
+            .map_err(|err| AppError::from_err(err, AppErrorType::Common))?;
+        Ok(format!(
+            "{}\n{}{}\n\n{}",
+            YAML_DELIMITER, head, YAML_DELIMITER, self.md
+        ))
+    }
+
+    /// 从文章生成
+    pub fn from_topic(topic: &TopicWithMdAndTagsForEdit, subject_slug: &str) -> Self {
+        let status = topic.status();
+        let publish_at = if status == TopicStatus::Scheduled {
+            Some(format_time(topic.publish_at))
+        } else {
+            None
+        };
+        Self {
+            front_matter: FrontMatter {
+                title: topic.title.clone(),
+                subject: subject_slug.to_string(),
+                slug: topic.slug.clone(),
+                summary: topic.summary.clone(),
+                author: topic.author.clone(),
+                src: topic.src.clone(),
+                tags: topic.tag_names.clone(),
+                dateline: Some(format_time(topic.dateline)),
+                status: Some(status),
+                publish_at,
+            },
+            md: topic.md.clone(),
+        }
+    }
+
+    /// 发布时间
+    pub fn dateline(&self) -> Result<Option<i32>> {
+        parse_time(&self.front_matter.dateline)
+    }
+
+    /// 转换成新建文章的表单。未指定状态时为已发布
+    pub fn to_create(&self, subject_id: i32) -> CreateTopic {
+        let fm = &self.front_matter;
+        CreateTopic {
+            subject_id,
+            title: fm.title.clone(),
+            slug: fm.slug.clone(),
+            summary: fm.summary.clone(),
+            src: fm.src.clone(),
+            author: fm.author.clone(),
+            md: self.md.clone(),
+            tags: fm.tags.join(","),
+            status: fm.status.unwrap_or_default(),
+            publish_at: fm.publish_at.clone().unwrap_or_default(),
+            protect_mode: ProtectMode::Inherit,
+            protect_count: 0,
+            dateline: None,
+        }
+    }
+
+    /// 转换成修改文章的表单。未指定状态时保留文章原有的状态
+    pub fn to_update(&self, existing: &TopicWithMdAndTagsForEdit) -> UpdateTopic {
+        let fm = &self.front_matter;
+        let (status, publish_at) = match fm.status {
+            Some(status) => (status, fm.publish_at.clone().unwrap_or_default()),
+            None => (existing.status(), existing.publish_at()),
+        };
+        UpdateTopic {
+            id: existing.id,
+            title: fm.title.clone(),
+            subject_id: existing.subject_id,
+            slug: fm.slug.clone(),
+            summary: fm.summary.clone(),
+            src: fm.src.clone(),
+            author: fm.author.clone(),
+            md: self.md.clone(),
+            tags: fm.tags.join(","),
+            status,
+            publish_at,
+            protect_mode: existing.protect_mode(),
+            protect_count: existing.protect_count,
+            dateline: None,
+        }
+    }
+
+    /// 导出时使用的文件名
+    pub fn file_name(&self) -> String {
+        format!("{}.md", self.front_matter.slug)
+    }
+}
+
+/// 找到单独成行的结束标记，返回标记前后的内容
+fn split_at_delimiter<'a>(text: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return Some((&text[..offset], &text[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn format_time(ts: i32) -> String {
+    Local
+        .timestamp(ts as i64, 0)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+fn parse_time(s: &Option<String>) -> Result<Option<i32>> {
+    match s {
+        Some(s) if !s.trim().is_empty() => time::parse_local(s)
+            .map(Some)
+            .ok_or_else(|| error(&format!("无效的时间：{}", s))),
+        _ => Ok(None),
+    }
+}
+
+fn error(msg: &str) -> AppError {
+    AppError::from_str(msg, AppErrorType::Common)
+}
@@ -0,0 +1,26 @@
+//! 管理员 JSON API，通过 API 令牌认证
+
+use axum::{
+    routing::{post, put},
+    Router,
+};
+
+pub mod subject;
+pub mod tag;
+pub mod topic;
+
+pub fn routers() -> Router {
+    Router::new()
+        .route("/subjects", post(subject::create))
+        .route(
+            "/subjects/:id",
+            put(subject::update).delete(subject::delete),
+        )
+        .route("/subjects/:id/restore", post(subject::restore))
+        .route("/tags", post(tag::create))
+        .route("/tags/:id", put(tag::update).delete(tag::delete))
+        .route("/tags/:id/restore", post(tag::restore))
+        .route("/topics", post(topic::create))
+        .route("/topics/:id", put(topic::update).delete(topic::delete))
+        .route("/topics/:id/restore", post(topic::restore))
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    audit::Audit,
+    cache,
+    db::subject,
+    form,
+    handler::helper::{get_client, log_error},
+    middleware::api_auth::ApiAuth,
+    model::{AppState, SubjectID},
+    role::Permission,
+    ApiResult,
+};
+
+pub async fn create(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+    auth: ApiAuth,
+    Json(cs): Json<form::CreateSubject>,
+) -> ApiResult<(StatusCode, Json<SubjectID>)> {
+    let handler_name = "api_admin_subject_create";
+    auth.require(Permission::ManageSubject)?;
+    let mut client = get_client(&state, handler_name).await?;
+    let id = subject::create(&mut client, &cs, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_SUBJECTS]).await;
+    Ok((StatusCode::CREATED, Json(id)))
+}
+
+pub async fn update(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+    auth: ApiAuth,
+    Path(id): Path<i32>,
+    Json(us): Json<form::UpdateSubject>,
+) -> ApiResult<StatusCode> {
+    let handler_name = "api_admin_subject_update";
+    auth.require(Permission::ManageSubject)?;
+    let us = form::UpdateSubject { id, ..us };
+    let mut client = get_client(&state, handler_name).await?;
+    subject::update(&mut client, &us, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_SUBJECTS]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+    auth: ApiAuth,
+    Path(id): Path<i32>,
+) -> ApiResult<StatusCode> {
+    let handler_name = "api_admin_subject_delete";
+    auth.require(Permission::ManageSubject)?;
+    let mut client = get_client(&state, handler_name).await?;
+    subject::delete(&mut client, id, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_SUBJECTS]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn restore(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+    auth: ApiAuth,
+    Path(id): Path<i32>,
+) -> ApiResult<StatusCode> {
+    let handler_name = "api_admin_subject_restore";
+    auth.require(Permission::ManageSubject)?;
+    let mut client = get_client(&state, handler_name).await?;
+    subject::restore(&mut client, id, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_SUBJECTS]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    audit::Audit,
+    cache,
+    db::tag,
+    form,
+    handler::helper::{get_client, log_error},
+    middleware::api_auth::ApiAuth,
+    model::{AppState, TagID},
+    role::Permission,
+    ApiResult,
+};
+
+pub async fn create(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+    auth: ApiAuth,
+    Json(ct): Json<form::CreateTag>,
+) -> ApiResult<(StatusCode, Json<TagID>)> {
+    let handler_name = "api_admin_tag_create";
+    auth.require(Permission::ManageTag)?;
+    let mut client = get_client(&state, handler_name).await?;
+    let id = tag::create(&mut client, &ct, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_TAGS]).await;
+    Ok((StatusCode::CREATED, Json(id)))
+}
+
+pub async fn update(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+    auth: ApiAuth,
+    Path(id): Path<i32>,
+    Json(ut): Json<form::UpdateTag>,
+) -> ApiResult<StatusCode> {
+    let handler_name = "api_admin_tag_update";
+    auth.require(Permission::ManageTag)?;
+    let ut = form::UpdateTag { id, ..ut };
+    let mut client = get_client(&state, handler_name).await?;
+    tag::update(&mut client, &ut, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_TAGS]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+    auth: ApiAuth,
+    Path(id): Path<i32>,
+) -> ApiResult<StatusCode> {
+    let handler_name = "api_admin_tag_delete";
+    auth.require(Permission::ManageTag)?;
+    let mut client = get_client(&state, handler_name).await?;
+    tag::del(&mut client, id, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_TAGS]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn restore(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+    auth: ApiAuth,
+    Path(id): Path<i32>,
+) -> ApiResult<StatusCode> {
+    let handler_name = "api_admin_tag_restore";
+    auth.require(Permission::ManageTag)?;
+    let mut client = get_client(&state, handler_name).await?;
+    tag::restore(&mut client, id, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_TAGS]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    audit::Audit,
+    db::topic,
+    form,
+    handler::helper::{
+        check_topic_owner, get_client, log_error, purge_topic_cache, topic_placement,
+    },
+    md,
+    middleware::api_auth::ApiAuth,
+    model::{AppState, TopicID},
+    role::Permission,
+    ApiResult,
+};
+
+pub async fn create(
+    Extension(state): Extension<Arc<AppState>>,
+    auth: ApiAuth,
+    audit: Audit,
+    Json(ct): Json<form::CreateTopic>,
+) -> ApiResult<(StatusCode, Json<TopicID>)> {
+    let handler_name = "api_admin_topic_create";
+    auth.require(Permission::WriteTopic)?;
+    let rendered = md::render(&ct.md, &state.sanitizer);
+    let mut client = get_client(&state, handler_name).await?;
+    let id = topic::create(&mut client, &ct, &rendered.html, &rendered.toc, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    purge_topic_cache(&state.cache, &client, id.id, None).await;
+    Ok((StatusCode::CREATED, Json(id)))
+}
+
+pub async fn update(
+    Extension(state): Extension<Arc<AppState>>,
+    auth: ApiAuth,
+    audit: Audit,
+    Path(id): Path<i64>,
+    Json(ut): Json<form::UpdateTopic>,
+) -> ApiResult<StatusCode> {
+    let handler_name = "api_admin_topic_update";
+    auth.require(Permission::WriteTopic)?;
+    let ut = form::UpdateTopic { id, ..ut };
+    let rendered = md::render(&ut.md, &state.sanitizer);
+    let mut client = get_client(&state, handler_name).await?;
+    check_topic_owner(&client, auth.admin_id, auth.role, id).await?;
+    let before = topic_placement(&client, id).await;
+    topic::update(&mut client, &ut, &rendered.html, &rendered.toc, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    purge_topic_cache(&state.cache, &client, id, before).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete(
+    Extension(state): Extension<Arc<AppState>>,
+    auth: ApiAuth,
+    audit: Audit,
+    Path(id): Path<i64>,
+) -> ApiResult<StatusCode> {
+    let handler_name = "api_admin_topic_delete";
+    auth.require(Permission::ManageTopic)?;
+    let mut client = get_client(&state, handler_name).await?;
+    let (topic_rows, topic_tag_rows) = topic::del_or_restore(&mut client, id, true, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    purge_topic_cache(&state.cache, &client, id, None).await;
+    tracing::debug!(
+        "删除文章数：{}, 删除关联标签数：{}",
+        topic_rows,
+        topic_tag_rows
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn restore(
+    Extension(state): Extension<Arc<AppState>>,
+    auth: ApiAuth,
+    audit: Audit,
+    Path(id): Path<i64>,
+) -> ApiResult<StatusCode> {
+    let handler_name = "api_admin_topic_restore";
+    auth.require(Permission::ManageTopic)?;
+    let mut client = get_client(&state, handler_name).await?;
+    let (topic_rows, topic_tag_rows) = topic::del_or_restore(&mut client, id, false, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    purge_topic_cache(&state.cache, &client, id, None).await;
+    tracing::debug!(
+        "还原文章数：{}, 还原关联标签数：{}",
+        topic_rows,
+        topic_tag_rows
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,25 @@
+//! JSON API
+
+use axum::{extract::extractor_middleware, routing::get, Router};
+
+use crate::middleware::api_auth::ApiAuth;
+
+pub mod admin;
+pub mod subject;
+pub mod tag;
+pub mod topic;
+
+pub fn routers() -> Router {
+    Router::new()
+        .route("/subjects", get(subject::index))
+        .route("/subjects/:slug", get(subject::detail))
+        .route("/subjects/:slug/topics", get(subject::topics))
+        .route("/topics", get(topic::index))
+        .route("/topics/:subject_slug/:slug", get(topic::detail))
+        .route("/tags", get(tag::index))
+        .route("/tags/:name/topics", get(tag::topics))
+        .nest(
+            "/admin",
+            admin::routers().layer(extractor_middleware::<ApiAuth>()),
+        )
+}
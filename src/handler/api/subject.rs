@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path, Query},
+    Json,
+};
+
+use crate::{
+    db::{pagination::Pagination, subject, topic},
+    handler::{
+        frontend::{subject::TOPICS_CONDITION, PaginationArgs},
+        helper::{get_client, log_error},
+    },
+    model::{AppState, Subject, SubjectTopicWithTagsAndTopicSummary},
+    ApiResult,
+};
+
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    page: Option<Query<PaginationArgs>>,
+) -> ApiResult<Json<Pagination<Vec<Subject>>>> {
+    let page = match page {
+        Some(arg) => arg.page,
+        None => 0,
+    };
+    let handler_name = "api_subject_index";
+    let client = get_client(&state, handler_name).await?;
+    let list = subject::select_with_summary(&client, Some("is_del=false"), &[], page)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(list))
+}
+
+pub async fn detail(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> ApiResult<Json<Subject>> {
+    let handler_name = "api_subject_detail";
+    let client = get_client(&state, handler_name).await?;
+    let subj = subject::find(&client, Some("slug=$1 AND is_del=false"), &[&slug])
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(subj))
+}
+
+pub async fn topics(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(slug): Path<String>,
+    page: Option<Query<PaginationArgs>>,
+) -> ApiResult<Json<Pagination<Vec<SubjectTopicWithTagsAndTopicSummary>>>> {
+    let page = match page {
+        Some(arg) => arg.page,
+        None => 0,
+    };
+    let handler_name = "api_subject_topics";
+    let client = get_client(&state, handler_name).await?;
+    subject::find(&client, Some("slug=$1 AND is_del=false"), &[&slug])
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let list = topic::select_with_summary(
+        &client,
+        Some(TOPICS_CONDITION),
+        &[&slug],
+        Some("id ASC"),
+        page,
+    )
+    .await
+    .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(list))
+}
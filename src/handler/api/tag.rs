@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path, Query},
+    Json,
+};
+
+use crate::{
+    db::{pagination::Pagination, tag, topic},
+    handler::{
+        frontend::{tag::TOPICS_CONDITION, PaginationArgs},
+        helper::{get_client, log_error},
+    },
+    model::{AppState, SubjectTopicWithTagsAndTopicSummary, Tag},
+    ApiResult,
+};
+
+pub async fn index(Extension(state): Extension<Arc<AppState>>) -> ApiResult<Json<Vec<Tag>>> {
+    let handler_name = "api_tag_index";
+    let client = get_client(&state, handler_name).await?;
+    let tags = tag::all(&client)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(tags))
+}
+
+pub async fn topics(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(name): Path<String>,
+    page: Option<Query<PaginationArgs>>,
+) -> ApiResult<Json<Pagination<Vec<SubjectTopicWithTagsAndTopicSummary>>>> {
+    let page = match page {
+        Some(arg) => arg.page,
+        None => 0,
+    };
+    let handler_name = "api_tag_topics";
+    let client = get_client(&state, handler_name).await?;
+    tag::find(&client, Some("name=$1 AND is_del=false"), &[&name])
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let list = topic::select_with_summary(
+        &client,
+        Some(TOPICS_CONDITION),
+        &[&name],
+        Some("id ASC"),
+        page,
+    )
+    .await
+    .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(list))
+}
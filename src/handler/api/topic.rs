@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path, Query},
+    Json,
+};
+
+use crate::{
+    db::{pagination::Pagination, topic},
+    handler::{
+        frontend::{topic::TopicArgs, PaginationArgs},
+        helper::{get_client, log_error, topic_detail},
+    },
+    model::{AppState, ProtectedTopicDetail, SubjectTopicWithTagsAndTopicSummary, TopicDetail},
+    protect, ApiResult,
+};
+
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    page: Option<Query<PaginationArgs>>,
+) -> ApiResult<Json<Pagination<Vec<SubjectTopicWithTagsAndTopicSummary>>>> {
+    let page = match page {
+        Some(arg) => arg.page,
+        None => 0,
+    };
+    let handler_name = "api_topic_index";
+    let client = get_client(&state, handler_name).await?;
+    let list = topic::select_with_summary(&client, None, &[], Some("id DESC"), page)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(list))
+}
+
+pub async fn detail(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(arg): Path<TopicArgs>,
+) -> ApiResult<Json<ProtectedTopicDetail>> {
+    let TopicArgs { subject_slug, slug } = arg;
+    let handler_name = "api_topic_detail";
+    let result = topic_detail(&state, &subject_slug, &slug, handler_name).await?;
+    Ok(Json(hide_protected(&state.web_cfg.secret_key, result)))
+}
+
+/// 与前台页面一样隐藏部分内容，客户端凭令牌通过人机验证后获取
+fn hide_protected(secret_key: &str, mut topic: TopicDetail) -> ProtectedTopicDetail {
+    let (html, protected) =
+        protect::hide(secret_key, topic.id, &topic.html, topic.protect_policy());
+    topic.html = html;
+    ProtectedTopicDetail { topic, protected }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ProtectMode;
+
+    fn topic(html: &str) -> TopicDetail {
+        TopicDetail {
+            id: 1,
+            subject_id: 1,
+            title: "标题".to_string(),
+            slug: "slug".to_string(),
+            author: "axum.rs".to_string(),
+            src: "axum.rs".to_string(),
+            html: html.to_string(),
+            tag_names: vec![],
+            subject_slug: "subject".to_string(),
+            dateline: 0,
+            hit: 0,
+            subject_name: "专题".to_string(),
+            protect_mode: ProtectMode::Explicit.code(),
+            protect_count: 0,
+            toc: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn response_does_not_contain_protected_text() {
+        let html = format!(
+            "<p>公开的内容</p>\n{}<p>只有通过验证才能看到</p>\n{}",
+            protect::BEGIN_MARKER,
+            protect::END_MARKER
+        );
+        let json = serde_json::to_string(&hide_protected("secret", topic(&html))).unwrap();
+        assert!(json.contains("公开的内容"));
+        assert!(!json.contains("只有通过验证才能看到"));
+        assert!(json.contains("protected-1"));
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["protected"]["topic_id"], 1);
+        assert_eq!(value["protected"]["ids"], "1");
+    }
+
+    #[test]
+    fn response_without_protected_content_has_no_token() {
+        let detail = hide_protected("secret", topic("<p>公开的内容</p>\n"));
+        assert!(detail.protected.is_none());
+        assert_eq!(detail.topic.html, "<p>公开的内容</p>\n");
+    }
+}
@@ -1,23 +1,27 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
 use axum::{
-    extract::{Extension, Form},
-    http::{HeaderMap, StatusCode},
+    extract::{ConnectInfo, Extension, Form},
+    http::{header::SET_COOKIE, HeaderMap, StatusCode},
     response::Html,
 };
 use serde_json::json;
 
 use crate::{
-    db::admin,
-    error::AppError,
+    audit::{self, Action, Audit, Entry, Target},
+    db::{admin, audit_log},
+    error::{AppError, AppErrorType},
     form,
     handler::helper::{get_client, get_cookie, log_error},
-    hcaptcha,
-    html::auth::LoginTemplate,
-    model::{AdminSession, AppState},
-    password, rdb,
-    session::{self, gen_redis_key},
+    html::auth::{LoginTemplate, RecoveryCodesTemplate, TwoFactorTemplate},
+    login_guard,
+    model::{Admin, AdminSession, AppState},
+    password,
+    role::Role,
+    session,
     time::now,
+    totp,
+    two_factor::{self, PendingLogin},
     Result,
 };
 
@@ -25,64 +29,313 @@ use super::{helper::render, redirect::redirect_with_cookie};
 
 pub async fn admin_login_ui(Extension(state): Extension<Arc<AppState>>) -> Result<Html<String>> {
     let handler_name = "admin_login_ui";
-    let site_key = state.hcap_cfg.site_key.clone();
-    let tmpl = LoginTemplate { site_key };
+    let tmpl = LoginTemplate {
+        captcha: state.captcha.widget(),
+    };
     render(tmpl, handler_name)
 }
 pub async fn admin_login(
     Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Form(login): Form<form::AdminLogin>,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "auth_login";
-    let is_valid = hcaptcha::verify(
-        login.hcaptcha_response.clone(),
-        state.hcap_cfg.secret_key.clone(),
-    )
-    .await?;
+    let guard_cfg = &state.login_guard_cfg;
+    let ip = audit::client_ip(&headers, Some(peer), &state.trusted_proxies)
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+    login_guard::check(&state.rdc, &ip, &login.username).await?;
+    let is_valid = state.captcha.verify(&login.captcha_response).await?;
     if !is_valid {
         return Err(AppError::auth_error("人机验证失败"));
     }
+    // 失败次数越多，等待越久
+    let delay = login_guard::delay(&state.rdc, guard_cfg, &ip, &login.username).await?;
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
     let client = get_client(&state, handler_name).await?;
-    let login_admin = admin::find(&client, &login.username)
-        .await
-        .map_err(log_error(handler_name.to_string()))?;
-    tracing::debug!("{:?}", password::hash(&login.password));
-    if !password::verify(&login.password, &login_admin.password)? {
-        return Err(AppError::auth_error("用户名或密码错误"));
+    let login_admin = match admin::find(&client, &login.username).await {
+        Ok(item) => Some(item),
+        Err(err) if matches!(err.error_type, AppErrorType::NotFound) => None,
+        Err(err) => return Err(log_error(handler_name.to_string())(err)),
+    };
+    // 用户名不存在时同样进行一次密码校验，响应时间不会泄露用户名是否存在
+    let is_valid = match &login_admin {
+        Some(item) => password::verify(&login.password, &item.password)?,
+        None => password::verify_dummy(&login.password)?,
+    };
+    let login_admin = match login_admin {
+        Some(item) if is_valid => item,
+        item => {
+            let locked =
+                login_guard::record_failure(&state.rdc, guard_cfg, &ip, &login.username).await?;
+            // 用户名不存在时对象 ID 为 0，记录下来便于发现撞库
+            let entry = Entry::new(
+                Action::LoginFailed,
+                Target::Admin,
+                item.as_ref().map(|item| item.id as i64).unwrap_or(0),
+                &login.username,
+            )
+            .after(json!({
+                "reason": if item.is_some() { "wrong_password" } else { "unknown_username" },
+                "locked": locked,
+            }));
+            audit_log::create(
+                &**client,
+                &Audit::anonymous(&headers, Some(peer), &state.trusted_proxies),
+                &entry,
+            )
+            .await
+            .map_err(log_error(handler_name.to_string()))?;
+            // 不区分用户名不存在和密码错误，避免泄露用户名
+            return Err(AppError::auth_error("用户名或密码错误"));
+        }
+    };
+    login_guard::clear(&state.rdc, &login_admin.username).await?;
+    let cfg = &state.sess_cfg;
+    // 启用了两步验证，或被要求启用时，先进入第二步
+    if login_admin.totp_enabled
+        || two_factor::is_required(&**client)
+            .await
+            .map_err(log_error(handler_name.to_string()))?
+    {
+        let pending = PendingLogin {
+            admin_id: login_admin.id,
+            secret: if login_admin.totp_enabled {
+                String::new()
+            } else {
+                totp::generate_secret()
+            },
+        };
+        let id = two_factor::create_pending(&state.rdc, &pending).await?;
+        return redirect_with_cookie("/login/2fa", Some(&two_factor::pending_cookie(cfg, &id)));
     }
-    let cfg = state.sess_cfg.clone();
-    let data = json!(AdminSession {
+    let cookie = create_session(&state, &headers, peer, login_admin).await?;
+    redirect_with_cookie("/admin", Some(&cookie))
+}
+
+/// 创建登录会话，返回会话的 Cookie
+async fn create_session(
+    state: &AppState,
+    headers: &HeaderMap,
+    peer: SocketAddr,
+    login_admin: Admin,
+) -> Result<String> {
+    let cfg = &state.sess_cfg;
+    // 登录前的会话 ID 作废，防止会话固定攻击
+    if let Some(old_id) = get_cookie(headers, &cfg.id_name) {
+        session::destroy(&state.rdc, cfg, &old_id).await?;
+    }
+    let now = now();
+    let audit = Audit::new(login_admin.id, headers, Some(peer), &state.trusted_proxies);
+    let admin_session = AdminSession {
         id: login_admin.id,
+        role: Role::from_code(login_admin.role),
         username: login_admin.username,
-        dateline: now() + cfg.expired as i32,
-        password: login_admin.password,
         is_sys: login_admin.is_sys,
-    });
-    let data = data.to_string();
-    tracing::debug!("data: {:?}", data);
-    let session::GeneratedKey {
-        id,
-        cookie_key,
-        redis_key,
-    } = session::gen_key(&cfg);
-    tracing::debug!("{}", &redis_key);
-    rdb::set(&state.rdc, &redis_key, &data, cfg.expired)
+        dateline: now + cfg.expired as i32,
+        login_at: now,
+        active_at: now,
+        ip: audit.ip,
+        user_agent: audit.user_agent,
+    };
+    let id = session::create(&state.rdc, cfg, &admin_session).await?;
+    Ok(session::cookie(cfg, &id))
+}
+
+/// 取出待验证的登录及对应的管理员
+async fn pending_login(
+    state: &AppState,
+    client: &tokio_postgres::Client,
+    headers: &HeaderMap,
+) -> Result<(String, PendingLogin, Admin)> {
+    let expired = || AppError::auth_error("验证已过期，请重新登录");
+    let id = two_factor::pending_id(&state.sess_cfg, headers).ok_or_else(expired)?;
+    let pending = two_factor::load_pending(&state.rdc, &id)
+        .await?
+        .ok_or_else(expired)?;
+    let item = admin::find_by_id(client, pending.admin_id).await?;
+    if item.is_del {
+        return Err(expired());
+    }
+    Ok((id, pending, item))
+}
+
+/// 输入验证码的页面。需要启用两步验证时同时显示二维码
+pub async fn admin_two_factor_ui(
+    Extension(state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Html<String>> {
+    let handler_name = "admin_two_factor_ui";
+    let client = get_client(&state, handler_name).await?;
+    let (_, pending, item) = pending_login(&state, &client, &headers).await?;
+    let qr_code = if pending.is_enroll() {
+        totp::qr_code(&totp::provisioning_uri(&item.username, &pending.secret))
+    } else {
+        String::new()
+    };
+    let tmpl = TwoFactorTemplate {
+        secret: pending.secret,
+        qr_code,
+    };
+    render(tmpl, handler_name)
+}
+
+/// 第二步验证失败，计入登录失败次数并记录日志
+async fn two_factor_failed(
+    state: &AppState,
+    client: &tokio_postgres::Client,
+    headers: &HeaderMap,
+    peer: SocketAddr,
+    pending_id: &str,
+    attempt: u8,
+    item: &Admin,
+) -> Result<AppError> {
+    let ip = audit::client_ip(headers, Some(peer), &state.trusted_proxies)
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+    let locked =
+        login_guard::record_failure(&state.rdc, &state.login_guard_cfg, &ip, &item.username)
+            .await?;
+    let entry = Entry::new(
+        Action::LoginFailed,
+        Target::Admin,
+        item.id as i64,
+        &item.username,
+    )
+    .after(json!({ "reason": "wrong_two_factor_code", "locked": locked }));
+    audit_log::create(
+        client,
+        &Audit::anonymous(headers, Some(peer), &state.trusted_proxies),
+        &entry,
+    )
+    .await?;
+    let exhausted = two_factor::pending_failed(&state.rdc, pending_id, attempt).await?;
+    Ok(if exhausted || locked {
+        AppError::auth_error("验证码错误次数过多，请重新登录")
+    } else {
+        AppError::auth_error("验证码错误")
+    })
+}
+
+/// 输入验证码或恢复码完成登录
+pub async fn admin_two_factor(
+    Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(frm): Form<form::TwoFactorCode>,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "auth_two_factor";
+    let client = get_client(&state, handler_name).await?;
+    let (pending_id, pending, item) = pending_login(&state, &client, &headers).await?;
+    if pending.is_enroll() {
+        return Err(AppError::auth_error("请先启用两步验证"));
+    }
+    let ip = audit::client_ip(&headers, Some(peer), &state.trusted_proxies)
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+    login_guard::check(&state.rdc, &ip, &item.username).await?;
+    let attempt = two_factor::take_attempt(&state.rdc, &pending_id)
+        .await?
+        .ok_or_else(|| AppError::auth_error("验证码错误次数过多，请重新登录"))?;
+    let is_valid = two_factor::verify(&client, &item, &frm.code)
         .await
-        .map_err(AppError::from)?;
-    let cookie = format!("{}={}", cookie_key, id);
-    redirect_with_cookie("/admin", Some(&cookie))
+        .map_err(log_error(handler_name.to_string()))?;
+    if !is_valid {
+        return Err(two_factor_failed(
+            &state,
+            &client,
+            &headers,
+            peer,
+            &pending_id,
+            attempt,
+            &item,
+        )
+        .await?);
+    }
+    two_factor::destroy_pending(&state.rdc, &pending_id).await?;
+    let cookie = create_session(&state, &headers, peer, item).await?;
+    let (status, mut header, _) = redirect_with_cookie("/admin", Some(&cookie))?;
+    header.append(
+        SET_COOKIE,
+        two_factor::clear_pending_cookie(&state.sess_cfg)
+            .parse()
+            .unwrap(),
+    );
+    Ok((status, header, ()))
+}
+
+/// 登录时启用两步验证。恢复码只在启用后显示一次
+pub async fn admin_two_factor_enroll(
+    Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(frm): Form<form::TwoFactorCode>,
+) -> Result<(HeaderMap, Html<String>)> {
+    let handler_name = "auth_two_factor_enroll";
+    let mut client = get_client(&state, handler_name).await?;
+    let (pending_id, pending, item) = pending_login(&state, &client, &headers).await?;
+    if !pending.is_enroll() {
+        return Err(AppError::auth_error("已经启用两步验证"));
+    }
+    let ip = audit::client_ip(&headers, Some(peer), &state.trusted_proxies)
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+    login_guard::check(&state.rdc, &ip, &item.username).await?;
+    let attempt = two_factor::take_attempt(&state.rdc, &pending_id)
+        .await?
+        .ok_or_else(|| AppError::auth_error("验证码错误次数过多，请重新登录"))?;
+    let step = match totp::verify(&pending.secret, frm.code.trim(), now() as i64) {
+        Some(step) => step,
+        None => {
+            return Err(two_factor_failed(
+                &state,
+                &client,
+                &headers,
+                peer,
+                &pending_id,
+                attempt,
+                &item,
+            )
+            .await?)
+        }
+    };
+    let codes = totp::generate_recovery_codes();
+    let code_hashes: Vec<String> = codes.iter().map(|c| totp::hash_recovery_code(c)).collect();
+    let audit = Audit::new(item.id, &headers, Some(peer), &state.trusted_proxies);
+    admin::enable_two_factor(
+        &mut client,
+        item.id,
+        &pending.secret,
+        step,
+        &code_hashes,
+        &audit,
+    )
+    .await
+    .map_err(log_error(handler_name.to_string()))?;
+    two_factor::destroy_pending(&state.rdc, &pending_id).await?;
+    let cookie = create_session(&state, &headers, peer, item).await?;
+    let mut header = HeaderMap::new();
+    header.append(SET_COOKIE, cookie.parse().unwrap());
+    header.append(
+        SET_COOKIE,
+        two_factor::clear_pending_cookie(&state.sess_cfg)
+            .parse()
+            .unwrap(),
+    );
+    let tmpl = RecoveryCodesTemplate { codes };
+    Ok((header, render(tmpl, handler_name)?))
 }
 
 pub async fn admin_logout(
     Extension(state): Extension<Arc<AppState>>,
     headers: HeaderMap,
 ) -> Result<(StatusCode, HeaderMap, ())> {
-    let cfg = state.sess_cfg.clone();
-    let cookie = get_cookie(&headers, &cfg.id_name);
-    if let Some(val) = cookie {
-        let redis_key = gen_redis_key(&cfg, &val);
-        rdb::del(&state.rdc, &redis_key).await?;
+    let cfg = &state.sess_cfg;
+    if let Some(id) = get_cookie(&headers, &cfg.id_name) {
+        session::destroy(&state.rdc, cfg, &id).await?;
     }
-    let cookie_logout = format!("{}=", &cfg.id_name);
-    redirect_with_cookie("/login", Some(&cookie_logout))
+    redirect_with_cookie("/login", Some(&session::clear_cookie(cfg)))
 }
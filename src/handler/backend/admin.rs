@@ -1,15 +1,20 @@
 use crate::{
     arg,
-    db::admin,
+    audit::{Action, Audit, Entry, Target},
+    db::{admin, audit_log},
     error::AppError,
-    form::{CreateAdmin, UpdateAdmin},
+    form::{CreateAdmin, SetAdminRole, UpdateAdmin},
     handler::{
-        helper::{get_client, log_error, render},
+        helper::{get_client, get_cookie, log_error, render},
         redirect::redirect,
     },
     html::backend::admin::{AddTemplate, EditTemplate, IndexTemplate},
-    model::AppState,
-    password, Result,
+    login_guard,
+    middleware::csrf::CsrfToken,
+    model::{Admin, AdminSession, AppState},
+    password,
+    role::{Permission, Role},
+    session, Result,
 };
 use axum::{
     extract::{Extension, Form, Path, Query},
@@ -18,16 +23,20 @@ use axum::{
 };
 use std::sync::Arc;
 
-use super::get_logined_admin;
+use super::current_admin;
 
-pub async fn add() -> Result<Html<String>> {
+pub async fn add(csrf: CsrfToken) -> Result<Html<String>> {
     let handler_name = "backend_admin_add";
-    let tmpl = AddTemplate {};
+    let tmpl = AddTemplate {
+        roles: Role::ALL,
+        csrf_token: csrf.0,
+    };
     render(tmpl, handler_name)
 }
 pub async fn add_action(
     Extension(state): Extension<Arc<AppState>>,
     Form(ca): Form<CreateAdmin>,
+    audit: Audit,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_admin_add_action";
     if ca.password.is_empty() {
@@ -42,10 +51,10 @@ pub async fn add_action(
             crate::error::AppErrorType::Common,
         ));
     }
-    let client = get_client(&state, handler_name).await?;
+    let mut client = get_client(&state, handler_name).await?;
     let mut ca = CreateAdmin { ..ca };
     ca.password = password::hash(&ca.password)?;
-    admin::create(&client, ca)
+    admin::create(&mut client, ca, Some(&audit))
         .await
         .map_err(log_error(handler_name.to_string()))?;
     redirect("/admin/admin?msg=账号添加成功")
@@ -53,6 +62,7 @@ pub async fn add_action(
 pub async fn index(
     Extension(state): Extension<Arc<AppState>>,
     args: Option<Query<arg::BackendQueryArg>>,
+    csrf: CsrfToken,
 ) -> Result<Html<String>> {
     let handler_name = "backend_admin_index";
     let args = args.unwrap();
@@ -66,29 +76,61 @@ pub async fn index(
     )
     .await
     .map_err(log_error(handler_name.to_string()))?;
+    let mut locked = vec![];
+    for item in admin_list.data.iter() {
+        if login_guard::is_locked(&state.rdc, &item.username)
+            .await
+            .map_err(log_error(handler_name.to_string()))?
+        {
+            locked.push(item.id);
+        }
+    }
     let tmpl = IndexTemplate {
         list: admin_list,
         arg: args.0,
+        locked,
+        csrf_token: csrf.0,
     };
     render(tmpl, handler_name)
 }
 
+/// 没有“管理账号”权限的管理员只能修改自己的账号。系统账号只能由自己修改
+fn check_self_or_manage(admin_session: &AdminSession, item: &Admin) -> Result<()> {
+    if admin_session.id == item.id {
+        return Ok(());
+    }
+    if item.is_sys {
+        return Err(AppError::forbidden("系统账号只能由自己修改"));
+    }
+    admin_session.role.require(Permission::ManageAdmin)
+}
+
 pub async fn edit(
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i32>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
 ) -> Result<Html<String>> {
     let handler_name = "backend_admin_edit";
+    let admin_session = current_admin(&state, &headers).await?;
     let client = get_client(&state, handler_name).await?;
     let item = admin::find_by_id(&client, id)
         .await
         .map_err(log_error(handler_name.to_string()))?;
-    let tmpl = EditTemplate { admin: item };
+    check_self_or_manage(&admin_session, &item)?;
+    let tmpl = EditTemplate {
+        can_set_role: can_set_role(&admin_session, &item),
+        admin: item,
+        roles: Role::ALL,
+        csrf_token: csrf.0,
+    };
     render(tmpl, handler_name)
 }
 pub async fn edit_action(
     Extension(state): Extension<Arc<AppState>>,
     Form(ua): Form<UpdateAdmin>,
     headers: HeaderMap,
+    audit: Audit,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_admin_edit_action";
     if ua.new_password.is_empty() {
@@ -103,39 +145,204 @@ pub async fn edit_action(
             crate::error::AppErrorType::Common,
         ));
     }
-    let admin_session = get_logined_admin(&state, &headers).await?;
-    if admin_session.is_none() {
-        return Err(AppError::auth_error("UNAUTHENTICATED"));
-    }
-    let admin_session = admin_session.unwrap();
-    if !password::verify(&ua.password, &admin_session.password)? {
+    let admin_session = current_admin(&state, &headers).await?;
+    let mut client = get_client(&state, handler_name).await?;
+    let item = admin::find_by_id(&client, ua.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    check_self_or_manage(&admin_session, &item)?;
+    // 会话中不保存密码，需要从数据库读取当前管理员的密码
+    let current = admin::find_by_id(&client, admin_session.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    if !password::verify(&ua.password, &current.password)? {
         return Err(AppError::auth_error("你输入的密码错误"));
     };
+    let id = ua.id;
     let mut ua = UpdateAdmin { ..ua };
     ua.new_password = password::hash(&ua.new_password)?;
-    let client = get_client(&state, handler_name).await?;
-    admin::update(&client, ua).await?;
+    admin::update(&mut client, ua, &audit).await?;
+    // 修改密码后，该账号在其它地方的登录全部失效
+    let revoked = if id == admin_session.id {
+        let current_id = get_cookie(&headers, &state.sess_cfg.id_name).unwrap_or_default();
+        session::revoke_others(&state.rdc, &state.sess_cfg, id, &current_id).await
+    } else {
+        session::revoke_all(&state.rdc, &state.sess_cfg, id).await
+    };
+    revoked.map_err(log_error(handler_name.to_string()))?;
     redirect("/admin/admin?msg=修改成功")
 }
+/// 设置角色、删除和恢复只能针对能修改的其它账号。不能操作自己的账号，以免失去管理权限
+fn check_manage_other(admin_session: &AdminSession, item: &Admin) -> Result<()> {
+    if admin_session.id == item.id {
+        return Err(AppError::forbidden("不能修改自己的角色或删除自己的账号"));
+    }
+    check_self_or_manage(admin_session, item)
+}
+fn can_set_role(admin_session: &AdminSession, item: &Admin) -> bool {
+    check_manage_other(admin_session, item).is_ok()
+}
+
+/// 设置角色。注销该管理员的所有会话，使新角色立即生效
+pub async fn set_role(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Form(frm): Form<SetAdminRole>,
+    headers: HeaderMap,
+    audit: Audit,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_admin_set_role";
+    let admin_session = current_admin(&state, &headers).await?;
+    let mut client = get_client(&state, handler_name).await?;
+    let item = admin::find_by_id(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    check_manage_other(&admin_session, &item)?;
+    admin::set_role(&mut client, id, frm.role, Some(&audit))
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    session::revoke_all(&state.rdc, &state.sess_cfg, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    redirect("/admin/admin?msg=角色修改成功")
+}
 pub async fn del(
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i32>,
+    headers: HeaderMap,
+    audit: Audit,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_admin_del";
-    let client = get_client(&state, handler_name).await?;
-    admin::del_or_restore(&client, id, true)
+    let admin_session = current_admin(&state, &headers).await?;
+    let mut client = get_client(&state, handler_name).await?;
+    let item = admin::find_by_id(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    check_manage_other(&admin_session, &item)?;
+    let rows = admin::del_or_restore(&mut client, id, true, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    if rows != 1 {
+        return Err(AppError::from_str(
+            "删除失败，账号不存在或不能删除",
+            crate::error::AppErrorType::Common,
+        ));
+    }
+    // 已删除的账号立即下线
+    session::revoke_all(&state.rdc, &state.sess_cfg, id)
         .await
         .map_err(log_error(handler_name.to_string()))?;
     redirect("/admin/admin?msg=删除成功")
 }
+/// 解除登录失败导致的锁定
+pub async fn unlock(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i32>,
+    audit: Audit,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_admin_unlock";
+    let client = get_client(&state, handler_name).await?;
+    let item = admin::find_by_id(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    login_guard::unlock(&state.rdc, &item.username)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let entry = Entry::new(Action::Unlock, Target::Admin, id as i64, &item.username);
+    audit_log::create(&**client, &audit, &entry)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    redirect("/admin/admin?msg=已解除锁定")
+}
 pub async fn restore(
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i32>,
+    headers: HeaderMap,
+    audit: Audit,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_admin_restore";
-    let client = get_client(&state, handler_name).await?;
-    admin::del_or_restore(&client, id, false)
+    let admin_session = current_admin(&state, &headers).await?;
+    let mut client = get_client(&state, handler_name).await?;
+    let item = admin::find_by_id(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    check_manage_other(&admin_session, &item)?;
+    let rows = admin::del_or_restore(&mut client, id, false, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    if rows != 1 {
+        return Err(AppError::from_str(
+            "恢复失败，账号不存在或不能恢复",
+            crate::error::AppErrorType::Common,
+        ));
+    }
     redirect("/admin/admin?msg=恢复成功")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: i32, role: Role) -> AdminSession {
+        AdminSession {
+            id,
+            username: String::new(),
+            is_sys: false,
+            role,
+            dateline: 0,
+            login_at: 0,
+            active_at: 0,
+            ip: String::new(),
+            user_agent: String::new(),
+        }
+    }
+
+    fn admin(id: i32, is_sys: bool) -> Admin {
+        Admin {
+            id,
+            username: String::new(),
+            password: String::new(),
+            is_sys,
+            is_del: false,
+            role: Role::Owner.code(),
+            totp_secret: String::new(),
+            totp_enabled: false,
+        }
+    }
+
+    #[test]
+    fn sys_account_only_editable_by_itself() {
+        let sys = admin(1, true);
+        let owner = session(2, Role::Owner);
+        assert!(check_self_or_manage(&owner, &sys).is_err());
+        assert!(!can_set_role(&owner, &sys));
+        let sys_session = session(1, Role::Owner);
+        assert!(check_self_or_manage(&sys_session, &sys).is_ok());
+        assert!(!can_set_role(&sys_session, &sys));
+    }
+
+    #[test]
+    fn manage_other_accounts() {
+        let other = admin(3, false);
+        let owner = session(2, Role::Owner);
+        assert!(check_self_or_manage(&owner, &other).is_ok());
+        assert!(can_set_role(&owner, &other));
+        let editor = session(4, Role::Editor);
+        assert!(check_self_or_manage(&editor, &other).is_err());
+        assert!(!can_set_role(&editor, &other));
+        // 可以修改自己的账号，但不能修改自己的角色
+        let own = admin(2, false);
+        assert!(check_self_or_manage(&owner, &own).is_ok());
+        assert!(!can_set_role(&owner, &own));
+    }
+
+    #[test]
+    fn delete_only_other_accounts() {
+        let owner = session(2, Role::Owner);
+        assert!(check_manage_other(&owner, &admin(3, false)).is_ok());
+        assert!(check_manage_other(&owner, &admin(2, false)).is_err());
+        assert!(check_manage_other(&owner, &admin(1, true)).is_err());
+        let sys_session = session(1, Role::Owner);
+        assert!(check_manage_other(&sys_session, &admin(1, true)).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use axum::{
+    extract::{Extension, Query},
+    http::HeaderMap,
+    response::Html,
+};
+use std::sync::Arc;
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    arg::AuditLogArg,
+    audit::{Action, Target},
+    db::{admin, audit_log},
+    handler::helper::{attachment, get_client, log_error, render},
+    html::backend::audit_log::IndexTemplate,
+    middleware::csrf::CsrfToken,
+    model::{AppState, AuditLog},
+    Result,
+};
+
+/// 筛选条件为空时对应的子句恒为真，参数的顺序见[`condition_args`]
+const CONDITION: &str = "($1=0 OR admin_id=$1) AND ($2='' OR action=$2) AND ($3='' OR target_type=$3) AND ($4::BIGINT=0 OR target_id=$4) AND (target_name ILIKE $5 OR before_summary ILIKE $5 OR after_summary ILIKE $5 OR ip ILIKE $5)";
+
+struct ConditionArgs {
+    admin_id: i32,
+    action: &'static str,
+    target: &'static str,
+    target_id: i64,
+    keyword: String,
+}
+fn condition_args(args: &AuditLogArg) -> ConditionArgs {
+    ConditionArgs {
+        admin_id: args.admin_id(),
+        action: args.action().map(|a| a.as_str()).unwrap_or(""),
+        target: args.target().map(|t| t.as_str()).unwrap_or(""),
+        target_id: args.target_id(),
+        keyword: format!("%{}%", args.keyword()),
+    }
+}
+impl ConditionArgs {
+    fn as_args(&self) -> [&(dyn ToSql + Sync); 5] {
+        [
+            &self.admin_id,
+            &self.action,
+            &self.target,
+            &self.target_id,
+            &self.keyword,
+        ]
+    }
+}
+
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    args: Option<Query<AuditLogArg>>,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_audit_log_index";
+    let args = args.map(|q| q.0).unwrap_or_default();
+    let client = get_client(&state, handler_name).await?;
+    let cond_args = condition_args(&args);
+    let list = audit_log::select(&client, Some(CONDITION), &cond_args.as_args(), args.page())
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let admins = admin::all(&client)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = IndexTemplate {
+        arg: args,
+        list,
+        admins,
+        actions: Action::ALL,
+        targets: Target::ALL,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+/// 按当前的筛选条件导出 CSV
+pub async fn export(
+    Extension(state): Extension<Arc<AppState>>,
+    args: Option<Query<AuditLogArg>>,
+) -> Result<(HeaderMap, Vec<u8>)> {
+    let handler_name = "backend_audit_log_export";
+    let args = args.map(|q| q.0).unwrap_or_default();
+    let client = get_client(&state, handler_name).await?;
+    let cond_args = condition_args(&args);
+    let list = audit_log::all(&client, Some(CONDITION), &cond_args.as_args())
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    attachment(
+        "text/csv; charset=utf-8",
+        "audit_log.csv",
+        to_csv(&list).into_bytes(),
+    )
+}
+
+fn to_csv(list: &[AuditLog]) -> String {
+    // 带 BOM，Excel 才能正确识别 UTF-8
+    let mut out = String::from("\u{feff}");
+    out.push_str("ID,时间,管理员,操作,对象类型,对象ID,对象名称,操作前,操作后,IP,User-Agent\r\n");
+    for row in list {
+        let fields = [
+            row.id.to_string(),
+            row.dateline(),
+            row.admin_name.clone(),
+            row.action_name().to_string(),
+            row.target_type_name().to_string(),
+            row.target_id.to_string(),
+            row.target_name.clone(),
+            row.before_summary.clone(),
+            row.after_summary.clone(),
+            row.ip.clone(),
+            row.user_agent.clone(),
+        ];
+        let line = fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&line);
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// 转义 CSV 字段。以公式符号开头的值加上单引号，避免在电子表格中被执行
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    arg,
+    db::comment,
+    error::{AppError, AppErrorType},
+    form,
+    handler::{
+        helper::{get_client, log_error, purge_comment_cache, render},
+        redirect::redirect,
+    },
+    html::backend::comment::IndexTemplate,
+    middleware::csrf::CsrfToken,
+    model::{AppState, CommentStatus},
+    Result,
+};
+use axum::{
+    extract::{Extension, Form, Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::Html,
+};
+use std::sync::Arc;
+
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    args: Option<Query<arg::CommentBackendQueryArg>>,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_comment_index";
+    let args = args.unwrap().0;
+    let q_keyword = format!("%{}%", args.keyword());
+    let client = get_client(&state, handler_name).await?;
+    let list = comment::select(
+        &client,
+        Some("is_del=$1 AND status=$2 AND (content ILIKE $3 OR nickname ILIKE $3)"),
+        &[&args.is_del(), &args.status().code(), &q_keyword],
+        args.page(),
+    )
+    .await
+    .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = IndexTemplate {
+        arg: args,
+        list,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+pub async fn approve(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_comment_approve";
+    let client = get_client(&state, handler_name).await?;
+    comment::set_status(&client, &[id], CommentStatus::Approved)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    purge_comment_cache(&state.cache, &client, &[id]).await;
+    redirect("/admin/comment?msg=评论已通过审核")
+}
+pub async fn reject(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_comment_reject";
+    let client = get_client(&state, handler_name).await?;
+    comment::set_status(&client, &[id], CommentStatus::Spam)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    purge_comment_cache(&state.cache, &client, &[id]).await;
+    redirect("/admin/comment?msg=评论已标记为垃圾评论")
+}
+pub async fn del(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_comment_del";
+    let client = get_client(&state, handler_name).await?;
+    comment::del(&client, &[id])
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    purge_comment_cache(&state.cache, &client, &[id]).await;
+    redirect("/admin/comment?msg=评论删除成功")
+}
+pub async fn restore(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_comment_restore";
+    let client = get_client(&state, handler_name).await?;
+    comment::restore(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    purge_comment_cache(&state.cache, &client, &[id]).await;
+    redirect("/admin/comment?msg=评论恢复成功")
+}
+/// 批量通过、拒绝或删除评论
+pub async fn batch(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(frm): Form<form::BatchComment>,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_comment_batch";
+    let ids = frm.ids();
+    if ids.is_empty() {
+        return Err(AppError::from_str("请选择评论", AppErrorType::Common));
+    }
+    let client = get_client(&state, handler_name).await?;
+    let rows = match frm.action.as_str() {
+        "approve" => comment::set_status(&client, &ids, CommentStatus::Approved).await,
+        "reject" => comment::set_status(&client, &ids, CommentStatus::Spam).await,
+        "del" => comment::del(&client, &ids).await,
+        _ => Err(AppError::from_str("不支持的操作", AppErrorType::Common)),
+    }
+    .map_err(log_error(handler_name.to_string()))?;
+    purge_comment_cache(&state.cache, &client, &ids).await;
+    redirect(&format!("/admin/comment?msg=已处理{}条评论", rows))
+}
@@ -0,0 +1,319 @@
+//! 文章的 Markdown 导入和导出
+
+use axum::{
+    extract::{Extension, Multipart, Path, Query},
+    http::HeaderMap,
+    response::Html,
+};
+use serde::Deserialize;
+use tokio_postgres::Client;
+
+use crate::{
+    archive::{self, ArchiveFormat},
+    audit::Audit,
+    cache::Cache,
+    db::{subject, topic},
+    error::{AppError, AppErrorType},
+    form::{CreateTopic, UpdateTopic},
+    front_matter::Document,
+    handler::helper::{
+        attachment, get_client, log_error, purge_topic_cache, render, topic_placement,
+    },
+    html::backend::import_export::{
+        ImportResultTemplate, SubjectImportTemplate, TopicImportTemplate,
+    },
+    md::{self, Sanitizer},
+    middleware::csrf::CsrfToken,
+    model::{AppState, Subject},
+    Result,
+};
+use std::sync::Arc;
+
+/// 单个文件的导入结果
+pub struct ImportResult {
+    pub file_name: String,
+    pub title: String,
+    /// 成功时为“新建”或“更新”
+    pub action: String,
+    pub error: Option<String>,
+}
+
+/// 导入一个文件。文章已存在（专题和固定链接相同）时更新，否则新建
+async fn import_document(
+    client: &mut Client,
+    doc: &Document,
+    default_subject: Option<&Subject>,
+    audit: &Audit,
+    sanitizer: &Sanitizer,
+    cache: &Cache,
+) -> Result<&'static str> {
+    let fm = &doc.front_matter;
+    let subject_rs = if !fm.subject.is_empty() {
+        subject::find_by_slug(client, &fm.subject).await?
+    } else {
+        match default_subject {
+            Some(s) => s.clone(),
+            None => {
+                return Err(AppError::from_str(
+                    "Front Matter 中没有指定专题(subject)",
+                    AppErrorType::Common,
+                ))
+            }
+        }
+    };
+    let dateline = doc.dateline()?;
+    let mut before = None;
+    let (id, action) = match topic::find_id_by_slug(client, subject_rs.id, &fm.slug).await? {
+        Some(id) => {
+            before = topic_placement(client, id).await;
+            let existing = topic::find_to_edit(client, id).await?;
+            let ut = UpdateTopic {
+                dateline,
+                ..doc.to_update(&existing)
+            };
+            let rendered = md::render(&ut.md, sanitizer);
+            topic::update(client, &ut, &rendered.html, &rendered.toc, audit).await?;
+            (id, "更新")
+        }
+        None => {
+            let ct = CreateTopic {
+                dateline,
+                ..doc.to_create(subject_rs.id)
+            };
+            let rendered = md::render(&ct.md, sanitizer);
+            let id = topic::create(client, &ct, &rendered.html, &rendered.toc, audit)
+                .await?
+                .id;
+            (id, "新建")
+        }
+    };
+    purge_topic_cache(cache, client, id, before).await;
+    Ok(action)
+}
+
+/// 导入多个文件，单个文件失败不影响其它文件
+async fn import_files(
+    client: &mut Client,
+    files: Vec<(String, String)>,
+    default_subject: Option<&Subject>,
+    audit: &Audit,
+    sanitizer: &Sanitizer,
+    cache: &Cache,
+) -> Vec<ImportResult> {
+    let mut results = Vec::with_capacity(files.len());
+    for (file_name, content) in files {
+        let doc = match Document::parse(&content) {
+            Ok(doc) => doc,
+            Err(err) => {
+                results.push(ImportResult {
+                    file_name,
+                    title: "".to_string(),
+                    action: "".to_string(),
+                    error: Some(err.message()),
+                });
+                continue;
+            }
+        };
+        let title = doc.front_matter.title.clone();
+        match import_document(client, &doc, default_subject, audit, sanitizer, cache).await {
+            Ok(action) => results.push(ImportResult {
+                file_name,
+                title,
+                action: action.to_string(),
+                error: None,
+            }),
+            Err(err) => {
+                tracing::error!("导入 {} 失败：{:?}", file_name, err);
+                results.push(ImportResult {
+                    file_name,
+                    title,
+                    action: "".to_string(),
+                    error: Some(err.message()),
+                })
+            }
+        }
+    }
+    results
+}
+
+/// 读取上传的文件。`file`字段为上传的文件，`md`字段为粘贴的内容
+async fn read_upload(mut multipart: Multipart) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| AppError::from_err(err, AppErrorType::Common))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        let file_name = field.file_name().unwrap_or_default().to_string();
+        let data = field
+            .bytes()
+            .await
+            .map_err(|err| AppError::from_err(err, AppErrorType::Common))?;
+        if data.is_empty() {
+            continue;
+        }
+        match name.as_str() {
+            "file" => files.push((file_name, data.to_vec())),
+            "md" => files.push(("粘贴的内容".to_string(), data.to_vec())),
+            _ => {}
+        }
+    }
+    if files.is_empty() {
+        return Err(AppError::from_str("请选择文件", AppErrorType::Common));
+    }
+    Ok(files)
+}
+
+fn to_text(file_name: String, data: Vec<u8>) -> Result<(String, String)> {
+    let content = String::from_utf8(data).map_err(|_| {
+        AppError::from_str(
+            &format!("{} 不是 UTF-8 编码的文本文件", file_name),
+            AppErrorType::Common,
+        )
+    })?;
+    Ok((file_name, content))
+}
+
+pub async fn topic_export(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<(HeaderMap, Vec<u8>)> {
+    let handler_name = "backend_topic_export";
+    let client = get_client(&state, handler_name).await?;
+    let topic_rs = topic::find_to_edit(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let subject_rs = subject::find(&client, Some("id=$1"), &[&topic_rs.subject_id])
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let doc = Document::from_topic(&topic_rs, &subject_rs.slug);
+    attachment(
+        "text/markdown; charset=utf-8",
+        &doc.file_name(),
+        doc.render()?.into_bytes(),
+    )
+}
+
+pub async fn topic_import(csrf: CsrfToken) -> Result<Html<String>> {
+    let handler_name = "backend_topic_import";
+    let tmpl = TopicImportTemplate { csrf_token: csrf.0 };
+    render(tmpl, handler_name)
+}
+
+pub async fn topic_import_action(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+    csrf: CsrfToken,
+    multipart: Multipart,
+) -> Result<Html<String>> {
+    let handler_name = "backend_topic_import_action";
+    let files = read_upload(multipart)
+        .await?
+        .into_iter()
+        .map(|(file_name, data)| to_text(file_name, data))
+        .collect::<Result<Vec<_>>>()?;
+    let mut client = get_client(&state, handler_name).await?;
+    let results = import_files(
+        &mut client,
+        files,
+        None,
+        &audit,
+        &state.sanitizer,
+        &state.cache,
+    )
+    .await;
+    let tmpl = ImportResultTemplate {
+        back_url: "/admin/topic".to_string(),
+        results,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+#[derive(Deserialize)]
+pub struct ExportArgs {
+    pub format: Option<String>,
+}
+
+pub async fn subject_export(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(args): Query<ExportArgs>,
+) -> Result<(HeaderMap, Vec<u8>)> {
+    let handler_name = "backend_subject_export";
+    let format = ArchiveFormat::from_name(args.format.as_deref().unwrap_or("zip"))
+        .ok_or_else(|| AppError::from_str("不支持的归档格式", AppErrorType::Common))?;
+    let client = get_client(&state, handler_name).await?;
+    let subject_rs = subject::find(&client, Some("id=$1"), &[&id])
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let topics = topic::find_to_edit_by_subject(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let mut files = Vec::with_capacity(topics.len());
+    for topic_rs in topics.iter() {
+        let doc = Document::from_topic(topic_rs, &subject_rs.slug);
+        files.push((doc.file_name(), doc.render()?));
+    }
+    let data = archive::pack(format, &files)?;
+    attachment(
+        format.content_type(),
+        &format!("{}.{}", subject_rs.slug, format.extension()),
+        data,
+    )
+}
+
+pub async fn subject_import(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i32>,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_subject_import";
+    let client = get_client(&state, handler_name).await?;
+    let subject_rs = subject::find(&client, Some("id=$1"), &[&id])
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = SubjectImportTemplate {
+        subject: subject_rs,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+/// 从归档批量导入。Front Matter 中没有指定专题的文章导入到当前专题
+pub async fn subject_import_action(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i32>,
+    audit: Audit,
+    csrf: CsrfToken,
+    multipart: Multipart,
+) -> Result<Html<String>> {
+    let handler_name = "backend_subject_import_action";
+    let mut files = Vec::new();
+    for (file_name, data) in read_upload(multipart).await? {
+        match ArchiveFormat::from_file_name(&file_name) {
+            Some(format) => files.extend(archive::unpack(format, &data)?),
+            None => files.push(to_text(file_name, data)?),
+        }
+    }
+    let mut client = get_client(&state, handler_name).await?;
+    let subject_rs = subject::find(&client, Some("id=$1"), &[&id])
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let results = import_files(
+        &mut client,
+        files,
+        Some(&subject_rs),
+        &audit,
+        &state.sanitizer,
+        &state.cache,
+    )
+    .await;
+    let tmpl = ImportResultTemplate {
+        back_url: "/admin/subject".to_string(),
+        results,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
@@ -1,9 +1,20 @@
-use axum::response::Html;
+use std::sync::Arc;
 
-use crate::{handler::helper::render, html::backend::index::IndexTemplate, Result};
+use axum::{extract::Extension, response::Html};
 
-pub async fn index() -> Result<Html<String>> {
+use crate::{
+    handler::helper::render, html::backend::index::IndexTemplate, middleware::csrf::CsrfToken,
+    model::AppState, Result,
+};
+
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
     let handler_name = "backend_index";
-    let tmpl = IndexTemplate {};
+    let tmpl = IndexTemplate {
+        csrf_token: csrf.0,
+        cache_stats: state.cache.stats(),
+    };
     render(tmpl, handler_name)
 }
@@ -0,0 +1,236 @@
+use crate::{
+    arg,
+    db::{media, pagination::Pagination},
+    error::{AppError, AppErrorType},
+    handler::{
+        helper::{get_client, log_error, render},
+        redirect::redirect,
+    },
+    html::backend::media::IndexTemplate,
+    media::{self as upload, NewMedia},
+    middleware::csrf::CsrfToken,
+    model::{AppState, Media},
+    Result,
+};
+use axum::{
+    extract::{Extension, Multipart, Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::Html,
+    Json,
+};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio_postgres::Client;
+
+use super::current_admin;
+
+/// 单个文件的上传结果
+#[derive(Serialize)]
+pub struct UploadResult {
+    pub file_name: String,
+    /// 失败原因，成功时为空
+    pub msg: String,
+    pub media: Option<Media>,
+}
+
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    args: Option<Query<arg::BackendQueryArg>>,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_media_index";
+    let args = args.unwrap().0;
+    let client = get_client(&state, handler_name).await?;
+    let list = select(&client, &args)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = IndexTemplate {
+        arg: args,
+        list,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+/// 编辑器中的媒体库
+pub async fn list(
+    Extension(state): Extension<Arc<AppState>>,
+    args: Option<Query<arg::BackendQueryArg>>,
+) -> Result<Json<Pagination<Vec<Media>>>> {
+    let handler_name = "backend_media_list";
+    let args = args.unwrap().0;
+    let client = get_client(&state, handler_name).await?;
+    let list = select(&client, &args)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(list))
+}
+
+async fn select(client: &Client, args: &arg::BackendQueryArg) -> Result<Pagination<Vec<Media>>> {
+    let q_keyword = format!("%{}%", args.keyword());
+    media::select(
+        client,
+        Some("is_del=$1 AND file_name ILIKE $2"),
+        &[&args.is_del(), &q_keyword],
+        args.page(),
+    )
+    .await
+}
+
+/// 上传文件，可以一次上传多个，每个文件单独返回结果
+pub async fn upload(
+    Extension(state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Json<Vec<UploadResult>>> {
+    let handler_name = "backend_media_upload";
+    let admin_session = current_admin(&state, &headers).await?;
+    let client = get_client(&state, handler_name).await?;
+    let max_size = state.upload_cfg.max_size;
+    let mut results = vec![];
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| AppError::from_err(err, AppErrorType::Common))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let file_name = field.file_name().unwrap_or_default().to_string();
+        // 边读边检查大小，超出限制的文件不会完整读入内存
+        let mut data = Vec::new();
+        let mut too_large = false;
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|err| AppError::from_err(err, AppErrorType::Common))?;
+            if data.len() + chunk.len() > max_size {
+                too_large = true;
+                break;
+            }
+            data.extend_from_slice(&chunk);
+        }
+        let result = if too_large {
+            Err(AppError::from_str(
+                &format!("文件超过{}的大小限制", upload::size_text(max_size as u64)),
+                AppErrorType::Common,
+            ))
+        } else if data.is_empty() {
+            Err(AppError::from_str("文件为空", AppErrorType::Common))
+        } else {
+            save(&state, &client, admin_session.id, &file_name, data)
+                .await
+                .map_err(log_error(handler_name.to_string()))
+        };
+        results.push(match result {
+            Ok(m) => UploadResult {
+                file_name,
+                msg: String::new(),
+                media: Some(m),
+            },
+            Err(err) => UploadResult {
+                file_name,
+                msg: err.message(),
+                media: None,
+            },
+        });
+    }
+    if results.is_empty() {
+        return Err(AppError::from_str("请选择文件", AppErrorType::Common));
+    }
+    Ok(Json(results))
+}
+
+/// 保存文件并记录到媒体库。相同内容的文件已经存在时直接返回已有的记录
+async fn save(
+    state: &AppState,
+    client: &Client,
+    admin_id: i32,
+    file_name: &str,
+    data: Vec<u8>,
+) -> Result<Media> {
+    let file_type = upload::detect(&data).ok_or_else(|| {
+        AppError::from_str(
+            "不支持的文件类型，只能上传 PNG、JPEG、GIF、WebP 图片及 PDF、ZIP、GZIP 文件",
+            AppErrorType::Common,
+        )
+    })?;
+    let hash = upload::hash(&data);
+    if let Some(mut m) = media::find_by_hash(client, &hash).await? {
+        if m.is_del {
+            media::restore(client, m.id).await?;
+            m.is_del = false;
+        }
+        return Ok(m);
+    }
+    // 先解析图片再保存，无法解析的图片不会写入存储
+    let (data, thumb) = match file_type.image {
+        Some(format) => {
+            let size = state.upload_cfg.thumb_size;
+            let (thumb, data) =
+                tokio::task::spawn_blocking(move || (upload::thumbnail(&data, format, size), data))
+                    .await
+                    .map_err(|err| AppError::from_err(err, AppErrorType::Common))?;
+            (data, Some(thumb?))
+        }
+        None => (data, None),
+    };
+    let path = upload::key(&hash, file_type.ext);
+    state
+        .storage
+        .put(&path, &data, file_type.content_type)
+        .await?;
+    let mut new_media = NewMedia {
+        admin_id,
+        hash: hash.clone(),
+        storage: state.storage.name(),
+        url: state.storage.url(&path),
+        path,
+        thumb_path: String::new(),
+        thumb_url: String::new(),
+        file_name: file_name.to_string(),
+        content_type: file_type.content_type,
+        size: data.len() as i64,
+        width: 0,
+        height: 0,
+        is_image: thumb.is_some(),
+    };
+    if let Some(thumb) = thumb {
+        let thumb_path = upload::thumb_key(&hash, thumb.file_type.ext);
+        state
+            .storage
+            .put(&thumb_path, &thumb.data, thumb.file_type.content_type)
+            .await?;
+        new_media.thumb_url = state.storage.url(&thumb_path);
+        new_media.thumb_path = thumb_path;
+        new_media.width = thumb.width as i32;
+        new_media.height = thumb.height as i32;
+    }
+    media::create(client, &new_media).await?;
+    media::find_by_hash(client, &hash)
+        .await?
+        .ok_or_else(|| AppError::from_str("上传文件失败", AppErrorType::Common))
+}
+
+pub async fn del(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_media_del";
+    let client = get_client(&state, handler_name).await?;
+    media::del(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    redirect("/admin/media?msg=文件删除成功")
+}
+
+pub async fn restore(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_media_restore";
+    let client = get_client(&state, handler_name).await?;
+    media::restore(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    redirect("/admin/media?msg=文件恢复成功")
+}
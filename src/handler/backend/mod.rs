@@ -1,72 +1,169 @@
-use axum::{http::HeaderMap, routing::get, Router};
+use axum::{
+    extract::extractor_middleware,
+    http::HeaderMap,
+    routing::{get, post},
+    Router,
+};
 
 use crate::{
     error::AppError,
+    middleware::permission::{
+        ImportExport, ManageAdmin, ManageMedia, ManageSubject, ManageTag, ManageTopic,
+        ModerateComment, Require, UploadMedia, WriteTopic,
+    },
     model::{AdminSession, AppState},
-    rdb,
-    session::gen_redis_key,
     Result,
 };
 
 use super::helper::get_cookie;
 
 pub mod admin;
+pub mod audit_log;
+pub mod comment;
+pub mod import_export;
 pub mod index;
+pub mod media;
+pub mod session;
 pub mod subject;
 pub mod tag;
+pub mod token;
 pub mod topic;
+pub mod topic_revision;
+pub mod two_factor;
 
 pub async fn get_logined_admin(
     state: &AppState,
     headers: &HeaderMap,
 ) -> Result<Option<AdminSession>> {
-    let sess_cfg = state.clone().sess_cfg.clone();
+    let sess_cfg = &state.sess_cfg;
     let cookie = get_cookie(headers, &sess_cfg.id_name);
     if let Some(session_id) = cookie {
-        if !session_id.is_empty() {
-            let redis_key = gen_redis_key(&sess_cfg, &session_id);
-            let admin_session = rdb::get(&state.rdc, &redis_key).await.map_err(|err| {
+        let admin_session = crate::session::load(&state.rdc, sess_cfg, &session_id)
+            .await
+            .map_err(|err| {
                 tracing::error!("get session failed: {:?}", err);
                 AppError::auth_error("UNAUTHENTICATED")
             })?;
-            if let Some(admin_session) = admin_session {
-                let admin_session: AdminSession =
-                    serde_json::from_str(&admin_session).map_err(|err| {
-                        tracing::error!("des admin_session failed: {:?}", err);
-                        AppError::auth_error("UNAUTHENTICATED")
-                    })?;
-                tracing::debug!("admin session: {:?}", admin_session);
-                return Ok(Some(admin_session));
-            }
-        }
+        return Ok(admin_session);
     }
     Ok(None)
 }
 
+/// 获取当前登录的管理员，未登录时返回认证错误
+pub async fn current_admin(state: &AppState, headers: &HeaderMap) -> Result<AdminSession> {
+    get_logined_admin(state, headers)
+        .await?
+        .ok_or_else(|| AppError::auth_error("UNAUTHENTICATED"))
+}
+
+/// 后台路由，按所需的权限分组。登录检查在外层统一进行
 pub fn routers() -> Router {
-    Router::new()
+    // 登录即可访问，需要区分管理员的在处理函数中检查
+    let common = Router::new()
         .route("/", get(index::index))
+        .route("/admin/edit/:id", get(admin::edit).post(admin::edit_action))
+        .route("/token", get(token::index))
+        .route("/token/add", get(token::add).post(token::add_action))
+        .route("/token/del/:id", post(token::del))
+        .route("/session", get(session::index))
+        .route("/session/revoke/:handle", post(session::revoke))
+        .route("/session/revoke-others", post(session::revoke_others))
+        .route("/two-factor", get(two_factor::index))
+        .route(
+            "/two-factor/setup",
+            get(two_factor::setup).post(two_factor::setup_action),
+        )
+        .route(
+            "/two-factor/recovery-codes",
+            post(two_factor::recovery_codes),
+        )
+        .route("/two-factor/disable", post(two_factor::disable))
+        .route("/two-factor/require", post(two_factor::require));
+    let write_topic = Router::new()
+        .route("/topic", get(topic::index))
+        .route("/topic/add", get(topic::add).post(topic::add_action))
+        .route("/topic/edit/:id", get(topic::edit).post(topic::edit_action))
+        .route("/topic/preview/:id", get(topic::preview))
+        .route("/topic/preview", post(topic::preview_md))
+        .route("/topic/draft", post(topic::save_draft))
+        .route("/topic/draft/:id", get(topic::draft))
+        .route("/topic/draft/discard/:id", post(topic::discard_draft))
+        .route("/topic/revision/:id", get(topic_revision::index))
+        .route("/topic/revision/:id/diff", get(topic_revision::diff))
+        .route(
+            "/topic/revision/:id/restore/:revision_id",
+            post(topic_revision::restore),
+        )
+        .layer(extractor_middleware::<Require<WriteTopic>>());
+    let manage_topic = Router::new()
+        .route("/topic/del/:id", post(topic::del))
+        .route("/topic/restore/:id", post(topic::restore))
+        .route("/topic/rerender", post(topic::rerender))
+        .layer(extractor_middleware::<Require<ManageTopic>>());
+    let manage_subject = Router::new()
         .route("/subject", get(subject::index))
         .route("/subject/add", get(subject::add).post(subject::add_action))
         .route(
             "/subject/edit/:id",
             get(subject::edit).post(subject::edit_action),
         )
-        .route("/subject/del/:id", get(subject::del))
-        .route("/subject/restore/:id", get(subject::restore))
+        .route("/subject/del/:id", post(subject::del))
+        .route("/subject/restore/:id", post(subject::restore))
+        .layer(extractor_middleware::<Require<ManageSubject>>());
+    let import_export = Router::new()
+        .route("/subject/export/:id", get(import_export::subject_export))
+        .route(
+            "/subject/import/:id",
+            get(import_export::subject_import).post(import_export::subject_import_action),
+        )
+        .route("/topic/export/:id", get(import_export::topic_export))
+        .route(
+            "/topic/import",
+            get(import_export::topic_import).post(import_export::topic_import_action),
+        )
+        .layer(extractor_middleware::<Require<ImportExport>>());
+    let manage_tag = Router::new()
         .route("/tag", get(tag::index))
         .route("/tag/add", get(tag::add).post(tag::add_action))
         .route("/tag/edit/:id", get(tag::edit).post(tag::edit_action))
-        .route("/tag/del/:id", get(tag::del))
-        .route("/tag/restore/:id", get(tag::restore))
-        .route("/topic", get(topic::index))
-        .route("/topic/add", get(topic::add).post(topic::add_action))
-        .route("/topic/del/:id", get(topic::del))
-        .route("/topic/restore/:id", get(topic::restore))
-        .route("/topic/edit/:id", get(topic::edit).post(topic::edit_action))
+        .route("/tag/del/:id", post(tag::del))
+        .route("/tag/restore/:id", post(tag::restore))
+        .layer(extractor_middleware::<Require<ManageTag>>());
+    let moderate_comment = Router::new()
+        .route("/comment", get(comment::index))
+        .route("/comment/approve/:id", post(comment::approve))
+        .route("/comment/reject/:id", post(comment::reject))
+        .route("/comment/del/:id", post(comment::del))
+        .route("/comment/restore/:id", post(comment::restore))
+        .route("/comment/batch", post(comment::batch))
+        .layer(extractor_middleware::<Require<ModerateComment>>());
+    let upload_media = Router::new()
+        .route("/media", get(media::index))
+        .route("/media/list", get(media::list))
+        .route("/media/upload", post(media::upload))
+        .layer(extractor_middleware::<Require<UploadMedia>>());
+    let manage_media = Router::new()
+        .route("/media/del/:id", post(media::del))
+        .route("/media/restore/:id", post(media::restore))
+        .layer(extractor_middleware::<Require<ManageMedia>>());
+    let manage_admin = Router::new()
         .route("/admin", get(admin::index))
         .route("/admin/add", get(admin::add).post(admin::add_action))
-        .route("/admin/edit/:id", get(admin::edit).post(admin::edit_action))
-        .route("/admin/del/:id", get(admin::del))
-        .route("/admin/restore/:id", get(admin::restore))
+        .route("/admin/role/:id", post(admin::set_role))
+        .route("/admin/del/:id", post(admin::del))
+        .route("/admin/restore/:id", post(admin::restore))
+        .route("/admin/unlock/:id", post(admin::unlock))
+        .route("/audit", get(audit_log::index))
+        .route("/audit/export", get(audit_log::export))
+        .layer(extractor_middleware::<Require<ManageAdmin>>());
+    common
+        .merge(write_topic)
+        .merge(manage_topic)
+        .merge(manage_subject)
+        .merge(import_export)
+        .merge(manage_tag)
+        .merge(moderate_comment)
+        .merge(upload_media)
+        .merge(manage_media)
+        .merge(manage_admin)
 }
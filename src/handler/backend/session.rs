@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::Html,
+};
+use std::sync::Arc;
+
+use crate::{
+    arg,
+    error::AppError,
+    handler::{
+        helper::{get_cookie, log_error, render},
+        redirect::redirect,
+    },
+    html::backend::session::IndexTemplate,
+    middleware::csrf::CsrfToken,
+    model::AppState,
+    session, Result,
+};
+
+use super::current_admin;
+
+/// 当前管理员的所有登录会话
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    args: Option<Query<arg::BackendQueryArg>>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_session_index";
+    let args = args.unwrap().0;
+    let admin_session = current_admin(&state, &headers).await?;
+    let list = session::list(&state.rdc, &state.sess_cfg, admin_session.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let current = get_cookie(&headers, &state.sess_cfg.id_name)
+        .map(|id| session::handle(&id))
+        .unwrap_or_default();
+    let tmpl = IndexTemplate {
+        list,
+        current,
+        arg: args,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+pub async fn revoke(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(handle): Path<String>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_session_revoke";
+    let admin_session = current_admin(&state, &headers).await?;
+    let revoked = session::revoke(&state.rdc, &state.sess_cfg, admin_session.id, &handle)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    if !revoked {
+        return Err(AppError::not_found("会话不存在或已失效"));
+    }
+    redirect("/admin/session?msg=会话已注销")
+}
+
+/// 注销除当前会话外的所有会话
+pub async fn revoke_others(
+    Extension(state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_session_revoke_others";
+    let admin_session = current_admin(&state, &headers).await?;
+    let current_id = get_cookie(&headers, &state.sess_cfg.id_name).unwrap_or_default();
+    let revoked =
+        session::revoke_others(&state.rdc, &state.sess_cfg, admin_session.id, &current_id)
+            .await
+            .map_err(log_error(handler_name.to_string()))?;
+    redirect(&format!("/admin/session?msg=已注销{}个会话", revoked))
+}
@@ -6,6 +6,8 @@ use axum::{
 
 use crate::{
     arg,
+    audit::Audit,
+    cache,
     db::subject,
     form,
     handler::{
@@ -13,6 +15,7 @@ use crate::{
         redirect::redirect,
     },
     html::backend::subject::{AddTemplate, EditTemplate, IndexTemplate},
+    middleware::csrf::CsrfToken,
     model::AppState,
     Result,
 };
@@ -21,6 +24,7 @@ use std::sync::Arc;
 pub async fn index(
     Extension(state): Extension<Arc<AppState>>,
     args: Option<Query<arg::SubjectBackendQueryArg>>,
+    csrf: CsrfToken,
 ) -> Result<Html<String>> {
     let handler_name = "backend_subject_index";
     let client = get_client(&state, handler_name).await?;
@@ -37,66 +41,79 @@ pub async fn index(
     let tmpl = IndexTemplate {
         arg: args,
         list: subject_list,
+        csrf_token: csrf.0,
     };
     render(tmpl, handler_name)
 }
-pub async fn add() -> Result<Html<String>> {
-    let tmpl = AddTemplate {};
+pub async fn add(csrf: CsrfToken) -> Result<Html<String>> {
+    let tmpl = AddTemplate { csrf_token: csrf.0 };
     render(tmpl, "backend_subject_add")
 }
 pub async fn add_action(
     Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
     form: Form<form::CreateSubject>,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_subject_add_action";
-    let client = get_client(&state, handler_name).await?;
-    subject::create(&client, &form)
+    let mut client = get_client(&state, handler_name).await?;
+    subject::create(&mut client, &form, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_SUBJECTS]).await;
     redirect("/admin/subject?msg=专题添加成功")
 }
 pub async fn edit(
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i32>,
+    csrf: CsrfToken,
 ) -> Result<Html<String>> {
     let handler_name = "backend_subject_edit";
     let client = get_client(&state, handler_name).await?;
     let sub = subject::find(&client, Some("id=$1"), &[&id])
         .await
         .map_err(log_error(handler_name.to_string()))?;
-    let tmpl = EditTemplate { subject: sub };
+    let tmpl = EditTemplate {
+        subject: sub,
+        csrf_token: csrf.0,
+    };
     render(tmpl, handler_name)
 }
 pub async fn edit_action(
     Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
     form: Form<form::UpdateSubject>,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_subject_edit_action";
-    let client = get_client(&state, handler_name).await?;
-    subject::update(&client, &form)
+    let mut client = get_client(&state, handler_name).await?;
+    subject::update(&mut client, &form, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_SUBJECTS]).await;
     redirect("/admin/subject?msg=专题修改成功")
 }
 pub async fn del(
     Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
     Path(id): Path<i32>,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_subject_del";
-    let client = get_client(&state, handler_name).await?;
-    subject::delete(&client, id)
+    let mut client = get_client(&state, handler_name).await?;
+    subject::delete(&mut client, id, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_SUBJECTS]).await;
     redirect("/admin/subject?msg=专题删除成功")
 }
 pub async fn restore(
     Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
     Path(id): Path<i32>,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_subject_restore";
-    let client = get_client(&state, handler_name).await?;
-    subject::restore(&client, id)
+    let mut client = get_client(&state, handler_name).await?;
+    subject::restore(&mut client, id, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_SUBJECTS]).await;
     redirect("/admin/subject?msg=专题还原成功")
 }
@@ -1,5 +1,7 @@
 use crate::{
     arg,
+    audit::Audit,
+    cache,
     db::tag,
     form,
     handler::{
@@ -7,6 +9,7 @@ use crate::{
         redirect::redirect,
     },
     html::backend::tag::{AddTemplate, EditTemplate, IndexTemplate},
+    middleware::csrf::CsrfToken,
     model::AppState,
     Result,
 };
@@ -20,6 +23,7 @@ use std::sync::Arc;
 pub async fn index(
     Extension(state): Extension<Arc<AppState>>,
     args: Option<Query<arg::TagBackendQueryArg>>,
+    csrf: CsrfToken,
 ) -> Result<Html<String>> {
     let handler_name = "backend_tag_index";
     let args = args.unwrap().0;
@@ -36,67 +40,80 @@ pub async fn index(
     let tmpl = IndexTemplate {
         arg: args,
         list: tag_list,
+        csrf_token: csrf.0,
     };
     render(tmpl, handler_name)
 }
-pub async fn add() -> Result<Html<String>> {
-    let tmpl = AddTemplate {};
+pub async fn add(csrf: CsrfToken) -> Result<Html<String>> {
+    let tmpl = AddTemplate { csrf_token: csrf.0 };
     render(tmpl, "backend_tag_add")
 }
 pub async fn add_action(
     Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
     Form(ct): Form<form::CreateTag>,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_tag_add_action";
-    let client = get_client(&state, handler_name).await?;
-    tag::create(&client, &ct)
+    let mut client = get_client(&state, handler_name).await?;
+    tag::create(&mut client, &ct, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_TAGS]).await;
     redirect("/admin/tag?msg=标签添加成功")
 }
 
 pub async fn edit(
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i32>,
+    csrf: CsrfToken,
 ) -> Result<Html<String>> {
     let handler_name = "backend_tag_edit";
     let client = get_client(&state, handler_name).await?;
     let tag = tag::find(&client, Some("id=$1"), &[&id])
         .await
         .map_err(log_error(handler_name.to_string()))?;
-    let tmpl = EditTemplate { tag };
+    let tmpl = EditTemplate {
+        tag,
+        csrf_token: csrf.0,
+    };
     render(tmpl, handler_name)
 }
 pub async fn edit_action(
     Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
     Form(ut): Form<form::UpdateTag>,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_tag_edit_action";
-    let client = get_client(&state, handler_name).await?;
-    tag::update(&client, &ut)
+    let mut client = get_client(&state, handler_name).await?;
+    tag::update(&mut client, &ut, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_TAGS]).await;
     redirect("/admin/tag?msg=标签修改成功")
 }
 pub async fn del(
     Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
     Path(id): Path<i32>,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_tag_del";
-    let client = get_client(&state, handler_name).await?;
-    tag::del(&client, id)
+    let mut client = get_client(&state, handler_name).await?;
+    tag::del(&mut client, id, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_TAGS]).await;
     redirect("/admin/tag?msg=标签删除成功")
 }
 pub async fn restore(
     Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
     Path(id): Path<i32>,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_tag_restore";
-    let client = get_client(&state, handler_name).await?;
-    tag::restore(&client, id)
+    let mut client = get_client(&state, handler_name).await?;
+    tag::restore(&mut client, id, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    state.cache.purge_tags(&[cache::TAG_TAGS]).await;
     redirect("/admin/tag?msg=标签恢复成功")
 }
@@ -0,0 +1,103 @@
+use crate::{
+    arg,
+    db::admin_token,
+    error::AppError,
+    form::CreateAdminToken,
+    handler::{
+        helper::{get_client, log_error, render},
+        redirect::redirect,
+    },
+    html::backend::token::{AddTemplate, CreatedTemplate, IndexTemplate},
+    middleware::csrf::CsrfToken,
+    model::AppState,
+    token, Result,
+};
+use axum::{
+    extract::{Extension, Form, Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::Html,
+};
+use std::sync::Arc;
+
+use super::current_admin;
+
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    args: Option<Query<arg::BackendQueryArg>>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_token_index";
+    let args = args.unwrap();
+    let admin_session = current_admin(&state, &headers).await?;
+    let q_keyword = format!("%{}%", args.keyword());
+    let client = get_client(&state, handler_name).await?;
+    let token_list = admin_token::select(
+        &client,
+        admin_session.id,
+        args.is_del(),
+        &q_keyword,
+        args.page(),
+    )
+    .await
+    .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = IndexTemplate {
+        list: token_list,
+        arg: args.0,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+pub async fn add(csrf: CsrfToken) -> Result<Html<String>> {
+    let handler_name = "backend_token_add";
+    let tmpl = AddTemplate { csrf_token: csrf.0 };
+    render(tmpl, handler_name)
+}
+
+/// 创建令牌。明文令牌只在创建后显示一次
+pub async fn add_action(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(ct): Form<CreateAdminToken>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_token_add_action";
+    if ct.name.trim().is_empty() {
+        return Err(AppError::from_str(
+            "请输入令牌名称",
+            crate::error::AppErrorType::Common,
+        ));
+    }
+    let admin_session = current_admin(&state, &headers).await?;
+    let plain_token = token::generate();
+    let client = get_client(&state, handler_name).await?;
+    admin_token::create(
+        &client,
+        admin_session.id,
+        ct.name.trim(),
+        &token::hash(&plain_token),
+    )
+    .await
+    .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = CreatedTemplate {
+        name: ct.name.trim().to_string(),
+        token: plain_token,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+pub async fn del(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i32>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_token_del";
+    let admin_session = current_admin(&state, &headers).await?;
+    let client = get_client(&state, handler_name).await?;
+    admin_token::revoke(&client, id, admin_session.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    redirect("/admin/token?msg=令牌已吊销")
+}
@@ -3,75 +3,127 @@ use axum::{
     http::HeaderMap,
     http::StatusCode,
     response::Html,
+    Json,
 };
 
 use crate::{
     arg,
-    db::{subject, topic},
+    audit::{Action, Audit, Entry, Target},
+    db::{audit_log, subject, topic},
+    draft::{self, Draft},
     form,
     handler::{
-        helper::{get_client, log_error, render},
+        helper::{
+            check_topic_owner, get_client, log_error, purge_topic_cache, render, topic_placement,
+        },
         redirect::redirect,
     },
     html::backend::topic::{AddTemplate, EditTemplate, IndexTemplate},
     md,
+    middleware::csrf::CsrfToken,
     model::AppState,
+    preview,
+    role::Permission,
     Result,
 };
+use serde_json::json;
 use std::sync::Arc;
 
-pub async fn add(Extension(state): Extension<Arc<AppState>>) -> Result<Html<String>> {
+use super::current_admin;
+
+pub async fn add(
+    Extension(state): Extension<Arc<AppState>>,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
     let handler_name = "backend_topic_add";
     let client = get_client(&state, handler_name).await?;
     let subjects = subject::all(&client)
         .await
         .map_err(log_error(handler_name.to_string()))?;
-    let tmpl = AddTemplate { subjects };
+    let tmpl = AddTemplate {
+        subjects,
+        csrf_token: csrf.0,
+    };
     render(tmpl, handler_name)
 }
 
 pub async fn add_action(
     Extension(state): Extension<Arc<AppState>>,
     Form(ct): Form<form::CreateTopic>,
+    headers: HeaderMap,
+    audit: Audit,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_topic_add";
-    let html_text = md::to_html(&ct.md);
+    let admin_session = current_admin(&state, &headers).await?;
+    let rendered = md::render(&ct.md, &state.sanitizer);
     let mut client = get_client(&state, handler_name).await?;
-    topic::create(&mut client, &ct, &html_text)
+    let id = topic::create(&mut client, &ct, &rendered.html, &rendered.toc, &audit)
         .await
-        .map_err(log_error(handler_name.to_string()))?;
+        .map_err(log_error(handler_name.to_string()))?
+        .id;
+    purge_topic_cache(&state.cache, &client, id, None).await;
+    // 草稿删除失败不影响保存的结果
+    draft::remove(&state.rdc, admin_session.id, 0)
+        .await
+        .map_err(log_error(handler_name.to_string()))
+        .ok();
     redirect("/admin/topic?msg=文章添加成功")
 }
 
 pub async fn index(
     Extension(state): Extension<Arc<AppState>>,
     args: Option<Query<arg::BackendQueryArg>>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
 ) -> Result<Html<String>> {
     let handler_name = "backend_topic_index";
+    let admin_session = current_admin(&state, &headers).await?;
     let client = get_client(&state, handler_name).await?;
     let args = args.unwrap().0;
     let q_keyword = format!("%{}%", args.keyword());
-    let list = topic::select(
-        &client,
-        Some("subject_is_del=false AND is_del=$1 AND (title LIKE $2 OR subject_name LIKE $2)"),
-        &[&args.is_del(), &q_keyword],
-        args.page(),
-    )
-    .await
+    let condition =
+        "subject_is_del=false AND is_del=$1 AND (title LIKE $2 OR subject_name LIKE $2)";
+    let list = if admin_session.role.can(Permission::ManageTopic) {
+        topic::select(
+            &client,
+            Some(condition),
+            &[&args.is_del(), &q_keyword],
+            args.page(),
+        )
+        .await
+    } else {
+        // 只列出自己的文章
+        topic::select(
+            &client,
+            Some(&format!(
+                "{} AND id IN (SELECT id FROM topic WHERE admin_id=$3)",
+                condition
+            )),
+            &[&args.is_del(), &q_keyword, &admin_session.id],
+            args.page(),
+        )
+        .await
+    }
     .map_err(log_error(handler_name.to_string()))?;
-    let tmpl = IndexTemplate { list, arg: args };
+    let tmpl = IndexTemplate {
+        list,
+        arg: args,
+        csrf_token: csrf.0,
+    };
     render(tmpl, handler_name)
 }
 
 pub async fn del(
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i64>,
+    audit: Audit,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_topic_del";
     let mut client = get_client(&state, handler_name).await?;
-    let (topic_rows, topic_tag_rows) = topic::del_or_restore(&mut client, id, true)
+    let (topic_rows, topic_tag_rows) = topic::del_or_restore(&mut client, id, true, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    purge_topic_cache(&state.cache, &client, id, None).await;
     tracing::debug!(
         "删除文章数：{}, 删除关联标签数：{}",
         topic_rows,
@@ -82,12 +134,14 @@ pub async fn del(
 pub async fn restore(
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i64>,
+    audit: Audit,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_topic_restore";
     let mut client = get_client(&state, handler_name).await?;
-    let (topic_rows, topic_tag_rows) = topic::del_or_restore(&mut client, id, false)
+    let (topic_rows, topic_tag_rows) = topic::del_or_restore(&mut client, id, false, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    purge_topic_cache(&state.cache, &client, id, None).await;
     tracing::debug!(
         "还原文章数：{}, 还原关联标签数：{}",
         topic_rows,
@@ -98,9 +152,13 @@ pub async fn restore(
 pub async fn edit(
     Extension(state): Extension<Arc<AppState>>,
     Path(id): Path<i64>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
 ) -> Result<Html<String>> {
     let handler_name = "backend_topic_edit";
+    let admin_session = current_admin(&state, &headers).await?;
     let client = get_client(&state, handler_name).await?;
+    check_topic_owner(&client, admin_session.id, admin_session.role, id).await?;
     let subjects = subject::all(&client)
         .await
         .map_err(log_error(handler_name.to_string()))?;
@@ -110,6 +168,7 @@ pub async fn edit(
     let tmpl = EditTemplate {
         subjects,
         topic: topic_rs,
+        csrf_token: csrf.0,
     };
     render(tmpl, handler_name)
 }
@@ -117,12 +176,121 @@ pub async fn edit(
 pub async fn edit_action(
     Extension(state): Extension<Arc<AppState>>,
     Form(ut): Form<form::UpdateTopic>,
+    headers: HeaderMap,
+    audit: Audit,
 ) -> Result<(StatusCode, HeaderMap, ())> {
     let handler_name = "backend_topic_edit_action";
+    let admin_session = current_admin(&state, &headers).await?;
     let mut client = get_client(&state, handler_name).await?;
-    let html_text = md::to_html(&ut.md);
-    topic::update(&mut client, &ut, &html_text)
+    check_topic_owner(&client, admin_session.id, admin_session.role, ut.id).await?;
+    let rendered = md::render(&ut.md, &state.sanitizer);
+    let before = topic_placement(&client, ut.id).await;
+    topic::update(&mut client, &ut, &rendered.html, &rendered.toc, &audit)
         .await
         .map_err(log_error(handler_name.to_string()))?;
+    purge_topic_cache(&state.cache, &client, ut.id, before).await;
+    draft::remove(&state.rdc, admin_session.id, ut.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))
+        .ok();
     redirect("/admin/topic?msg=文章修改成功")
 }
+
+/// 跳转到文章的签名预览链接
+pub async fn preview(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_topic_preview";
+    let admin_session = current_admin(&state, &headers).await?;
+    let client = get_client(&state, handler_name).await?;
+    check_topic_owner(&client, admin_session.id, admin_session.role, id).await?;
+    redirect(&preview::url(&state.web_cfg.secret_key, id))
+}
+
+/// 使用当前的 Markdown 渲染流程重新生成所有文章的 HTML 和目录
+pub async fn rerender(
+    Extension(state): Extension<Arc<AppState>>,
+    audit: Audit,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_topic_rerender";
+    let client = get_client(&state, handler_name).await?;
+    let contents = topic::all_contents(&client)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let mut changed = 0;
+    for content in contents.iter() {
+        let rendered = md::render(&content.md, &state.sanitizer);
+        if rendered.html == content.html && rendered.toc == content.toc {
+            continue;
+        }
+        topic::update_html(&client, content.topic_id, &rendered.html, &rendered.toc)
+            .await
+            .map_err(log_error(handler_name.to_string()))?;
+        changed += 1;
+    }
+    if changed > 0 {
+        state.cache.purge_all().await;
+    }
+    let entry = Entry::new(Action::Rerender, Target::Topic, 0, "全部文章")
+        .after(json!({ "total": contents.len(), "changed": changed }));
+    audit_log::create(&**client, &audit, &entry)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    redirect(&format!(
+        "/admin/topic?msg=已重新渲染{}篇文章，其中{}篇有变化",
+        contents.len(),
+        changed
+    ))
+}
+
+/// 编辑器的实时预览，与保存时使用相同的渲染流程
+pub async fn preview_md(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(frm): Form<form::PreviewTopic>,
+) -> Html<String> {
+    Html(md::render(&frm.md, &state.sanitizer).html)
+}
+
+/// 获取当前管理员在指定文章上的草稿
+pub async fn draft(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(topic_id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Json<Option<Draft>>> {
+    let handler_name = "backend_topic_draft";
+    let admin_session = current_admin(&state, &headers).await?;
+    let d = draft::get(&state.rdc, admin_session.id, topic_id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(d))
+}
+
+/// 自动保存草稿，返回保存时间
+pub async fn save_draft(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(frm): Form<form::SaveDraft>,
+    headers: HeaderMap,
+) -> Result<Json<i32>> {
+    let handler_name = "backend_topic_save_draft";
+    let admin_session = current_admin(&state, &headers).await?;
+    let dateline = draft::save(&state.rdc, admin_session.id, frm.topic_id, &frm.md)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(dateline))
+}
+
+/// 放弃草稿
+pub async fn discard_draft(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(topic_id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Json<bool>> {
+    let handler_name = "backend_topic_discard_draft";
+    let admin_session = current_admin(&state, &headers).await?;
+    draft::remove(&state.rdc, admin_session.id, topic_id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    Ok(Json(true))
+}
@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::HeaderMap,
+    http::StatusCode,
+    response::Html,
+};
+
+use crate::{
+    arg,
+    audit::Audit,
+    db::{topic, topic_revision},
+    diff, form,
+    handler::{
+        helper::{
+            check_topic_owner, get_client, log_error, purge_topic_cache, render, topic_placement,
+        },
+        redirect::redirect,
+    },
+    html::backend::topic::{RevisionDiffTemplate, RevisionTemplate},
+    md,
+    middleware::csrf::CsrfToken,
+    model::{AppState, ProtectMode, TopicStatus},
+    time, Result,
+};
+use std::sync::Arc;
+
+use super::current_admin;
+
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+    args: Option<Query<arg::BackendQueryArg>>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_topic_revision_index";
+    let args = args.unwrap().0;
+    let admin_session = current_admin(&state, &headers).await?;
+    let client = get_client(&state, handler_name).await?;
+    check_topic_owner(&client, admin_session.id, admin_session.role, id).await?;
+    let topic_rs = topic::find_to_edit(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let list = topic_revision::select(&client, id, args.page())
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = RevisionTemplate {
+        topic: topic_rs,
+        list,
+        arg: args,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+pub async fn diff(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(args): Query<arg::TopicRevisionDiffArg>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_topic_revision_diff";
+    let admin_session = current_admin(&state, &headers).await?;
+    let client = get_client(&state, handler_name).await?;
+    check_topic_owner(&client, admin_session.id, admin_session.role, id).await?;
+    let topic_rs = topic::find_to_edit(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let from = topic_revision::find(&client, id, args.from)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let to = topic_revision::find(&client, id, args.to)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let hunks = diff::lines(&from.md, &to.md);
+    let tmpl = RevisionDiffTemplate {
+        topic: topic_rs,
+        from,
+        to,
+        hunks,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+/// 恢复到指定版本。通过正常的修改流程保存，因此会产生一个新的版本
+pub async fn restore(
+    Extension(state): Extension<Arc<AppState>>,
+    Path((id, revision_id)): Path<(i64, i64)>,
+    headers: HeaderMap,
+    audit: Audit,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_topic_revision_restore";
+    let admin_session = current_admin(&state, &headers).await?;
+    let mut client = get_client(&state, handler_name).await?;
+    check_topic_owner(&client, admin_session.id, admin_session.role, id).await?;
+    let topic_rs = topic::find_to_edit(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let revision = topic_revision::find(&client, id, revision_id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let ut = form::UpdateTopic {
+        id,
+        title: revision.title,
+        subject_id: topic_rs.subject_id,
+        slug: topic_rs.slug,
+        summary: revision.summary,
+        src: topic_rs.src,
+        author: topic_rs.author,
+        md: revision.md,
+        tags: revision.tags,
+        status: TopicStatus::from_code(topic_rs.status),
+        publish_at: time::format_local(topic_rs.publish_at),
+        protect_mode: ProtectMode::from_code(topic_rs.protect_mode),
+        protect_count: topic_rs.protect_count,
+        dateline: None,
+    };
+    let rendered = md::render(&ut.md, &state.sanitizer);
+    let before = topic_placement(&client, id).await;
+    topic::update(&mut client, &ut, &rendered.html, &rendered.toc, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    purge_topic_cache(&state.cache, &client, id, before).await;
+    redirect(&format!("/admin/topic/revision/{}?msg=版本恢复成功", id))
+}
@@ -0,0 +1,235 @@
+use axum::{
+    extract::{Extension, Form, Query},
+    http::{HeaderMap, StatusCode},
+    response::Html,
+};
+use std::sync::Arc;
+
+use crate::{
+    arg,
+    audit::Audit,
+    db::{admin, setting},
+    error::AppError,
+    form::{DisableTwoFactor, RequireTwoFactor, TwoFactorCode},
+    handler::{
+        helper::{get_client, log_error, render},
+        redirect::redirect,
+    },
+    html::backend::two_factor::{IndexTemplate, RecoveryCodesTemplate, SetupTemplate},
+    middleware::csrf::CsrfToken,
+    model::AppState,
+    password, session,
+    time::now,
+    totp, two_factor, Result,
+};
+
+use super::current_admin;
+
+/// 当前管理员的两步验证
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    args: Option<Query<arg::BackendQueryArg>>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_two_factor_index";
+    let admin_session = current_admin(&state, &headers).await?;
+    let client = get_client(&state, handler_name).await?;
+    let item = admin::find_by_id(&client, admin_session.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let recovery_codes = admin::recovery_code_count(&client, item.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let required = two_factor::is_required(&**client)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = IndexTemplate {
+        enabled: item.totp_enabled,
+        recovery_codes,
+        required,
+        is_sys: item.is_sys,
+        arg: args.unwrap().0,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+/// 显示新密钥的二维码
+pub async fn setup(
+    Extension(state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_two_factor_setup";
+    let admin_session = current_admin(&state, &headers).await?;
+    let client = get_client(&state, handler_name).await?;
+    check_not_enabled(&client, admin_session.id).await?;
+    let secret = two_factor::setup_secret(&state.rdc, admin_session.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = SetupTemplate {
+        qr_code: totp::qr_code(&totp::provisioning_uri(&admin_session.username, &secret)),
+        secret,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+async fn check_not_enabled(client: &tokio_postgres::Client, id: i32) -> Result<()> {
+    let item = admin::find_by_id(client, id).await?;
+    if item.totp_enabled {
+        return Err(AppError::is_exists("已经启用两步验证"));
+    }
+    Ok(())
+}
+
+/// 输入验证码确认后启用，恢复码只在启用后显示一次
+pub async fn setup_action(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(frm): Form<TwoFactorCode>,
+    headers: HeaderMap,
+    audit: Audit,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_two_factor_setup_action";
+    let admin_session = current_admin(&state, &headers).await?;
+    let mut client = get_client(&state, handler_name).await?;
+    check_not_enabled(&client, admin_session.id).await?;
+    let secret = two_factor::load_setup_secret(&state.rdc, admin_session.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?
+        .ok_or_else(|| AppError::auth_error("密钥已过期，请重新扫描二维码"))?;
+    let step = totp::verify(&secret, frm.code.trim(), now() as i64)
+        .ok_or_else(|| AppError::auth_error("验证码错误"))?;
+    let codes = totp::generate_recovery_codes();
+    let code_hashes: Vec<String> = codes.iter().map(|c| totp::hash_recovery_code(c)).collect();
+    admin::enable_two_factor(
+        &mut client,
+        admin_session.id,
+        &secret,
+        step,
+        &code_hashes,
+        &audit,
+    )
+    .await
+    .map_err(log_error(handler_name.to_string()))?;
+    two_factor::clear_setup_secret(&state.rdc, admin_session.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = RecoveryCodesTemplate {
+        codes,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+/// 重新生成恢复码
+pub async fn recovery_codes(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(frm): Form<TwoFactorCode>,
+    headers: HeaderMap,
+    audit: Audit,
+    csrf: CsrfToken,
+) -> Result<Html<String>> {
+    let handler_name = "backend_two_factor_recovery_codes";
+    let admin_session = current_admin(&state, &headers).await?;
+    let mut client = get_client(&state, handler_name).await?;
+    let item = admin::find_by_id(&client, admin_session.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    // 恢复码不能用来生成新的恢复码
+    if !item.totp_enabled || !totp::is_code(frm.code.trim()) {
+        return Err(AppError::auth_error("请输入验证器中的验证码"));
+    }
+    if !two_factor::verify(&client, &item, &frm.code).await? {
+        return Err(AppError::auth_error("验证码错误"));
+    }
+    let codes = totp::generate_recovery_codes();
+    let code_hashes: Vec<String> = codes.iter().map(|c| totp::hash_recovery_code(c)).collect();
+    admin::regenerate_recovery_codes(&mut client, item.id, &code_hashes, &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = RecoveryCodesTemplate {
+        codes,
+        csrf_token: csrf.0,
+    };
+    render(tmpl, handler_name)
+}
+
+/// 关闭两步验证，需要同时输入密码和验证码
+pub async fn disable(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(frm): Form<DisableTwoFactor>,
+    headers: HeaderMap,
+    audit: Audit,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_two_factor_disable";
+    let admin_session = current_admin(&state, &headers).await?;
+    let mut client = get_client(&state, handler_name).await?;
+    if two_factor::is_required(&**client)
+        .await
+        .map_err(log_error(handler_name.to_string()))?
+    {
+        return Err(AppError::forbidden(
+            "已要求所有管理员启用两步验证，不能关闭",
+        ));
+    }
+    let item = admin::find_by_id(&client, admin_session.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    if !password::verify(&frm.password, &item.password)? {
+        return Err(AppError::auth_error("你输入的密码错误"));
+    }
+    if !two_factor::verify(&client, &item, &frm.code).await? {
+        return Err(AppError::auth_error("验证码错误"));
+    }
+    admin::disable_two_factor(&mut client, item.id, Some(&audit))
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    redirect("/admin/two-factor?msg=已关闭两步验证")
+}
+
+/// 是否要求所有管理员启用。要求后，未启用的管理员的会话全部注销，重新登录时完成启用
+pub async fn require(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(frm): Form<RequireTwoFactor>,
+    headers: HeaderMap,
+    audit: Audit,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "backend_two_factor_require";
+    let admin_session = current_admin(&state, &headers).await?;
+    if !admin_session.is_sys {
+        return Err(AppError::forbidden("只有系统管理员可以修改该设置"));
+    }
+    let mut client = get_client(&state, handler_name).await?;
+    if !frm.required {
+        setting::set(&mut client, two_factor::SETTING_REQUIRED, "0", &audit)
+            .await
+            .map_err(log_error(handler_name.to_string()))?;
+        return redirect("/admin/two-factor?msg=已取消要求");
+    }
+    let admins = admin::all(&client)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    // 避免自己被注销后无法登录
+    if !admins
+        .iter()
+        .any(|item| item.id == admin_session.id && item.totp_enabled)
+    {
+        return Err(AppError::forbidden("请先为自己启用两步验证"));
+    }
+    setting::set(&mut client, two_factor::SETTING_REQUIRED, "1", &audit)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let mut revoked = 0;
+    for item in admins.iter().filter(|item| !item.totp_enabled) {
+        revoked += session::revoke_all(&state.rdc, &state.sess_cfg, item.id)
+            .await
+            .map_err(log_error(handler_name.to_string()))?;
+    }
+    redirect(&format!(
+        "/admin/two-factor?msg=已要求所有管理员启用两步验证，注销了{}个未启用的会话",
+        revoked
+    ))
+}
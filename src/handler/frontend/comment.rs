@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Form},
+    http::{HeaderMap, StatusCode},
+};
+
+use crate::{
+    db::comment,
+    form,
+    handler::{
+        helper::{get_client, log_error},
+        redirect::redirect,
+    },
+    model::AppState,
+    Result,
+};
+
+use super::topic::verify_captcha;
+
+/// 提交评论。评论需要审核后才会显示
+pub async fn add(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(frm): Form<form::CreateComment>,
+) -> Result<(StatusCode, HeaderMap, ())> {
+    let handler_name = "frontend_comment_add";
+    frm.validate()?;
+    verify_captcha(&state, &frm.response, handler_name).await?;
+    let client = get_client(&state, handler_name).await?;
+    let path = comment::find_topic_path(&client, frm.topic_id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    comment::create(&client, &frm)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    redirect(&format!(
+        "{}?msg=评论已提交，审核通过后将会显示#comments",
+        path.url()
+    ))
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    http::HeaderMap,
+};
+
+use crate::{
+    cache,
+    db::{subject, tag, topic},
+    feed::{Feed, FeedFormat},
+    handler::helper::{get_client, log_error, with_content_type},
+    model::AppState,
+    Result,
+};
+
+/// 订阅源的响应
+fn response(format: FeedFormat, xml: String) -> Result<(HeaderMap, String)> {
+    with_content_type(format.content_type(), xml)
+}
+
+async fn site(state: Arc<AppState>, format: FeedFormat) -> Result<(HeaderMap, String)> {
+    let handler_name = "frontend_feed_site";
+    let xml = state
+        .cache
+        .get_or_compute(&cache::Key::site_feed(format.name()), || async {
+            let client = get_client(&state, handler_name).await?;
+            let list = topic::select_with_summary(&client, None, &[], Some("id DESC"), 0).await?;
+            let feed_path = match format {
+                FeedFormat::Atom => "/feed.xml",
+                FeedFormat::Rss => "/rss.xml",
+            };
+            Ok(Feed {
+                title: Feed::title(None),
+                path: "/".to_string(),
+                feed_path: feed_path.to_string(),
+                items: &list.data,
+            }
+            .render(format, &state.web_cfg, &state.sanitizer))
+        })
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    response(format, xml)
+}
+
+/// 全站 Atom 订阅源
+pub async fn atom(Extension(state): Extension<Arc<AppState>>) -> Result<(HeaderMap, String)> {
+    site(state, FeedFormat::Atom).await
+}
+
+/// 全站 RSS 订阅源
+pub async fn rss(Extension(state): Extension<Arc<AppState>>) -> Result<(HeaderMap, String)> {
+    site(state, FeedFormat::Rss).await
+}
+
+/// 专题的 Atom 订阅源
+pub async fn subject(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> Result<(HeaderMap, String)> {
+    let handler_name = "frontend_feed_subject";
+    let format = FeedFormat::Atom;
+    let xml = state
+        .cache
+        .get_or_compute(&cache::Key::subject_feed(format.name(), &slug), || async {
+            let client = get_client(&state, handler_name).await?;
+            let subj = subject::find(&client, Some("slug=$1 AND is_del=false"), &[&slug]).await?;
+            let list = topic::select_with_summary(
+                &client,
+                Some(super::subject::TOPICS_CONDITION),
+                &[&slug],
+                Some("id DESC"),
+                0,
+            )
+            .await?;
+            Ok(Feed {
+                title: Feed::title(Some(&subj.name)),
+                path: format!("/subject/{}", slug),
+                feed_path: format!("/subject/{}/feed.xml", slug),
+                items: &list.data,
+            }
+            .render(format, &state.web_cfg, &state.sanitizer))
+        })
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    response(format, xml)
+}
+
+/// 标签的 Atom 订阅源
+pub async fn tag(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<(HeaderMap, String)> {
+    let handler_name = "frontend_feed_tag";
+    let format = FeedFormat::Atom;
+    let xml = state
+        .cache
+        .get_or_compute(&cache::Key::tag_feed(format.name(), &name), || async {
+            let client = get_client(&state, handler_name).await?;
+            let tag = tag::find(&client, Some("name=$1 AND is_del=false"), &[&name]).await?;
+            let list = topic::select_with_summary(
+                &client,
+                Some(super::tag::TOPICS_CONDITION),
+                &[&name],
+                Some("id DESC"),
+                0,
+            )
+            .await?;
+            Ok(Feed {
+                title: Feed::title(Some(&tag.name)),
+                path: format!("/tag/{}", name),
+                feed_path: format!("/tag/{}/feed.xml", name),
+                items: &list.data,
+            }
+            .render(format, &state.web_cfg, &state.sanitizer))
+        })
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    response(format, xml)
+}
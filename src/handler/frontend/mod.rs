@@ -1,5 +1,9 @@
 pub mod about;
+pub mod comment;
+pub mod feed;
 pub mod index;
+pub mod search;
+pub mod sitemap;
 pub mod subject;
 pub mod tag;
 pub mod topic;
@@ -10,6 +14,8 @@ use axum::{
 };
 use serde::Deserialize;
 
+use crate::{middleware::rate_limit::RateLimitLayer, rate_limit::Group};
+
 #[derive(Deserialize)]
 pub struct PaginationArgs {
     pub page: u32,
@@ -20,14 +26,27 @@ pub fn routers() -> Router {
         .route("/", get(index::index))
         .route("/subject", get(subject::index))
         .route("/subject/:slug", get(subject::topics))
+        .route("/subject/:slug/feed.xml", get(feed::subject))
         .route("/tag", get(tag::index))
         .route("/tag/:name", get(tag::topics))
+        .route("/tag/:name/feed.xml", get(feed::tag))
         .route("/topic", get(topic::index))
         .route("/topic/:subject_slug/:slug", get(topic::detail))
         .route(
             "/topic/get_procted_content",
-            post(topic::get_procted_content),
+            post(topic::get_procted_content).layer(RateLimitLayer::new(Group::ProtectedContent)),
+        )
+        .route(
+            "/topic/comment",
+            post(comment::add).layer(RateLimitLayer::new(Group::Comment)),
         )
+        .route("/preview/topic/:id", get(topic::preview))
+        .route("/search", get(search::index))
+        .route("/feed.xml", get(feed::atom))
+        .route("/rss.xml", get(feed::rss))
+        .route("/sitemap.xml", get(sitemap::index))
+        .route("/sitemaps/:name", get(sitemap::part))
+        .route("/robots.txt", get(sitemap::robots))
         .route("/about", get(about::index))
         .route("/video", get(index::video))
 }
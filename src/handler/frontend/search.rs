@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Query},
+    response::Html,
+};
+use serde::Deserialize;
+
+use crate::{
+    db::topic,
+    handler::helper::{get_client, log_error, render},
+    html::frontend::search::IndexTemplate,
+    model::AppState,
+    Result,
+};
+
+#[derive(Deserialize)]
+pub struct SearchArgs {
+    pub q: Option<String>,
+    pub page: Option<u32>,
+}
+
+pub async fn index(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(args): Query<SearchArgs>,
+) -> Result<Html<String>> {
+    let handler_name = "frontend_search_index";
+    let keyword = args.q.unwrap_or_default().trim().to_string();
+    let page = args.page.unwrap_or(0);
+    tracing::debug!("keyword: {:?}, page: {:?}", keyword, page);
+    let list = if keyword.is_empty() {
+        None
+    } else {
+        let client = get_client(&state, handler_name).await?;
+        let list = topic::search(&client, &keyword, page)
+            .await
+            .map_err(log_error(handler_name.to_string()))?;
+        Some(list)
+    };
+    let tmpl = IndexTemplate {
+        keyword,
+        page,
+        list,
+    };
+    render(tmpl, handler_name)
+}
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    http::HeaderMap,
+};
+use tokio_postgres::Client;
+
+use crate::{
+    cache,
+    db::sitemap,
+    error::AppError,
+    handler::helper::{get_client, log_error, with_content_type},
+    model::{AppState, SitemapUrl},
+    sitemap::{self as sm, MAX_URLS, STATIC_PATHS},
+    Result,
+};
+
+const CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+/// 固定页面、专题和标签
+async fn pages(client: &Client) -> Result<Vec<SitemapUrl>> {
+    let home = sitemap::home(client).await?;
+    let mut urls: Vec<SitemapUrl> = STATIC_PATHS
+        .iter()
+        .map(|&path| SitemapUrl {
+            loc: path.to_string(),
+            lastmod: if path == "/about" { 0 } else { home.lastmod },
+        })
+        .collect();
+    urls.append(&mut sitemap::subjects(client).await?);
+    urls.append(&mut sitemap::tags(client).await?);
+    Ok(urls)
+}
+
+/// 站点地图。URL数超过限制时，返回站点地图索引
+pub async fn index(Extension(state): Extension<Arc<AppState>>) -> Result<(HeaderMap, String)> {
+    let handler_name = "frontend_sitemap_index";
+    let xml = state
+        .cache
+        .get_or_compute(&cache::Key::sitemap("index"), || async {
+            let client = get_client(&state, handler_name).await?;
+            let mut urls = pages(&client).await?;
+            let topic_count = sitemap::count_topics(&client).await?;
+            if urls.len() as i64 + topic_count <= MAX_URLS as i64 {
+                let mut topics = sitemap::topics(&client, 0, MAX_URLS as i64).await?;
+                urls.append(&mut topics);
+                return Ok(sm::urlset(&state.web_cfg, &urls));
+            }
+            let lastmod = urls.first().map(|url| url.lastmod).unwrap_or(0);
+            let parts = (topic_count as f64 / MAX_URLS as f64).ceil() as i64;
+            let mut sitemaps = vec![SitemapUrl {
+                loc: "/sitemaps/main.xml".to_string(),
+                lastmod,
+            }];
+            for i in 0..parts {
+                sitemaps.push(SitemapUrl {
+                    loc: format!("/sitemaps/topic-{}.xml", i),
+                    lastmod,
+                });
+            }
+            Ok(sm::index(&state.web_cfg, &sitemaps))
+        })
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    with_content_type(CONTENT_TYPE, xml)
+}
+
+/// 站点地图索引中的子站点地图：`main.xml` 或 `topic-<N>.xml`
+pub async fn part(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<(HeaderMap, String)> {
+    let handler_name = "frontend_sitemap_part";
+    // 先解析文件名，只用规范化后的名称作为缓存键
+    let topic_part = match name.as_str() {
+        "main.xml" => None,
+        _ => match name
+            .strip_prefix("topic-")
+            .and_then(|s| s.strip_suffix(".xml"))
+            .and_then(|s| s.parse::<i64>().ok())
+        {
+            Some(i) if i >= 0 => Some(i),
+            _ => return Err(AppError::not_found("没有找到站点地图")),
+        },
+    };
+    let key = match topic_part {
+        Some(i) => format!("topic-{}.xml", i),
+        None => "main.xml".to_string(),
+    };
+    let xml = state
+        .cache
+        .get_or_compute(&cache::Key::sitemap(&key), || async {
+            let client = get_client(&state, handler_name).await?;
+            let urls = match topic_part {
+                Some(i) => sitemap::topics(&client, i * MAX_URLS as i64, MAX_URLS as i64).await?,
+                None => pages(&client).await?,
+            };
+            if urls.is_empty() {
+                return Err(AppError::not_found("没有找到站点地图"));
+            }
+            Ok(sm::urlset(&state.web_cfg, &urls))
+        })
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    with_content_type(CONTENT_TYPE, xml)
+}
+
+pub async fn robots(Extension(state): Extension<Arc<AppState>>) -> Result<(HeaderMap, String)> {
+    let txt = sm::robots(&state.web_cfg, &state.robots_cfg);
+    with_content_type("text/plain; charset=utf-8", txt)
+}
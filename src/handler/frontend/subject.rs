@@ -4,18 +4,20 @@ use axum::{
     extract::{Extension, Path, Query},
     response::Html,
 };
-use serde_json::{from_str, json};
 
 use super::PaginationArgs;
 use crate::{
     cache,
-    db::{pagination::Pagination, subject, topic},
+    db::{subject, topic},
     handler::helper::{get_client, log_error, render},
     html::frontend::subject::{IndexTemplate, TopicsTemplate},
-    model::{AppState, Subject},
+    model::AppState,
     Result,
 };
 
+/// 专题文章列表的条件
+pub const TOPICS_CONDITION: &str = "subject_slug=$1";
+
 pub async fn index(
     Extension(state): Extension<Arc<AppState>>,
     page: Option<Query<PaginationArgs>>,
@@ -25,36 +27,18 @@ pub async fn index(
         None => 0,
     };
     let handler_name = "frontend_subject_index";
-    let cache_key = cache::gen_name(format!("{}:{}", handler_name, page).as_str());
-    let cached_content = cache::read(&state.rdc, &cache_key).await;
-    tracing::debug!("page: {:?}", page);
-    let client = get_client(&state, handler_name).await?;
-    let mut list: Option<Pagination<Vec<Subject>>> = None;
-    let mut flag = false;
-    if let Some(cached_content) = cached_content {
-        match from_str(&cached_content) {
-            Ok(l) => {
-                list = Some(l);
-                flag = true;
-                tracing::debug!("命中缓存");
-            }
-            _ => {
-                flag = false;
+    let (page, list) = state
+        .cache
+        .get_page(cache::Key::subject_index, page, |page| {
+            let state = &state;
+            async move {
+                let client = get_client(state, handler_name).await?;
+                subject::select_with_summary(&client, Some("is_del=false"), &[], page).await
             }
-        };
-    }
-    if !flag {
-        let list_db = subject::select_with_summary(&client, Some("is_del=false"), &[], page)
-            .await
-            .map_err(log_error(handler_name.to_string()))?;
-        cache::write(&state.rdc, &cache_key, json!(list_db).to_string().as_str()).await;
-        list = Some(list_db);
-    };
-
-    let tmpl = IndexTemplate {
-        page,
-        list: list.unwrap(),
-    };
+        })
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let tmpl = IndexTemplate { page, list };
     render(tmpl, handler_name)
 }
 
@@ -69,19 +53,29 @@ pub async fn topics(
     };
     tracing::debug!("slug: {:?}, page: {:?}", slug, page);
     let handler_name = "frontend_subject_topics";
-    let client = get_client(&state, handler_name).await?;
-    let subj = subject::find(&client, Some("slug=$1 AND is_del=false"), &[&slug])
+    let key = |page| cache::Key::subject_topics(&slug, page);
+    let (page, (subj, list)) = state
+        .cache
+        .get_page(key, page, |page| {
+            let state = &state;
+            let slug = &slug;
+            async move {
+                let client = get_client(state, handler_name).await?;
+                let subj =
+                    subject::find(&client, Some("slug=$1 AND is_del=false"), &[slug]).await?;
+                let list = topic::select_with_summary(
+                    &client,
+                    Some(TOPICS_CONDITION),
+                    &[slug],
+                    Some("id ASC"),
+                    page,
+                )
+                .await?;
+                Ok((subj, list))
+            }
+        })
         .await
         .map_err(log_error(handler_name.to_string()))?;
-    let list = topic::select_with_summary(
-        &client,
-        Some("subject_slug=$1"),
-        &[&slug],
-        Some("id ASC"),
-        page,
-    )
-    .await
-    .map_err(log_error(handler_name.to_string()))?;
     let tmpl = TopicsTemplate {
         page,
         list,
@@ -1,4 +1,4 @@
-use std::{sync::Arc};
+use std::sync::Arc;
 
 use axum::{
     extract::{Extension, Form, Path, Query},
@@ -6,17 +6,17 @@ use axum::{
     Json,
 };
 use serde::Deserialize;
-use serde_json::from_str;
 
 use crate::{
-    db::topic,
+    db::{comment, topic},
     error::AppError,
     form,
-    handler::helper::{get_client, log_error, protected_content, render, ProtectedContent},
-    hcaptcha,
+    handler::helper::{get_client, log_error, render, topic_detail},
     html::frontend::topic::{DetailTemplate, IndexTemplate},
     model::AppState,
-    rdb, recaptcha, Result,
+    preview,
+    protect::{self, ProtectedContent},
+    Result,
 };
 
 use super::PaginationArgs;
@@ -45,7 +45,7 @@ pub struct TopicArgs {
 }
 #[derive(Deserialize)]
 pub struct TopicQuery {
-    pub hc: Option<u8>,
+    pub msg: Option<String>,
 }
 
 pub async fn detail(
@@ -53,69 +53,120 @@ pub async fn detail(
     Path(arg): Path<TopicArgs>,
     Query(qarg): Query<TopicQuery>,
 ) -> Result<Html<String>> {
-    let hc = qarg.hc.unwrap_or(0) == 1;
     let TopicArgs { subject_slug, slug } = arg;
     let handler_name = "frontend_topics_detail";
-    let mut client = get_client(&state.clone(), handler_name).await?;
-    let mut result = topic::detail(&mut client, &subject_slug, &slug)
+    let mut result = topic_detail(&state, &subject_slug, &slug, handler_name).await?;
+    let (p_html, protected) = protect::hide(
+        &state.web_cfg.secret_key,
+        result.id,
+        &result.html,
+        result.protect_policy(),
+    );
+    result.html = p_html;
+    let client = get_client(&state, handler_name).await?;
+    let comments = comment::approved(&client, result.id)
         .await
         .map_err(log_error(handler_name.to_string()))?;
-    let site_key = if hc {
-        state.hcap_cfg.site_key.clone()
-    } else {
-        state.recap_cfg.site_key.clone()
-    };
-    let (p_html, uuids) = protected_content(&result.html, &state.rdc, &site_key, hc).await;
-    result.html = p_html;
     let tmpl = DetailTemplate {
         topic: result,
-        uuids,
-        hc,
+        protected,
+        captcha: state.captcha.widget(),
+        comments,
+        can_comment: true,
+        msg: qarg.msg,
+        preview: None,
     };
     render(tmpl, handler_name)
 }
 
-pub async fn get_procted_content(
+/// 通过签名链接预览未发布的文章
+pub async fn preview(
     Extension(state): Extension<Arc<AppState>>,
-    Form(frm): Form<form::GetProctedContent>,
-) -> Result<Json<Vec<ProtectedContent>>> {
-    let handler_name = "frontend_topics_get_procted_content";
-    let is_valid = if frm.hc {
-        hcaptcha::verify(frm.response, state.hcap_cfg.secret_key.clone())
-            .await
-            .map_err(log_error(handler_name.to_string()))?
-    } else {
-        recaptcha::verify(frm.response, state.recap_cfg.secret_key.clone())
-            .await
-            .map_err(log_error(handler_name.to_string()))?
+    Path(id): Path<i64>,
+    Query(qarg): Query<preview::Signature>,
+) -> Result<Html<String>> {
+    let handler_name = "frontend_topics_preview";
+    if !preview::verify(&state.web_cfg.secret_key, id, qarg.expires, &qarg.sig) {
+        return Err(AppError::not_found("预览链接无效或已过期"));
+    }
+    let client = get_client(&state, handler_name).await?;
+    let mut result = topic::preview(&client, id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    let (p_html, protected) = protect::hide(
+        &state.web_cfg.secret_key,
+        result.id,
+        &result.html,
+        result.protect_policy(),
+    );
+    result.html = p_html;
+    let tmpl = DetailTemplate {
+        topic: result,
+        protected,
+        captcha: state.captcha.widget(),
+        comments: vec![],
+        can_comment: false,
+        msg: None,
+        preview: Some(qarg),
     };
+    render(tmpl, handler_name)
+}
+
+/// 人机验证
+pub(super) async fn verify_captcha(
+    state: &AppState,
+    response: &str,
+    handler_name: &str,
+) -> Result<()> {
+    let is_valid = state
+        .captcha
+        .verify(response)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
     if !is_valid {
         return Err(AppError::from_str(
             "人机验证失败",
             crate::error::AppErrorType::Common,
         ));
     };
-    let ids: Vec<&str> = frm.id.split(",").collect();
-    let mut pcs = Vec::with_capacity(ids.len());
-    for id in ids {
-        let redis_key = format!("protected_content:{}", id);
-        let s = rdb::get(&state.rdc, &redis_key)
-            .await
-            .map_err(log_error(handler_name.to_string()))?;
-        if let Some(s) = s {
-            rdb::del(&state.rdc, &redis_key)
-                .await
-                .map_err(log_error(handler_name.to_string()))?;
-            let r: ProtectedContent = from_str(&s)
-                .map_err(|err| AppError::protected_content(err.to_string().as_str()))
-                .map_err(log_error(handler_name.to_string()))?;
-            pcs.push(r);
-        }
+    Ok(())
+}
+
+pub async fn get_procted_content(
+    Extension(state): Extension<Arc<AppState>>,
+    Form(frm): Form<form::GetProctedContent>,
+) -> Result<Json<Vec<ProtectedContent>>> {
+    let handler_name = "frontend_topics_get_procted_content";
+    let ids = protect::verify(
+        &state.web_cfg.secret_key,
+        frm.topic_id,
+        &frm.ids,
+        frm.expires,
+        &frm.sig,
+    )
+    .ok_or_else(|| AppError::protected_content("页面已过期，请刷新页面重试"))?;
+    verify_captcha(&state, &frm.response, handler_name).await?;
+    let client = get_client(&state, handler_name).await?;
+    // 只有同时持有有效的预览链接时才能获取未发布文章的内容，
+    // 否则文章删除或下线后，令牌在有效期内仍能取得隐藏的内容
+    let is_preview = !frm.preview_sig.is_empty()
+        && preview::verify(
+            &state.web_cfg.secret_key,
+            frm.topic_id,
+            frm.preview_expires,
+            &frm.preview_sig,
+        );
+    let result = if is_preview {
+        topic::preview(&client, frm.topic_id).await
+    } else {
+        topic::detail_by_id(&client, frm.topic_id).await
     }
-    if pcs.len() > 0 {
-        return Ok(Json(pcs));
+    .map_err(log_error(handler_name.to_string()))?;
+    let pcs = protect::reveal(&result.html, &ids);
+    if pcs.is_empty() {
+        return Err(AppError::protected_content(
+            "没有找到需要的内容，请刷新页面重试",
+        ));
     }
-    Err(AppError::protected_content(
-        "没有找到需要的内容，请刷新页面重试",
-    ))
+    Ok(Json(pcs))
 }
@@ -1,15 +1,13 @@
-use crate::model::AppState;
+use crate::cache::{self, Cache};
+use crate::db::{comment, topic};
+use crate::error::AppError;
+use crate::model::{AppState, TopicDetail, TopicPlacement};
+use crate::role::{Permission, Role};
 use crate::Result;
-use crate::{error::AppError, rdb};
 use askama::Template;
 use axum::http::HeaderMap;
 use axum::response::Html;
 use deadpool_postgres::Client;
-use rand::Rng;
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use uuid::Uuid;
 
 pub async fn get_client(state: &AppState, handler_name: &str) -> Result<Client> {
     state.pool.get().await.map_err(|err| {
@@ -18,6 +16,82 @@ pub async fn get_client(state: &AppState, handler_name: &str) -> Result<Client>
     })
 }
 
+/// 没有“管理所有文章”权限的管理员只能操作自己创建的文章
+pub async fn check_topic_owner(
+    client: &Client,
+    admin_id: i32,
+    role: Role,
+    topic_id: i64,
+) -> Result<()> {
+    if role.can(Permission::ManageTopic) {
+        return Ok(());
+    }
+    let owner = topic::find_owner(client, topic_id).await?;
+    if owner.admin_id != Some(admin_id) {
+        return Err(AppError::forbidden("只能操作自己的文章"));
+    }
+    Ok(())
+}
+
+/// 前台显示的文章详情，优先从缓存中读取。每次都增加浏览次数，返回的浏览次数包括本次
+pub async fn topic_detail(
+    state: &AppState,
+    subject_slug: &str,
+    slug: &str,
+    handler_name: &str,
+) -> Result<TopicDetail> {
+    let client = get_client(state, handler_name).await?;
+    let mut result = state
+        .cache
+        .get_or_compute(&cache::Key::topic_detail(subject_slug, slug), || {
+            topic::detail(&client, subject_slug, slug)
+        })
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    result.hit = topic::hit(&client, result.id)
+        .await
+        .map_err(log_error(handler_name.to_string()))?;
+    Ok(result)
+}
+
+/// 文章的固定链接、所属的专题和标签，修改文章前获取，用于清除修改前的缓存。出错时返回`None`
+pub async fn topic_placement(client: &tokio_postgres::Client, id: i64) -> Option<TopicPlacement> {
+    topic::placement(client, id)
+        .await
+        .map_err(|err| tracing::error!("获取文章所属的专题和标签失败：{:?}", err))
+        .ok()
+}
+
+/// 文章变化后清除相关的缓存。`before`为修改前的，修改可能改变文章的固定链接、专题和标签
+pub async fn purge_topic_cache(
+    cache: &Cache,
+    client: &tokio_postgres::Client,
+    id: i64,
+    before: Option<TopicPlacement>,
+) {
+    let placements: Vec<TopicPlacement> = before
+        .into_iter()
+        .chain(topic_placement(client, id).await)
+        .collect();
+    cache.purge_topic(&placements).await;
+}
+
+/// 审核评论后清除所属文章的缓存，列表中显示了评论数
+pub async fn purge_comment_cache(cache: &Cache, client: &tokio_postgres::Client, ids: &[i64]) {
+    let topic_ids = match comment::topic_ids(client, ids).await {
+        Ok(topic_ids) => topic_ids,
+        Err(err) => {
+            tracing::error!("获取评论所属的文章失败：{:?}", err);
+            return;
+        }
+    };
+    let mut placements = Vec::with_capacity(topic_ids.len());
+    for id in topic_ids {
+        placements.extend(topic_placement(client, id).await);
+    }
+    cache.purge_topic(&placements).await;
+}
+
 pub fn log_error(handler_name: String) -> Box<dyn Fn(AppError) -> AppError> {
     Box::new(move |err| {
         tracing::error!("操作失败：{:?},  {}", err, handler_name);
@@ -33,102 +107,34 @@ pub fn render<T: Template>(tmpl: T, handler_name: &str) -> Result<Html<String>>
     Ok(Html(out))
 }
 
-#[derive(Deserialize, Serialize)]
-pub struct ProtectedContent {
-    pub uuid: String,
-    pub tag: String,
-    pub content: String,
-}
-fn in_idx(idx: &[usize], i: usize) -> bool {
-    for ii in idx.iter() {
-        if *ii == i {
-            return true;
-        }
-    }
-    false
-}
-pub async fn protected_content(
-    html: &str,
-    client: &redis::Client,
-    site_key: &str,
-    hc:bool,
-) -> (String, Vec<String>) {
-    let mut hcs = vec![];
-    let re = Regex::new(r"(?sm)<(p|pre)>(.+?)</(p|pre)>").unwrap();
-    for cap in re.captures_iter(html) {
-        hcs.push(ProtectedContent {
-            uuid: Uuid::new_v4().to_simple().to_string(),
-            tag: cap[1].to_string(),
-            content: cap[2].to_string(),
-        });
-    }
-    let mut hcs: Vec<&ProtectedContent> = hcs.iter().rev().into_iter().collect();
-    let hcs_num = hcs.len();
-    let protect_num: usize = match hcs_num {
-        0..=1 => 0,
-        2..=4 => 1,
-        5..=8 => 2,
-        _ => 3,
-    };
-    tracing::debug!("protect_num: {:?}, hcs_num: {:?}", protect_num, hcs_num);
-    if protect_num < 1 {
-        return (html.to_string(), vec![]);
-    }
-    let mut protect_idx: Vec<usize> = Vec::with_capacity(protect_num);
-    for _ in 0..protect_num {
-        loop {
-            let tmp: usize = rand::thread_rng().gen_range(0..100);
-            let tmp = tmp % hcs_num;
-            if !in_idx(&protect_idx[..], tmp) {
-                protect_idx.push(tmp);
-                break;
-            }
-        }
-    }
-    // 替换
-    let hide_tag = "---待验证---";
-    let mut out = String::new();
-    let html = re.replace_all(html, hide_tag);
-    let mut line_idx = 0usize;
-    let mut out_uuids = vec![];
-    for line in html.lines() {
-        if line == hide_tag {
-            if let Some(c) = hcs.pop() {
-                let line = if !in_idx(&protect_idx[..], line_idx) {
-                    format!("<{}>{}</{}>", c.tag, c.content, c.tag)
-                } else {
-                    out_uuids.push(c.uuid.clone());
-                    let key = format!("protected_content:{}", c.uuid);
-                    rdb::set(&client, &key, json!(c).to_string().as_str(), 60 * 20)
-                        .await
-                        .unwrap();
-                    if hc {
-                        format!(
-                            "<div id=\"hcaptcha-{uuid}\" class=\"callout callout-info\"><div>你需要进行人机验证才能查看隐藏的内容(大约{count}字节)</div><div class=\"h-captcha\" data-sitekey=\"{site_key}\"  data-callback=\"get_procted_content_{uuid}\"></div></div>",
-                            site_key=site_key, uuid=c.uuid,count=word_count(&c.content)
-                        )
-                    } else {
-                        format!(r#"<div class="g-recaptcha" data-sitekey="{site_key}" data-callback="get_procted_content_recaptcha" data-size="invisible" id="hcaptcha-{uuid}"></div><div id="captcha-notice-{uuid}" class="captcha-notice callout callout-info">
-                        </div>"#,site_key=site_key, uuid=c.uuid)
-                    }
-                    
-                };
-                out.push_str(&line);
-                out.push('\n');
-                line_idx += 1;
-                continue;
-            } else {
-                line_idx += 1;
-            }
-        }
-        out.push_str(line);
-        out.push('\n');
-    }
-    (out, out_uuids)
+/// 使用指定的`Content-Type`响应
+pub fn with_content_type(content_type: &str, body: String) -> Result<(HeaderMap, String)> {
+    let mut header = HeaderMap::new();
+    header.insert(
+        axum::http::header::CONTENT_TYPE,
+        content_type.parse().unwrap(),
+    );
+    Ok((header, body))
 }
 
-fn word_count(s: &str) -> usize {
-    s.len()
+/// 以附件形式下载
+pub fn attachment(
+    content_type: &str,
+    file_name: &str,
+    body: Vec<u8>,
+) -> Result<(HeaderMap, Vec<u8>)> {
+    let mut header = HeaderMap::new();
+    header.insert(
+        axum::http::header::CONTENT_TYPE,
+        content_type.parse().unwrap(),
+    );
+    header.insert(
+        axum::http::header::CONTENT_DISPOSITION,
+        format!("attachment; filename=\"{}\"", file_name)
+            .parse()
+            .map_err(|_| AppError::from_str("无效的文件名", crate::error::AppErrorType::Common))?,
+    );
+    Ok((header, body))
 }
 
 pub fn get_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
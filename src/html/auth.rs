@@ -1,6 +1,24 @@
 use askama::Template;
+
+use crate::captcha::Widget;
+
 #[derive(Template)]
 #[template(path = "auth/login.html")]
 pub struct LoginTemplate {
-    pub site_key: String,
+    pub captcha: Widget,
+}
+
+/// 登录的第二步，需要启用两步验证时显示密钥和二维码
+#[derive(Template)]
+#[template(path = "auth/two_factor.html")]
+pub struct TwoFactorTemplate {
+    /// 新的密钥，已启用两步验证时为空
+    pub secret: String,
+    pub qr_code: String,
+}
+
+#[derive(Template)]
+#[template(path = "auth/recovery_codes.html")]
+pub struct RecoveryCodesTemplate {
+    pub codes: Vec<String>,
 }
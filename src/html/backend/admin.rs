@@ -1,14 +1,21 @@
 use askama::Template;
 
-use crate::{arg, db::pagination::Pagination, model::Admin};
+use crate::{arg, db::pagination::Pagination, model::Admin, role::Role};
 
 #[derive(Template)]
 #[template(path = "backend/admin/add.html")]
-pub struct AddTemplate {}
+pub struct AddTemplate {
+    pub roles: [Role; 4],
+    pub csrf_token: String,
+}
 #[derive(Template)]
 #[template(path = "backend/admin/edit.html")]
 pub struct EditTemplate {
     pub admin: Admin,
+    pub roles: [Role; 4],
+    /// 当前管理员能否修改该账号的角色
+    pub can_set_role: bool,
+    pub csrf_token: String,
 }
 
 #[derive(Template)]
@@ -16,4 +23,12 @@ pub struct EditTemplate {
 pub struct IndexTemplate {
     pub list: Pagination<Vec<Admin>>,
     pub arg: arg::BackendQueryArg,
+    /// 因登录失败次数过多被锁定的账号
+    pub locked: Vec<i32>,
+    pub csrf_token: String,
+}
+impl IndexTemplate {
+    pub fn is_locked(&self, id: &i32) -> bool {
+        self.locked.contains(id)
+    }
 }
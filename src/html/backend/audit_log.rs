@@ -0,0 +1,19 @@
+use crate::{
+    arg,
+    audit::{Action, Target},
+    db::pagination::Pagination,
+    model::{Admin, AuditLog},
+};
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "backend/audit_log/index.html")]
+pub struct IndexTemplate {
+    pub arg: arg::AuditLogArg,
+    pub list: Pagination<Vec<AuditLog>>,
+    /// 用于按管理员筛选
+    pub admins: Vec<Admin>,
+    pub actions: [Action; 11],
+    pub targets: [Target; 5],
+    pub csrf_token: String,
+}
@@ -0,0 +1,14 @@
+use crate::{
+    arg,
+    db::pagination::Pagination,
+    model::{CommentList, CommentStatus},
+};
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "backend/comment/index.html")]
+pub struct IndexTemplate {
+    pub arg: arg::CommentBackendQueryArg,
+    pub list: Pagination<Vec<CommentList>>,
+    pub csrf_token: String,
+}
@@ -0,0 +1,24 @@
+use askama::Template;
+
+use crate::{handler::backend::import_export::ImportResult, model::Subject};
+
+#[derive(Template)]
+#[template(path = "backend/topic/import.html")]
+pub struct TopicImportTemplate {
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "backend/subject/import.html")]
+pub struct SubjectImportTemplate {
+    pub subject: Subject,
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "backend/topic/import_result.html")]
+pub struct ImportResultTemplate {
+    pub back_url: String,
+    pub results: Vec<ImportResult>,
+    pub csrf_token: String,
+}
@@ -1,5 +1,11 @@
 use askama::Template;
 
+use crate::cache::Stat;
+
 #[derive(Template)]
 #[template(path = "backend/index/index.html")]
-pub struct IndexTemplate {}
+pub struct IndexTemplate {
+    pub csrf_token: String,
+    /// 前台缓存的命中情况
+    pub cache_stats: Vec<(&'static str, Stat)>,
+}
@@ -0,0 +1,10 @@
+use crate::{arg, db::pagination::Pagination, model::Media};
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "backend/media/index.html")]
+pub struct IndexTemplate {
+    pub arg: arg::BackendQueryArg,
+    pub list: Pagination<Vec<Media>>,
+    pub csrf_token: String,
+}
@@ -1,5 +1,12 @@
 pub mod admin;
+pub mod audit_log;
+pub mod comment;
+pub mod import_export;
 pub mod index;
+pub mod media;
+pub mod session;
 pub mod subject;
 pub mod tag;
+pub mod token;
 pub mod topic;
+pub mod two_factor;
@@ -0,0 +1,13 @@
+use askama::Template;
+
+use crate::{arg, session::ActiveSession};
+
+#[derive(Template)]
+#[template(path = "backend/session/index.html")]
+pub struct IndexTemplate {
+    pub list: Vec<ActiveSession>,
+    /// 当前会话的标识
+    pub current: String,
+    pub arg: arg::BackendQueryArg,
+    pub csrf_token: String,
+}
@@ -7,13 +7,17 @@ use crate::{arg, db::pagination::Pagination, model};
 pub struct IndexTemplate {
     pub list: Pagination<Vec<model::SubjectList>>,
     pub arg: arg::SubjectBackendQueryArg,
+    pub csrf_token: String,
 }
 #[derive(Template)]
 #[template(path = "backend/subject/add.html")]
-pub struct AddTemplate {}
+pub struct AddTemplate {
+    pub csrf_token: String,
+}
 
 #[derive(Template)]
 #[template(path = "backend/subject/edit.html")]
 pub struct EditTemplate {
     pub subject: model::Subject,
+    pub csrf_token: String,
 }
@@ -6,13 +6,17 @@ use askama::Template;
 pub struct IndexTemplate {
     pub arg: arg::TagBackendQueryArg,
     pub list: Pagination<Vec<Tag>>,
+    pub csrf_token: String,
 }
 #[derive(Template)]
 #[template(path = "backend/tag/add.html")]
-pub struct AddTemplate {}
+pub struct AddTemplate {
+    pub csrf_token: String,
+}
 
 #[derive(Template)]
 #[template(path = "backend/tag/edit.html")]
 pub struct EditTemplate {
     pub tag: Tag,
+    pub csrf_token: String,
 }
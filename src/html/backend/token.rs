@@ -0,0 +1,25 @@
+use askama::Template;
+
+use crate::{arg, db::pagination::Pagination, model::AdminToken};
+
+#[derive(Template)]
+#[template(path = "backend/token/add.html")]
+pub struct AddTemplate {
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "backend/token/created.html")]
+pub struct CreatedTemplate {
+    pub name: String,
+    pub token: String,
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "backend/token/index.html")]
+pub struct IndexTemplate {
+    pub list: Pagination<Vec<AdminToken>>,
+    pub arg: arg::BackendQueryArg,
+    pub csrf_token: String,
+}
@@ -1,20 +1,40 @@
-use crate::{arg, db::pagination::Pagination, model};
+use crate::{arg, db::pagination::Pagination, diff, model};
 use askama::Template;
 
 #[derive(Template)]
 #[template(path = "backend/topic/add.html")]
 pub struct AddTemplate {
     pub subjects: Vec<model::SubjectList>,
+    pub csrf_token: String,
 }
 #[derive(Template)]
 #[template(path = "backend/topic/index.html")]
 pub struct IndexTemplate {
     pub list: Pagination<Vec<model::TopicSubjectListView>>,
     pub arg: arg::BackendQueryArg,
+    pub csrf_token: String,
 }
 #[derive(Template)]
 #[template(path = "backend/topic/edit.html")]
 pub struct EditTemplate {
     pub subjects: Vec<model::SubjectList>,
     pub topic: model::TopicWithMdAndTagsForEdit,
+    pub csrf_token: String,
+}
+#[derive(Template)]
+#[template(path = "backend/topic/revision.html")]
+pub struct RevisionTemplate {
+    pub topic: model::TopicWithMdAndTagsForEdit,
+    pub list: Pagination<Vec<model::TopicRevisionList>>,
+    pub arg: arg::BackendQueryArg,
+    pub csrf_token: String,
+}
+#[derive(Template)]
+#[template(path = "backend/topic/revision_diff.html")]
+pub struct RevisionDiffTemplate {
+    pub topic: model::TopicWithMdAndTagsForEdit,
+    pub from: model::TopicRevision,
+    pub to: model::TopicRevision,
+    pub hunks: Vec<Vec<diff::DiffLine>>,
+    pub csrf_token: String,
 }
@@ -0,0 +1,32 @@
+use askama::Template;
+
+use crate::arg;
+
+#[derive(Template)]
+#[template(path = "backend/two_factor/index.html")]
+pub struct IndexTemplate {
+    pub enabled: bool,
+    /// 未使用的恢复码数量
+    pub recovery_codes: i64,
+    /// 是否要求所有管理员启用
+    pub required: bool,
+    /// 系统管理员才能修改是否要求所有管理员启用
+    pub is_sys: bool,
+    pub arg: arg::BackendQueryArg,
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "backend/two_factor/setup.html")]
+pub struct SetupTemplate {
+    pub secret: String,
+    pub qr_code: String,
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "backend/two_factor/recovery_codes.html")]
+pub struct RecoveryCodesTemplate {
+    pub codes: Vec<String>,
+    pub csrf_token: String,
+}
@@ -1,5 +1,6 @@
 pub mod about;
 pub mod index;
+pub mod search;
 pub mod subject;
 pub mod tag;
 pub mod topic;
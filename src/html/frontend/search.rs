@@ -0,0 +1,11 @@
+use askama::Template;
+
+use crate::{db::pagination::Pagination, model::TopicSearchResult};
+
+#[derive(Template)]
+#[template(path = "frontend/search/index.html")]
+pub struct IndexTemplate {
+    pub keyword: String,
+    pub page: u32,
+    pub list: Option<Pagination<Vec<TopicSearchResult>>>,
+}
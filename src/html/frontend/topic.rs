@@ -1,8 +1,11 @@
 use askama::Template;
 
 use crate::{
+    captcha::Widget,
     db::pagination::Pagination,
-    model::{SubjectTopicWithTagsAndTopicSummary, TopicDetail},
+    model::{CommentNode, SubjectTopicWithTagsAndTopicSummary, TopicDetail},
+    preview::Signature,
+    protect::Token,
 };
 
 #[derive(Template)]
@@ -15,6 +18,14 @@ pub struct IndexTemplate {
 #[template(path = "frontend/topic/detail.html")]
 pub struct DetailTemplate {
     pub topic: TopicDetail,
-    pub uuids: Vec<String>,
-    pub hc: bool,
+    /// 有隐藏内容时，查看隐藏内容的令牌
+    pub protected: Option<Token>,
+    pub captcha: Widget,
+    /// 已通过审核的评论
+    pub comments: Vec<CommentNode>,
+    /// 是否显示评论表单，预览时不能评论
+    pub can_comment: bool,
+    pub msg: Option<String>,
+    /// 预览时为预览链接的签名，获取隐藏内容时一并提交
+    pub preview: Option<Signature>,
 }
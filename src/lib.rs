@@ -1,20 +1,43 @@
 pub mod arg;
+pub mod archive;
+pub mod audit;
 pub mod cache;
+pub mod captcha;
+pub mod cli;
 pub mod config;
+pub mod csrf;
 pub mod db;
+pub mod diff;
+pub mod draft;
 pub mod error;
+pub mod feed;
 pub mod form;
+pub mod front_matter;
 pub mod handler;
-pub mod hcaptcha;
 pub mod html;
+pub mod login_guard;
 pub mod md;
+pub mod media;
 pub mod middleware;
+pub mod migrate;
 pub mod model;
 pub mod password;
+pub mod preview;
+pub mod protect;
+pub mod rate_limit;
 pub mod rdb;
+pub mod role;
+pub mod search;
 pub mod session;
+pub mod sign;
+pub mod sitemap;
+pub mod storage;
 pub mod time;
-pub mod recaptcha;
+pub mod token;
+pub mod totp;
+pub mod two_factor;
 
 /// 结果
 type Result<T> = std::result::Result<T, self::error::AppError>;
+/// API 结果
+type ApiResult<T> = std::result::Result<T, self::error::ApiError>;
@@ -0,0 +1,113 @@
+//! 登录防暴力破解
+//!
+//! 按 IP 和用户名分别统计时间窗口内的登录失败次数。失败次数越多，下一次尝试前的等待越长；
+//! 超过上限后锁定一段时间，锁定期间直接拒绝登录。用户名的锁定可由管理员提前解除。
+
+use std::time::Duration;
+
+use redis::Client;
+
+use crate::{config::LoginGuardConfig, error::AppError, rdb, Result};
+
+const PREFIX: &str = "axum_rs:login_guard:";
+/// 第一次失败后的等待（毫秒），之后每失败一次翻倍
+const DELAY_BASE_MS: u64 = 250;
+/// 最长的等待（毫秒）
+const DELAY_MAX_MS: u64 = 5000;
+
+/// 统计的对象
+#[derive(Clone, Copy)]
+enum Kind {
+    Ip,
+    Username,
+}
+impl Kind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Ip => "ip",
+            Kind::Username => "username",
+        }
+    }
+}
+
+fn failures_key(kind: Kind, value: &str) -> String {
+    format!("{}failures:{}:{}", PREFIX, kind.as_str(), value)
+}
+fn lock_key(kind: Kind, value: &str) -> String {
+    format!("{}lock:{}:{}", PREFIX, kind.as_str(), value)
+}
+
+/// 登录前检查 IP 和用户名是否已被锁定
+pub async fn check(client: &Client, ip: &str, username: &str) -> Result<()> {
+    for key in [lock_key(Kind::Ip, ip), lock_key(Kind::Username, username)] {
+        let ttl = rdb::ttl(client, &key).await?;
+        if ttl > 0 {
+            // 向上取整到分钟
+            let minutes = (ttl + 59) / 60;
+            return Err(AppError::too_many_requests(&format!(
+                "登录失败次数过多，请在{}分钟后重试",
+                minutes
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 本次尝试前需要等待的时间，按 IP 和用户名中失败次数较多的计算
+pub async fn delay(
+    client: &Client,
+    cfg: &LoginGuardConfig,
+    ip: &str,
+    username: &str,
+) -> Result<Duration> {
+    let ip_failures = rdb::window_count(client, &failures_key(Kind::Ip, ip), cfg.window).await?;
+    let user_failures =
+        rdb::window_count(client, &failures_key(Kind::Username, username), cfg.window).await?;
+    let failures = ip_failures.max(user_failures) as u32;
+    if failures == 0 {
+        return Ok(Duration::ZERO);
+    }
+    let ms = DELAY_BASE_MS
+        .saturating_mul(2u64.saturating_pow(failures - 1))
+        .min(DELAY_MAX_MS);
+    Ok(Duration::from_millis(ms))
+}
+
+/// 记录一次失败，超过上限时锁定。返回本次是否导致锁定
+pub async fn record_failure(
+    client: &Client,
+    cfg: &LoginGuardConfig,
+    ip: &str,
+    username: &str,
+) -> Result<bool> {
+    let mut locked = false;
+    for (kind, value, max) in [
+        (Kind::Ip, ip, cfg.max_ip_failures),
+        (Kind::Username, username, cfg.max_user_failures),
+    ] {
+        let failures = rdb::window_add(client, &failures_key(kind, value), cfg.window).await?;
+        if failures >= max {
+            rdb::set(client, &lock_key(kind, value), "1", cfg.lockout).await?;
+            // 解锁后重新计数
+            rdb::del(client, &failures_key(kind, value)).await?;
+            locked = true;
+        }
+    }
+    Ok(locked)
+}
+
+/// 登录成功后清除该用户名的失败记录。IP 的记录保留，避免用一个账号掩护对其它账号的尝试
+pub async fn clear(client: &Client, username: &str) -> Result<()> {
+    rdb::del(client, &failures_key(Kind::Username, username)).await
+}
+
+/// 用户名是否被锁定
+pub async fn is_locked(client: &Client, username: &str) -> Result<bool> {
+    rdb::is_exists(client, &lock_key(Kind::Username, username)).await
+}
+
+/// 解除用户名的锁定，并清除失败记录
+pub async fn unlock(client: &Client, username: &str) -> Result<()> {
+    rdb::del(client, &lock_key(Kind::Username, username)).await?;
+    clear(client, username).await
+}
@@ -1,19 +1,29 @@
 //#![recursion_limit = "256"]
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
 use axum::{
     extract::extractor_middleware,
     extract::Extension,
     http::StatusCode,
-    routing::{get, get_service},
+    routing::{get, get_service, post},
     Router,
 };
 use axum_rs::{
-    config,
-    handler::{auth, backend, frontend},
-    middleware::admin_auth::Auth,
+    audit,
+    cache::Cache,
+    captcha,
+    cli::{self, Cli, Command},
+    config, db,
+    error::AppError,
+    handler::{api, auth, backend, frontend},
+    md,
+    middleware::{admin_auth::Auth, csrf::VerifyCsrf, rate_limit::RateLimitLayer},
+    migrate,
     model::AppState,
+    rate_limit::{Group, MemoryLimiter},
+    storage,
 };
+use clap::Parser;
 use dotenv::dotenv;
 use tower_http::services::ServeDir;
 
@@ -25,21 +35,56 @@ async fn main() {
     tracing_subscriber::fmt::init();
 
     dotenv().ok();
+    let cli = Cli::parse();
     let cfg = config::Config::from_env().unwrap();
+    let result = match cli.command {
+        None | Some(Command::Serve) => serve(cfg).await,
+        Some(command) => cli::run(cfg, command).await,
+    };
+    if let Err(err) = result {
+        tracing::error!("{}", err.message());
+        tracing::debug!("{:?}", err);
+        std::process::exit(1);
+    }
+}
+
+async fn serve(cfg: config::Config) -> Result<(), AppError> {
     let pool = cfg.pg.create_pool(None, tokio_postgres::NoTls).unwrap();
+    {
+        let mut client = pool.get().await.map_err(AppError::from)?;
+        migrate::check(&mut client, cfg.migrate.auto).await?;
+        // 迁移后尚未生成全文搜索纯文本的文章
+        let filled = db::topic::refresh_search_text(&**client, "is_pending", &[]).await?;
+        if filled > 0 {
+            tracing::info!("已为{}篇文章生成全文搜索内容", filled);
+        }
+    }
     let rdc = redis::Client::open(cfg.redis.dsn).unwrap();
+    let cache = Cache::new(rdc.clone());
     tracing::info!("Web服务监听于{}", &cfg.web.addr);
 
     let state = Arc::new(AppState {
+        web_cfg: cfg.web.clone(),
         pool,
         rdc,
         sess_cfg: cfg.session,
-        hcap_cfg: cfg.hcaptcha,
-        recap_cfg: cfg.recaptcha,
+        login_guard_cfg: cfg.login_guard,
+        trusted_proxies: audit::TrustedProxies::parse(&cfg.web.trusted_proxies),
+        rate_limit_cfg: cfg.rate_limit,
+        rate_limiter: MemoryLimiter::default(),
+        cache,
+        captcha: captcha::from_config(&cfg.captcha)?,
+        robots_cfg: cfg.robots,
+        sanitizer: md::Sanitizer::new(&cfg.sanitize),
+        storage: storage::from_config(&cfg.upload)?,
+        upload_cfg: cfg.upload,
     });
 
-    let backend_router = backend::routers().layer(extractor_middleware::<Auth>());
+    let backend_router = backend::routers()
+        .layer(extractor_middleware::<VerifyCsrf>())
+        .layer(extractor_middleware::<Auth>());
     let frontend_router = frontend::routers();
+    let api_router = api::routers();
     let static_serve = get_service(ServeDir::new("static")).handle_error(|err| async move {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -51,11 +96,31 @@ async fn main() {
         .nest("/", frontend_router)
         .nest("/static", static_serve)
         .nest("/admin", backend_router)
-        .route("/login", get(auth::admin_login_ui).post(auth::admin_login))
-        .route("/logout", get(auth::admin_logout))
+        .nest("/api/v1", api_router)
+        // 只限制提交，不限制打开登录页
+        .route(
+            "/login",
+            get(auth::admin_login_ui)
+                .merge(post(auth::admin_login).layer(RateLimitLayer::new(Group::Login))),
+        )
+        .route(
+            "/login/2fa",
+            get(auth::admin_two_factor_ui)
+                .merge(post(auth::admin_two_factor).layer(RateLimitLayer::new(Group::Login))),
+        )
+        .route(
+            "/login/2fa/enroll",
+            post(auth::admin_two_factor_enroll).layer(RateLimitLayer::new(Group::Login)),
+        )
+        .route(
+            "/logout",
+            post(auth::admin_logout).layer(extractor_middleware::<VerifyCsrf>()),
+        )
         .layer(Extension(state));
     axum::Server::bind(&cfg.web.addr.parse().unwrap())
-        .serve(app.into_make_service())
+        // 审计日志需要客户端的地址
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap();
+    Ok(())
 }
@@ -0,0 +1,55 @@
+//! 服务端代码高亮，目前支持 Rust、TOML 和 Shell，其它语言仍由前端的 highlight.js 处理
+use std::sync::OnceLock;
+
+use syntect::{
+    html::{ClassStyle, ClassedHTMLGenerator},
+    parsing::{SyntaxDefinition, SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// 高亮结果中 CSS 类名的前缀，样式见`static/css/highlight.css`
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(|| {
+        let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+        match SyntaxDefinition::load_from_str(include_str!("toml.sublime-syntax"), true, None) {
+            Ok(toml) => builder.add(toml),
+            Err(err) => tracing::error!("加载 TOML 语法定义失败：{:?}", err),
+        }
+        builder.build()
+    })
+}
+
+/// 根据代码块的语言标记查找语法，不支持的语言返回 None
+fn find_syntax(lang: &str) -> Option<&'static SyntaxReference> {
+    let ext = match lang.to_lowercase().as_str() {
+        "rust" | "rs" => "rs",
+        "toml" => "toml",
+        "sh" | "bash" | "shell" | "zsh" | "console" => "sh",
+        _ => return None,
+    };
+    syntax_set().find_syntax_by_extension(ext)
+}
+
+/// 代码块的语言，形如 ```` ```rust,ignore ```` 的只取第一个
+pub fn lang_of(info: &str) -> &str {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .next()
+        .unwrap_or_default()
+}
+
+/// 高亮代码，返回`<code>`内的 HTML。不支持的语言返回 None
+pub fn highlight(lang: &str, code: &str) -> Option<String> {
+    let syntax = find_syntax(lang)?;
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, syntax_set(), CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        if let Err(err) = generator.parse_html_for_line_which_includes_newline(line) {
+            tracing::error!("高亮 {} 代码失败：{:?}", lang, err);
+            return None;
+        }
+    }
+    Some(generator.finalize())
+}
@@ -0,0 +1,355 @@
+use std::collections::HashSet;
+
+use pulldown_cmark::{
+    escape::{escape_href, escape_html},
+    html, CodeBlockKind, Event, HeadingLevel, LinkType, Options, Parser, Tag,
+};
+
+use crate::protect;
+
+mod highlight;
+mod sanitize;
+
+pub use sanitize::Sanitizer;
+
+/// 提示块，`:::note 标题`，标题可省略
+const ADMONITIONS: [(&str, &str, &str); 5] = [
+    ("note", "info", "注意"),
+    ("info", "info", "信息"),
+    ("tip", "success", "提示"),
+    ("warning", "warning", "警告"),
+    ("danger", "danger", "危险"),
+];
+
+/// 渲染结果
+pub struct Rendered {
+    pub html: String,
+    /// 文章目录，没有标题时为空
+    pub toc: String,
+}
+
+fn get_parser(md: &str) -> Parser<'_, '_> {
+    Parser::new_ext(md, Options::all())
+}
+
+/// 只需要 HTML 的场景，比如 RSS 中的摘要
+pub fn to_html(md: &str, sanitizer: &Sanitizer) -> String {
+    render(md, sanitizer).html
+}
+
+/// 渲染 Markdown，结果经过白名单过滤
+pub fn render(md: &str, sanitizer: &Sanitizer) -> Rendered {
+    let mut renderer = Renderer::default();
+    let mut out_html = String::new();
+    let mut in_protected = false;
+    for block in split_blocks(md) {
+        match block {
+            Block::Markdown(segment) => renderer.push_html(&mut out_html, &segment, in_protected),
+            Block::Open(Container::Protected) => {
+                in_protected = true;
+                out_html.push_str(protect::BEGIN_MARKER);
+            }
+            Block::Close(Container::Protected) => {
+                in_protected = false;
+                out_html.push_str(protect::END_MARKER);
+            }
+            Block::Open(Container::Admonition { kind, class, title }) => {
+                out_html.push_str(&format!(
+                    "<div class=\"callout callout-{} admonition admonition-{}\">\n<p class=\"admonition-title\">",
+                    class, kind
+                ));
+                escape_html(&mut out_html, &title).ok();
+                out_html.push_str("</p>\n");
+            }
+            Block::Close(Container::Admonition { .. }) => out_html.push_str("</div>\n"),
+        }
+    }
+    Rendered {
+        html: sanitizer.clean(&out_html),
+        toc: renderer.toc_html(),
+    }
+}
+
+/// 正在收集内容的标题
+struct Heading<'a> {
+    level: HeadingLevel,
+    id: Option<&'a str>,
+    classes: Vec<&'a str>,
+    inner: Vec<Event<'a>>,
+}
+
+struct TocItem {
+    level: usize,
+    id: String,
+    text: String,
+}
+
+/// 在多个片段之间共享标题 ID 和目录
+#[derive(Default)]
+struct Renderer {
+    ids: HashSet<String>,
+    toc: Vec<TocItem>,
+}
+
+impl Renderer {
+    fn push_html(&mut self, out_html: &mut String, md: &str, in_protected: bool) {
+        let mut events = vec![];
+        let mut heading: Option<Heading> = None;
+        let mut code: Option<(String, String)> = None;
+        for event in get_parser(md) {
+            if let Some((_, text)) = code.as_mut() {
+                match event {
+                    Event::Text(t) => text.push_str(&t),
+                    Event::End(Tag::CodeBlock(kind)) => {
+                        let (lang, text) = code.take().unwrap_or_default();
+                        // 不支持的语言原样输出，交给前端高亮
+                        match highlight::highlight(&lang, &text) {
+                            Some(h) => events.push(Event::Html(
+                                format!(
+                                    "<pre><code class=\"language-{} hl\">{}</code></pre>\n",
+                                    lang, h
+                                )
+                                .into(),
+                            )),
+                            None => {
+                                events.push(Event::Start(Tag::CodeBlock(kind.clone())));
+                                events.push(Event::Text(text.into()));
+                                events.push(Event::End(Tag::CodeBlock(kind)));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some(Heading {
+                level,
+                id,
+                classes,
+                inner,
+            }) = heading.as_mut()
+            {
+                if let Event::End(Tag::Heading(..)) = event {
+                    let text = heading_text(inner);
+                    let id = match id {
+                        Some(id) => {
+                            let mut escaped = String::new();
+                            escape_html(&mut escaped, id).ok();
+                            self.unique_id(escaped)
+                        }
+                        None => self.unique_id(slugify(&text)),
+                    };
+                    let level = *level as usize;
+                    let class = if classes.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" class=\"{}\"", classes.join(" "))
+                    };
+                    events.push(Event::Html(
+                        format!("<h{} id=\"{}\"{}>", level, id, class).into(),
+                    ));
+                    events.append(inner);
+                    events.push(Event::Html(
+                        format!(
+                            "<a class=\"heading-anchor\" href=\"#{}\">#</a></h{}>\n",
+                            id, level
+                        )
+                        .into(),
+                    ));
+                    // 隐藏的内容不出现在目录中
+                    if !in_protected {
+                        self.toc.push(TocItem { level, id, text });
+                    }
+                    heading = None;
+                } else {
+                    inner.push(event);
+                }
+                continue;
+            }
+            match event {
+                Event::Start(Tag::Heading(level, id, classes)) => {
+                    heading = Some(Heading {
+                        level,
+                        id,
+                        classes,
+                        inner: vec![],
+                    });
+                }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                    code = Some((highlight::lang_of(&info).to_string(), String::new()));
+                }
+                Event::Start(Tag::Link(link_type, dest, title))
+                    if is_external(link_type, &dest) =>
+                {
+                    events.push(Event::Html(external_link(&dest, &title).into()));
+                }
+                _ => events.push(event),
+            }
+        }
+        html::push_html(out_html, events.into_iter());
+    }
+
+    /// 重复的标题依次加上`-1`、`-2`等后缀
+    fn unique_id(&mut self, base: String) -> String {
+        let mut id = base.clone();
+        let mut n = 1;
+        while !self.ids.insert(id.clone()) {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+        id
+    }
+
+    fn toc_html(&self) -> String {
+        let min_level = match self.toc.iter().map(|item| item.level).min() {
+            Some(l) => l,
+            None => return String::new(),
+        };
+        let mut out = String::from("<ul class=\"toc\">\n");
+        for item in self.toc.iter() {
+            out.push_str(&format!(
+                "<li class=\"toc-level-{}\"><a href=\"#{}\">",
+                item.level - min_level,
+                item.id
+            ));
+            escape_html(&mut out, &item.text).ok();
+            out.push_str("</a></li>\n");
+        }
+        out.push_str("</ul>\n");
+        out
+    }
+}
+
+fn heading_text(events: &[Event]) -> String {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Text(t) | Event::Code(t) => Some(t.as_ref()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+/// 生成标题 ID：保留字母、数字（包括中文），空白和连字符合并为一个`-`
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+fn is_external(link_type: LinkType, dest: &str) -> bool {
+    link_type != LinkType::Email
+        && (dest.starts_with("http://") || dest.starts_with("https://") || dest.starts_with("//"))
+}
+
+fn external_link(dest: &str, title: &str) -> String {
+    let mut out = String::from("<a href=\"");
+    escape_href(&mut out, dest).ok();
+    if !title.is_empty() {
+        out.push_str("\" title=\"");
+        escape_html(&mut out, title).ok();
+    }
+    out.push_str("\" rel=\"noopener noreferrer\" target=\"_blank\">");
+    out
+}
+
+#[derive(Clone)]
+enum Container {
+    /// `:::protected`，需要隐藏的内容
+    Protected,
+    Admonition {
+        kind: &'static str,
+        class: &'static str,
+        title: String,
+    },
+}
+
+enum Block {
+    Markdown(String),
+    Open(Container),
+    Close(Container),
+}
+
+fn parse_container(line: &str, in_protected: bool) -> Option<Container> {
+    let rest = line.strip_prefix(":::")?.trim();
+    let (name, title) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    if name == "protected" {
+        // 不支持嵌套隐藏
+        return if in_protected {
+            None
+        } else {
+            Some(Container::Protected)
+        };
+    }
+    ADMONITIONS
+        .iter()
+        .find(|(kind, _, _)| *kind == name)
+        .map(|(kind, class, default_title)| Container::Admonition {
+            kind,
+            class,
+            title: match title.trim() {
+                "" => default_title.to_string(),
+                t => t.to_string(),
+            },
+        })
+}
+
+/// 按`:::protected`、`:::note`等开始标记和`:::`结束标记切分 Markdown。
+/// 代码块中的标记不会被识别，没有结束标记时一直到文末
+fn split_blocks(md: &str) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut current = String::new();
+    let mut stack: Vec<Container> = vec![];
+    let mut fence: Option<&str> = None;
+    for line in md.lines() {
+        let trimmed = line.trim();
+        if let Some(f) = fence {
+            if trimmed.starts_with(f) {
+                fence = None;
+            }
+        } else if trimmed.starts_with("```") {
+            fence = Some("```");
+        } else if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+        } else if trimmed == ":::" && !stack.is_empty() {
+            if !current.is_empty() {
+                blocks.push(Block::Markdown(std::mem::take(&mut current)));
+            }
+            if let Some(c) = stack.pop() {
+                blocks.push(Block::Close(c));
+            }
+            continue;
+        } else if let Some(c) = parse_container(
+            trimmed,
+            stack.iter().any(|c| matches!(c, Container::Protected)),
+        ) {
+            if !current.is_empty() {
+                blocks.push(Block::Markdown(std::mem::take(&mut current)));
+            }
+            stack.push(c.clone());
+            blocks.push(Block::Open(c));
+            continue;
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.is_empty() {
+        blocks.push(Block::Markdown(current));
+    }
+    while let Some(c) = stack.pop() {
+        blocks.push(Block::Close(c));
+    }
+    blocks
+}
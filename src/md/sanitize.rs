@@ -0,0 +1,151 @@
+//! 渲染结果的 HTML 过滤，只保留白名单中的标签和属性
+use std::{borrow::Cow, iter};
+
+use ammonia::Builder;
+
+use crate::config::SanitizeConfig;
+
+/// 在 ammonia 默认白名单的基础上，渲染 Markdown 还需要的标签属性
+const TAG_ATTRIBUTES: [(&str, &[&str]); 8] = [
+    ("h1", &["id"]),
+    ("h2", &["id"]),
+    ("h3", &["id"]),
+    ("h4", &["id"]),
+    ("h5", &["id"]),
+    ("h6", &["id"]),
+    ("a", &["rel", "target"]),
+    // 任务列表的复选框
+    ("input", &["type", "checked", "disabled"]),
+];
+
+/// 嵌入视频等内容时 iframe 可用的属性
+const IFRAME_ATTRIBUTES: [&str; 8] = [
+    "src",
+    "width",
+    "height",
+    "title",
+    "allow",
+    "allowfullscreen",
+    "frameborder",
+    "loading",
+];
+
+/// 不论如何配置都不允许的属性，此外事件处理属性`on*`也不允许
+const FORBIDDEN_ATTRIBUTES: [&str; 3] = ["style", "srcdoc", "formaction"];
+
+/// 不论如何配置都不允许的标签
+const FORBIDDEN_TAGS: [&str; 10] = [
+    "script", "style", "iframe", "object", "embed", "form", "base", "meta", "link", "frame",
+];
+
+fn split_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|item| item.trim().to_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// 只允许 https 协议且域名在白名单中的 iframe
+fn is_trusted_embed(hosts: &[String], src: &str) -> bool {
+    let rest = match src.trim().strip_prefix("https://") {
+        Some(r) => r,
+        None => return false,
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if authority.contains('@') {
+        return false;
+    }
+    let host = authority
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    hosts.iter().any(|h| h == &host)
+}
+
+pub struct Sanitizer {
+    /// 额外允许的标签
+    tags: Vec<String>,
+    /// 额外允许的属性，`(标签, 属性)`，标签为`*`时对所有标签有效
+    attributes: Vec<(String, String)>,
+    /// 允许嵌入的 iframe 域名
+    iframe_hosts: Vec<String>,
+}
+
+impl Sanitizer {
+    pub fn new(cfg: &SanitizeConfig) -> Self {
+        let tags = split_list(&cfg.tags)
+            .into_iter()
+            .filter(|tag| {
+                let forbidden = FORBIDDEN_TAGS.contains(&tag.as_str());
+                if forbidden {
+                    tracing::warn!("HTML 过滤：不允许开放 <{}> 标签，已忽略", tag);
+                }
+                !forbidden
+            })
+            .collect();
+        let attributes = split_list(&cfg.attributes)
+            .into_iter()
+            .filter_map(|item| match item.split_once(':') {
+                Some((_, attr))
+                    if attr.starts_with("on") || FORBIDDEN_ATTRIBUTES.contains(&attr) =>
+                {
+                    tracing::warn!("HTML 过滤：不允许开放 {} 属性，已忽略", attr);
+                    None
+                }
+                Some((tag, attr)) if !tag.is_empty() && !attr.is_empty() => {
+                    Some((tag.to_string(), attr.to_string()))
+                }
+                _ => {
+                    tracing::warn!("HTML 过滤：属性配置 {} 的格式应为 标签:属性，已忽略", item);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            tags,
+            attributes,
+            iframe_hosts: split_list(&cfg.iframe_hosts),
+        }
+    }
+
+    fn builder(&self) -> Builder<'_> {
+        let mut builder = Builder::default();
+        builder
+            // 隐藏内容的标记是 HTML 注释
+            .strip_comments(false)
+            // 外部链接的 rel 在渲染时已经设置
+            .link_rel(None)
+            .add_tags(&["input"])
+            .add_tags(self.tags.iter().map(String::as_str))
+            .add_generic_attributes(&["class"]);
+        for (tag, attrs) in TAG_ATTRIBUTES {
+            builder.add_tag_attributes(tag, attrs.iter());
+        }
+        for (tag, attr) in self.attributes.iter() {
+            if tag == "*" {
+                builder.add_generic_attributes(iter::once(attr.as_str()));
+            } else {
+                builder.add_tag_attributes(tag.as_str(), iter::once(attr.as_str()));
+            }
+        }
+        if !self.iframe_hosts.is_empty() {
+            builder
+                .add_tags(&["iframe"])
+                .add_tag_attributes("iframe", IFRAME_ATTRIBUTES.iter());
+        }
+        let iframe_hosts = self.iframe_hosts.clone();
+        builder.attribute_filter(
+            move |element, attribute, value| match (element, attribute) {
+                ("iframe", "src") if !is_trusted_embed(&iframe_hosts, value) => None,
+                ("input", "type") => Some(Cow::Borrowed("checkbox")),
+                _ => Some(Cow::Borrowed(value)),
+            },
+        );
+        builder
+    }
+
+    pub fn clean(&self, html: &str) -> String {
+        self.builder().clean(html).to_string()
+    }
+}
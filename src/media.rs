@@ -0,0 +1,207 @@
+//! 上传文件的校验与处理
+//!
+//! 文件类型根据内容判断，不信任客户端提供的类型和扩展名；
+//! 文件名使用内容的 SHA-256，相同的文件只保存一份。
+
+use std::io::Cursor;
+
+use image::{imageops::FilterType, io::Limits, ImageFormat};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{AppError, AppErrorType},
+    Result,
+};
+
+/// 允许上传的文件类型
+#[derive(Clone, Copy)]
+pub struct FileType {
+    pub content_type: &'static str,
+    pub ext: &'static str,
+    /// 图片才生成缩略图
+    pub image: Option<ImageFormat>,
+}
+
+const PNG: FileType = FileType {
+    content_type: "image/png",
+    ext: "png",
+    image: Some(ImageFormat::Png),
+};
+const JPEG: FileType = FileType {
+    content_type: "image/jpeg",
+    ext: "jpg",
+    image: Some(ImageFormat::Jpeg),
+};
+const GIF: FileType = FileType {
+    content_type: "image/gif",
+    ext: "gif",
+    image: Some(ImageFormat::Gif),
+};
+const WEBP: FileType = FileType {
+    content_type: "image/webp",
+    ext: "webp",
+    image: Some(ImageFormat::WebP),
+};
+const PDF: FileType = FileType {
+    content_type: "application/pdf",
+    ext: "pdf",
+    image: None,
+};
+const ZIP: FileType = FileType {
+    content_type: "application/zip",
+    ext: "zip",
+    image: None,
+};
+const GZIP: FileType = FileType {
+    content_type: "application/gzip",
+    ext: "gz",
+    image: None,
+};
+
+/// 根据文件头判断类型，不在白名单中的返回 None。
+/// SVG 等可能包含脚本的类型不允许上传
+pub fn detect(data: &[u8]) -> Option<FileType> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(PNG)
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some(JPEG)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some(GIF)
+    } else if data.len() > 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some(WEBP)
+    } else if data.starts_with(b"%PDF-") {
+        Some(PDF)
+    } else if data.starts_with(b"PK\x03\x04") {
+        Some(ZIP)
+    } else if data.starts_with(&[0x1f, 0x8b]) {
+        Some(GZIP)
+    } else {
+        None
+    }
+}
+
+/// 文件内容的 SHA-256
+pub fn hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 保存的路径，按哈希的前两位分目录
+pub fn key(hash: &str, ext: &str) -> String {
+    format!("{}/{}.{}", &hash[..2], hash, ext)
+}
+
+/// 缩略图的保存路径
+pub fn thumb_key(hash: &str, ext: &str) -> String {
+    format!("{}/{}_thumb.{}", &hash[..2], hash, ext)
+}
+
+/// 便于阅读的文件大小
+pub fn size_text(size: u64) -> String {
+    match size {
+        s if s >= 1024 * 1024 => format!("{:.1}MB", s as f64 / 1024.0 / 1024.0),
+        s if s >= 1024 => format!("{:.1}KB", s as f64 / 1024.0),
+        s => format!("{}B", s),
+    }
+}
+
+/// 缩略图
+pub struct Thumbnail {
+    /// 原图的宽度
+    pub width: u32,
+    /// 原图的高度
+    pub height: u32,
+    pub data: Vec<u8>,
+    pub file_type: FileType,
+}
+
+/// 解码图片时允许的最大宽度和高度
+const MAX_IMAGE_DIMENSION: u32 = 10_000;
+/// 解码图片时允许分配的最大内存
+const MAX_IMAGE_ALLOC: u64 = 256 * 1024 * 1024;
+
+/// 生成不超过`size`×`size`的缩略图。JPEG 保持为 JPEG，其它格式输出 PNG
+///
+/// 解码时限制图片尺寸和内存，防止很小的文件声明巨大的尺寸耗尽内存
+pub fn thumbnail(data: &[u8], format: ImageFormat, size: u32) -> Result<Thumbnail> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+    limits.max_alloc = Some(MAX_IMAGE_ALLOC);
+    let mut reader = image::io::Reader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+    let img = reader.decode().map_err(|err| {
+        AppError::from_err(format!("无法解析图片：{}", err), AppErrorType::Common)
+    })?;
+    let (width, height) = (img.width(), img.height());
+    let thumb = if width > size || height > size {
+        img.resize(size, size, FilterType::Triangle)
+    } else {
+        img
+    };
+    let file_type = if format == ImageFormat::Jpeg {
+        JPEG
+    } else {
+        PNG
+    };
+    let mut out = Cursor::new(Vec::new());
+    let result = match file_type.image {
+        Some(ImageFormat::Jpeg) => thumb.to_rgb8().write_to(&mut out, ImageFormat::Jpeg),
+        _ => thumb.write_to(&mut out, ImageFormat::Png),
+    };
+    result.map_err(|err| {
+        AppError::from_err(format!("生成缩略图失败：{}", err), AppErrorType::Common)
+    })?;
+    Ok(Thumbnail {
+        width,
+        height,
+        data: out.into_inner(),
+        file_type,
+    })
+}
+
+/// 新上传的文件，写入`media`表
+pub struct NewMedia {
+    pub admin_id: i32,
+    pub hash: String,
+    pub storage: &'static str,
+    pub path: String,
+    pub thumb_path: String,
+    pub url: String,
+    pub thumb_url: String,
+    pub file_name: String,
+    pub content_type: &'static str,
+    pub size: i64,
+    pub width: i32,
+    pub height: i32,
+    pub is_image: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut out = Cursor::new(Vec::new());
+        image::DynamicImage::new_luma8(width, height)
+            .write_to(&mut out, ImageFormat::Png)
+            .unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn thumbnail_resizes() {
+        let thumb = thumbnail(&png(400, 200), ImageFormat::Png, 100).unwrap();
+        assert_eq!((thumb.width, thumb.height), (400, 200));
+        let img = image::load_from_memory_with_format(&thumb.data, ImageFormat::Png).unwrap();
+        assert_eq!((img.width(), img.height()), (100, 50));
+    }
+
+    #[test]
+    fn thumbnail_rejects_huge_dimensions() {
+        let data = png(MAX_IMAGE_DIMENSION + 1, 1);
+        assert!(thumbnail(&data, ImageFormat::Png, 100).is_err());
+    }
+}
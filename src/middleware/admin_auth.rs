@@ -5,7 +5,11 @@ use axum::{
     extract::{FromRequest, RequestParts},
 };
 
-use crate::{error::AppError, handler::backend::get_logined_admin, model::AppState};
+use crate::{
+    error::AppError,
+    handler::backend::get_logined_admin,
+    model::{AdminSession, AppState},
+};
 
 pub struct Auth {}
 #[async_trait]
@@ -15,12 +19,23 @@ where
 {
     type Rejection = AppError;
     async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
-        let state = req.extensions().get::<Arc<AppState>>().unwrap();
-        let headers = req.headers();
-        let admin_session = get_logined_admin(&state, headers).await?;
-        if let Some(_) = admin_session {
+        if let Some(admin_session) = logined_admin(req).await? {
+            // 保存到请求中，之后的权限检查不必再次读取会话
+            req.extensions_mut().insert(admin_session);
             return Ok(Self {});
         }
         Err(AppError::auth_error("UNAUTHENTICATED"))
     }
 }
+
+/// 当前请求的管理员会话，优先使用[`Auth`]已经读取的
+pub async fn logined_admin<B>(req: &mut RequestParts<B>) -> Result<Option<AdminSession>, AppError>
+where
+    B: Send,
+{
+    if let Some(admin_session) = req.extensions().get::<AdminSession>() {
+        return Ok(Some(admin_session.clone()));
+    }
+    let state = req.extensions().get::<Arc<AppState>>().unwrap();
+    get_logined_admin(state, req.headers()).await
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, RequestParts},
+    http::header::AUTHORIZATION,
+};
+
+use crate::{
+    db::{admin, admin_token},
+    error::{ApiError, AppError},
+    handler::helper::get_client,
+    model::AppState,
+    role::{Permission, Role},
+    token,
+};
+
+/// 通过`Authorization: Bearer <令牌>`认证的管理员
+#[derive(Clone)]
+pub struct ApiAuth {
+    pub admin_id: i32,
+    /// 令牌所属管理员的角色，令牌的权限与之相同
+    pub role: Role,
+}
+impl ApiAuth {
+    /// 没有权限时返回 403 错误
+    pub fn require(&self, permission: Permission) -> Result<(), ApiError> {
+        self.role.require(permission).map_err(ApiError::from)
+    }
+}
+#[async_trait]
+impl<B> FromRequest<B> for ApiAuth
+where
+    B: Send,
+{
+    type Rejection = ApiError;
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        // 同一请求中已经认证过（如路由层和处理函数都需要时），直接复用
+        if let Some(auth) = req.extensions().get::<Self>() {
+            return Ok(auth.clone());
+        }
+        let state = req.extensions().get::<Arc<AppState>>().unwrap();
+        let bearer = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty());
+        let bearer = match bearer {
+            Some(bearer) => bearer,
+            None => return Err(AppError::auth_error("UNAUTHENTICATED").into()),
+        };
+        let client = get_client(state, "api_auth").await?;
+        let admin_token = admin_token::find_by_hash(&client, &token::hash(bearer))
+            .await
+            .map_err(|err| {
+                tracing::debug!("find token failed: {:?}", err);
+                AppError::auth_error("UNAUTHENTICATED")
+            })?;
+        // 账号删除后，其令牌随之失效
+        let token_admin =
+            admin::find_by_condition(&client, "id=$1 AND is_del=false", &[&admin_token.admin_id])
+                .await
+                .map_err(|err| {
+                    tracing::debug!("find token admin failed: {:?}", err);
+                    AppError::auth_error("UNAUTHENTICATED")
+                })?;
+        admin_token::touch(&client, admin_token.id).await?;
+        let auth = Self {
+            admin_id: admin_token.admin_id,
+            role: token_admin.role(),
+        };
+        req.extensions_mut().insert(auth.clone());
+        Ok(auth)
+    }
+}
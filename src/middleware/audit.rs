@@ -0,0 +1,41 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequest, RequestParts},
+};
+
+use crate::{audit::Audit, error::AppError, model::AppState};
+
+use super::{admin_auth::logined_admin, api_auth::ApiAuth};
+
+/// 审计日志需要的操作者信息。后台使用登录的管理员，API 使用令牌所属的管理员
+#[async_trait]
+impl<B> FromRequest<B> for Audit
+where
+    B: Send,
+{
+    type Rejection = AppError;
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let admin_id = match req.extensions().get::<ApiAuth>() {
+            Some(auth) => auth.admin_id,
+            None => {
+                logined_admin(req)
+                    .await?
+                    .ok_or_else(|| AppError::auth_error("UNAUTHENTICATED"))?
+                    .id
+            }
+        };
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr);
+        let state = req.extensions().get::<Arc<AppState>>().unwrap();
+        Ok(Audit::new(
+            admin_id,
+            req.headers(),
+            peer,
+            &state.trusted_proxies,
+        ))
+    }
+}
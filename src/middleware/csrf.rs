@@ -0,0 +1,140 @@
+//! 后台的 CSRF 检查
+//!
+//! 用法：`router.layer(extractor_middleware::<VerifyCsrf>())`，GET、HEAD、OPTIONS 以外的请求
+//! 需要提交当前会话的令牌，不一致时返回 403 页面。
+
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{
+    async_trait,
+    body::{Body, Bytes, HttpBody},
+    extract::{FromRequest, RequestParts},
+    http::{header::CONTENT_TYPE, Method},
+};
+use futures_util::StreamExt;
+
+use crate::{
+    csrf,
+    error::{AppError, AppErrorType},
+    handler::helper::get_cookie,
+    model::AppState,
+};
+
+/// 当前会话的令牌，用于在模板中输出。未登录时为空
+pub struct CsrfToken(pub String);
+#[async_trait]
+impl<B> FromRequest<B> for CsrfToken
+where
+    B: Send,
+{
+    type Rejection = AppError;
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let state = req.extensions().get::<Arc<AppState>>().unwrap();
+        let token = get_cookie(req.headers(), &state.sess_cfg.id_name)
+            .map(|id| csrf::token(&state.web_cfg.secret_key, &id))
+            .unwrap_or_default();
+        Ok(Self(token))
+    }
+}
+
+pub struct VerifyCsrf;
+#[async_trait]
+impl FromRequest<Body> for VerifyCsrf {
+    type Rejection = AppError;
+    async fn from_request(req: &mut RequestParts<Body>) -> Result<Self, Self::Rejection> {
+        if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+            return Ok(Self);
+        }
+        let token = submitted_token(req).await?.unwrap_or_default();
+        let state = req.extensions().get::<Arc<AppState>>().unwrap();
+        let session_id = get_cookie(req.headers(), &state.sess_cfg.id_name).unwrap_or_default();
+        if !csrf::verify(&state.web_cfg.secret_key, &session_id, &token) {
+            return Err(AppError::forbidden("页面已过期，请刷新后重试"));
+        }
+        Ok(Self)
+    }
+}
+
+/// 普通表单的最大字节数，超过时返回 413
+const FORM_MAX_SIZE: usize = 4 * 1024 * 1024;
+/// 上传文件的表单中，令牌必须位于开头的这些字节内。表单模板把令牌放在第一个字段，
+/// 所以只需读取表单的开头，文件内容仍以流的方式交给处理函数，不会在这里读入内存
+const MULTIPART_TOKEN_SIZE: usize = 64 * 1024;
+
+/// 依次从请求头和表单中读取提交的令牌。令牌不通过查询参数提交，以免出现在日志和`Referer`中
+async fn submitted_token(req: &mut RequestParts<Body>) -> Result<Option<String>, AppError> {
+    if let Some(token) = req
+        .headers()
+        .get(csrf::HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        return Ok(Some(token.to_string()));
+    }
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    // 上传文件的表单
+    let boundary = multer::parse_boundary(content_type).ok();
+    if boundary.is_none() && !content_type.starts_with("application/x-www-form-urlencoded") {
+        return Ok(None);
+    }
+    let mut body = match req.take_body() {
+        Some(body) => body,
+        None => return Ok(None),
+    };
+    let mut read = Vec::new();
+    let token = match boundary {
+        Some(boundary) => {
+            let mut token = None;
+            while token.is_none() && read.len() < MULTIPART_TOKEN_SIZE {
+                match body.data().await {
+                    Some(chunk) => read.extend_from_slice(&chunk.map_err(body_error)?),
+                    None => break,
+                }
+                token = find_multipart_field(Bytes::copy_from_slice(&read), boundary.clone()).await;
+            }
+            token
+        }
+        None => {
+            while let Some(chunk) = body.data().await {
+                let chunk = chunk.map_err(body_error)?;
+                if read.len() + chunk.len() > FORM_MAX_SIZE {
+                    return Err(AppError::payload_too_large("提交的内容过大"));
+                }
+                read.extend_from_slice(&chunk);
+            }
+            std::str::from_utf8(&read).ok().and_then(find_field)
+        }
+    };
+    // 已读取的部分放回请求，处理函数仍然可以解析；未读取的部分继续以流的方式读取
+    let read = Bytes::from(read);
+    let head = futures_util::stream::once(async move { Ok(read) });
+    *req.body_mut() = Some(Body::wrap_stream(head.chain(body)));
+    Ok(token)
+}
+
+fn body_error(err: impl ToString) -> AppError {
+    AppError::from_err(err, AppErrorType::Common)
+}
+
+/// 在上传文件的表单中查找令牌
+async fn find_multipart_field(body: Bytes, boundary: String) -> Option<String> {
+    let stream = futures_util::stream::once(async move { Ok::<_, Infallible>(body) });
+    let mut multipart = multer::Multipart::new(stream, boundary);
+    while let Ok(Some(field)) = multipart.next_field().await {
+        if field.name() == Some(csrf::FIELD) {
+            return field.text().await.ok();
+        }
+    }
+    None
+}
+
+/// 在`a=1&b=2`格式的字符串中查找令牌。令牌是十六进制字符，不需要解码
+fn find_field(s: &str) -> Option<String> {
+    s.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == csrf::FIELD).then(|| value.to_string())
+    })
+}
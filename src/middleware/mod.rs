@@ -1 +1,6 @@
 pub mod admin_auth;
+pub mod api_auth;
+pub mod audit;
+pub mod csrf;
+pub mod permission;
+pub mod rate_limit;
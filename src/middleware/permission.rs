@@ -0,0 +1,61 @@
+//! 后台路由的权限检查
+//!
+//! 用法：`router.layer(extractor_middleware::<Require<ManageTag>>())`，
+//! 当前管理员的角色没有对应的权限时返回 403 页面。
+
+use std::marker::PhantomData;
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, RequestParts},
+};
+
+use crate::{error::AppError, role::Permission};
+
+use super::admin_auth::logined_admin;
+
+/// 路由所需的权限
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+macro_rules! required_permissions {
+    ($($name:ident),* $(,)?) => {
+        $(
+            pub struct $name;
+            impl RequiredPermission for $name {
+                const PERMISSION: Permission = Permission::$name;
+            }
+        )*
+    };
+}
+
+required_permissions!(
+    WriteTopic,
+    ManageTopic,
+    ManageSubject,
+    ManageTag,
+    ModerateComment,
+    UploadMedia,
+    ManageMedia,
+    ImportExport,
+    ManageAdmin,
+);
+
+/// 要求当前管理员拥有`P`对应的权限
+pub struct Require<P>(PhantomData<P>);
+#[async_trait]
+impl<B, P> FromRequest<B> for Require<P>
+where
+    B: Send,
+    P: RequiredPermission,
+{
+    type Rejection = AppError;
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let admin_session = logined_admin(req)
+            .await?
+            .ok_or_else(|| AppError::auth_error("UNAUTHENTICATED"))?;
+        admin_session.role.require(P::PERMISSION)?;
+        Ok(Self(PhantomData))
+    }
+}
@@ -0,0 +1,96 @@
+//! 按 IP 限制请求频率的中间件
+//!
+//! 用法：`post(handler).layer(RateLimitLayer::new(Group::Login))`。超出限制时返回 429，
+//! 并通过`Retry-After`告知需要等待的秒数。
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header::RETRY_AFTER, HeaderValue, Request},
+    response::{IntoResponse, Response},
+};
+use tower::{Layer, Service};
+
+use crate::{
+    audit::client_ip,
+    error::AppError,
+    model::AppState,
+    rate_limit::{self, Group},
+};
+
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    group: Group,
+}
+impl RateLimitLayer {
+    pub fn new(group: Group) -> Self {
+        Self { group }
+    }
+}
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            group: self.group,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    group: Group,
+}
+impl<S> Service<Request<Body>> for RateLimit<S>
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // 使用已经就绪的服务处理本次请求
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let group = self.group;
+        Box::pin(async move {
+            let state = req.extensions().get::<Arc<AppState>>().unwrap().clone();
+            let peer = req
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| *addr);
+            let ip = client_ip(req.headers(), peer, &state.trusted_proxies)
+                .map(|ip| ip.to_string())
+                .unwrap_or_default();
+            if let Some(retry_after) = rate_limit::check(&state, group, &ip).await {
+                return Ok(too_many_requests(retry_after));
+            }
+            inner.call(req).await
+        })
+    }
+}
+
+fn too_many_requests(retry_after: u64) -> Response {
+    let mut res =
+        AppError::too_many_requests(&format!("请求过于频繁，请在{}秒后重试", retry_after))
+            .into_response();
+    res.headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+    res
+}
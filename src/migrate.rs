@@ -0,0 +1,200 @@
+//! 数据库迁移
+//!
+//! 迁移脚本位于`migrations`目录，编译时嵌入程序。每个迁移在独立的事务中执行，
+//! 并记录到`schema_migrations`表中。读取迁移状态和执行迁移期间持有 PostgreSQL 的咨询锁，
+//! 避免多个实例同时启动时重复执行同一个迁移。
+
+use tokio_postgres::Client;
+
+use crate::{db::migration, error::AppError, model::SchemaMigration, Result};
+
+/// 一个迁移
+pub struct Migration {
+    /// 版本，从1开始递增
+    pub version: i32,
+    pub name: &'static str,
+    up: &'static str,
+    /// 回滚脚本，为`None`时不可回滚
+    down: Option<&'static str>,
+}
+impl Migration {
+    pub fn is_reversible(&self) -> bool {
+        self.down.is_some()
+    }
+}
+
+macro_rules! migration {
+    ($version:expr, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!("../migrations/", $name, ".up.sql")),
+            down: None,
+        }
+    };
+    ($version:expr, $name:literal, reversible) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!("../migrations/", $name, ".up.sql")),
+            down: Some(include_str!(concat!("../migrations/", $name, ".down.sql"))),
+        }
+    };
+}
+
+/// 所有迁移，按版本升序。只能在末尾追加，已发布的迁移不应修改
+pub static MIGRATIONS: &[Migration] = &[
+    migration!(1, "0001_init"),
+    migration!(2, "0002_subject_topics_summary"),
+    migration!(3, "0003_topic_search", reversible),
+    migration!(4, "0004_subject_topics_author", reversible),
+    migration!(5, "0005_admin_token", reversible),
+    migration!(6, "0006_topic_revision", reversible),
+    migration!(7, "0007_topic_status", reversible),
+    migration!(8, "0008_topic_edit_dateline", reversible),
+    migration!(9, "0009_comment", reversible),
+    migration!(10, "0010_protect_policy", reversible),
+    migration!(11, "0011_topic_toc", reversible),
+    migration!(12, "0012_media", reversible),
+    migration!(13, "0013_admin_role", reversible),
+    migration!(14, "0014_audit_log", reversible),
+    migration!(15, "0015_audit_log_anonymous", reversible),
+    migration!(16, "0016_admin_two_factor", reversible),
+    migration!(17, "0017_topic_search_protected", reversible),
+    migration!(18, "0018_topic_search_trgm", reversible),
+];
+
+/// 迁移状态
+pub struct Status<'a> {
+    pub migration: &'a Migration,
+    /// 已执行时为执行记录
+    pub applied: Option<SchemaMigration>,
+}
+
+/// 所有迁移的状态，以及数据库中存在但程序中没有的迁移（通常是数据库被更新版本的程序迁移过）
+pub async fn status(client: &Client) -> Result<(Vec<Status<'static>>, Vec<SchemaMigration>)> {
+    migration::init(client).await?;
+    let mut applied = migration::applied(client).await?;
+    let list = MIGRATIONS
+        .iter()
+        .map(|m| Status {
+            migration: m,
+            applied: applied
+                .iter()
+                .position(|a| a.version == m.version)
+                .map(|i| applied.remove(i)),
+        })
+        .collect();
+    Ok((list, applied))
+}
+
+/// 尚未执行的迁移
+pub async fn pending(client: &Client) -> Result<Vec<&'static Migration>> {
+    let (list, _) = status(client).await?;
+    Ok(list
+        .into_iter()
+        .filter(|s| s.applied.is_none())
+        .map(|s| s.migration)
+        .collect())
+}
+
+/// 执行所有尚未执行的迁移，返回本次执行的迁移
+pub async fn up(client: &mut Client) -> Result<Vec<&'static Migration>> {
+    migration::lock(client).await?;
+    let result = up_locked(client).await;
+    migration::unlock(client).await?;
+    result
+}
+
+/// 在持有迁移锁时读取并执行尚未执行的迁移
+async fn up_locked(client: &mut Client) -> Result<Vec<&'static Migration>> {
+    let pending = pending(client).await?;
+    for m in pending.iter() {
+        tracing::info!("执行迁移 {}", m.name);
+        migration::apply(client, m.version, m.name, m.up).await?;
+    }
+    Ok(pending)
+}
+
+/// 按版本倒序回滚最近执行的`steps`个迁移，返回本次回滚的迁移
+pub async fn down(client: &mut Client, steps: usize) -> Result<Vec<&'static Migration>> {
+    migration::lock(client).await?;
+    let result = down_locked(client, steps).await;
+    migration::unlock(client).await?;
+    result
+}
+
+async fn down_locked(client: &mut Client, steps: usize) -> Result<Vec<&'static Migration>> {
+    let (list, unknown) = status(client).await?;
+    if !unknown.is_empty() {
+        return Err(AppError::from_str(
+            "数据库中存在本程序未知的迁移，请使用更新版本的程序回滚",
+            crate::error::AppErrorType::Common,
+        ));
+    }
+    let targets: Vec<&'static Migration> = list
+        .into_iter()
+        .rev()
+        .filter(|s| s.applied.is_some())
+        .take(steps)
+        .map(|s| s.migration)
+        .collect();
+    // 先检查，避免回滚到一半才发现不可回滚
+    if let Some(m) = targets.iter().find(|m| !m.is_reversible()) {
+        return Err(AppError::from_str(
+            &format!("迁移 {} 不可回滚", m.name),
+            crate::error::AppErrorType::Common,
+        ));
+    }
+    for m in targets.iter() {
+        tracing::info!("回滚迁移 {}", m.name);
+        migration::revert(client, m.version, m.down.unwrap_or_default()).await?;
+    }
+    Ok(targets)
+}
+
+/// 将`version`及之前的迁移标记为已执行而不实际执行。
+/// 用于此前手动执行`scripts/sql`下脚本创建的数据库
+pub async fn baseline(client: &Client, version: i32) -> Result<Vec<&'static Migration>> {
+    if !MIGRATIONS.iter().any(|m| m.version == version) {
+        return Err(AppError::not_found(&format!(
+            "没有版本为 {} 的迁移",
+            version
+        )));
+    }
+    migration::lock(client).await?;
+    let result = baseline_locked(client, version).await;
+    migration::unlock(client).await?;
+    result
+}
+
+async fn baseline_locked(client: &Client, version: i32) -> Result<Vec<&'static Migration>> {
+    migration::init(client).await?;
+    let mut marked = Vec::new();
+    for m in MIGRATIONS.iter().filter(|m| m.version <= version) {
+        if migration::mark(client, m.version, m.name).await? > 0 {
+            marked.push(m);
+        }
+    }
+    Ok(marked)
+}
+
+/// 启动时检查数据库结构是否为最新。`auto`为`true`时自动执行迁移，否则返回错误
+pub async fn check(client: &mut Client, auto: bool) -> Result<()> {
+    if auto {
+        up(client).await?;
+        return Ok(());
+    }
+    let pending = pending(client).await?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<&str> = pending.iter().map(|m| m.name).collect();
+    Err(AppError::from_str(
+        &format!(
+            "数据库结构不是最新的，尚未执行的迁移：{}。请运行`axum-rs migrate up`，或设置`MIGRATE.AUTO=true`自动迁移。手动执行SQL脚本创建的数据库，请先运行`axum-rs migrate baseline <版本>`",
+            names.join(", ")
+        ),
+        crate::error::AppErrorType::Config,
+    ))
+}
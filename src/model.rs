@@ -1,19 +1,196 @@
+use std::sync::Arc;
+
 use chrono::{Local, TimeZone};
 use redis::Client;
 use serde::{Deserialize, Serialize};
 use tokio_pg_mapper_derive::PostgresMapper;
 
-use crate::config::{HCaptchaConfig, ReCaptchaConfig, SessionConfig};
+use crate::{
+    audit,
+    cache::Cache,
+    captcha::CaptchaProvider,
+    config::{
+        LoginGuardConfig, RateLimitConfig, RobotsConfig, SessionConfig, UploadConfig, WebConfig,
+    },
+    md::Sanitizer,
+    protect, rate_limit,
+    role::Role,
+    search,
+    storage::Storage,
+    time,
+};
+
+/// 文章状态
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TopicStatus {
+    /// 草稿
+    Draft,
+    /// 定时发布，到达`publish_at`后对前台可见
+    Scheduled,
+    /// 已发布
+    #[default]
+    Published,
+}
+impl TopicStatus {
+    /// 数据库中保存的值
+    pub fn code(&self) -> i16 {
+        match self {
+            TopicStatus::Draft => 0,
+            TopicStatus::Scheduled => 1,
+            TopicStatus::Published => 2,
+        }
+    }
+    pub fn from_code(code: i16) -> Self {
+        match code {
+            0 => TopicStatus::Draft,
+            1 => TopicStatus::Scheduled,
+            _ => TopicStatus::Published,
+        }
+    }
+    /// 用于表单的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TopicStatus::Draft => "draft",
+            TopicStatus::Scheduled => "scheduled",
+            TopicStatus::Published => "published",
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            TopicStatus::Draft => "草稿",
+            TopicStatus::Scheduled => "定时发布",
+            TopicStatus::Published => "已发布",
+        }
+    }
+}
+
+/// 隐藏内容的策略
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtectMode {
+    /// 使用专题的策略，仅用于文章
+    #[default]
+    Inherit,
+    /// 不隐藏任何内容
+    None,
+    /// 随机隐藏若干段落，以及`:::protected`标记的内容
+    Random,
+    /// 仅隐藏`:::protected`标记的内容
+    Explicit,
+}
+impl ProtectMode {
+    /// 数据库中保存的值
+    pub fn code(&self) -> i16 {
+        match self {
+            ProtectMode::Inherit => -1,
+            ProtectMode::None => 0,
+            ProtectMode::Random => 1,
+            ProtectMode::Explicit => 2,
+        }
+    }
+    pub fn from_code(code: i16) -> Self {
+        match code {
+            0 => ProtectMode::None,
+            1 => ProtectMode::Random,
+            2 => ProtectMode::Explicit,
+            _ => ProtectMode::Inherit,
+        }
+    }
+    /// 用于表单的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtectMode::Inherit => "inherit",
+            ProtectMode::None => "none",
+            ProtectMode::Random => "random",
+            ProtectMode::Explicit => "explicit",
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProtectMode::Inherit => "使用专题的设置",
+            ProtectMode::None => "不隐藏",
+            ProtectMode::Random => "随机隐藏段落",
+            ProtectMode::Explicit => "仅隐藏标记的内容",
+        }
+    }
+    /// 专题不能继承，缺省为随机隐藏
+    pub fn for_subject(self) -> Self {
+        match self {
+            ProtectMode::Inherit => ProtectMode::Random,
+            mode => mode,
+        }
+    }
+}
+
+/// 评论状态
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentStatus {
+    /// 待审核
+    #[default]
+    Pending,
+    /// 已通过
+    Approved,
+    /// 垃圾评论
+    Spam,
+}
+impl CommentStatus {
+    /// 数据库中保存的值
+    pub fn code(&self) -> i16 {
+        match self {
+            CommentStatus::Pending => 0,
+            CommentStatus::Approved => 1,
+            CommentStatus::Spam => 2,
+        }
+    }
+    pub fn from_code(code: i16) -> Self {
+        match code {
+            1 => CommentStatus::Approved,
+            2 => CommentStatus::Spam,
+            _ => CommentStatus::Pending,
+        }
+    }
+    /// 用于查询参数的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommentStatus::Pending => "pending",
+            CommentStatus::Approved => "approved",
+            CommentStatus::Spam => "spam",
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommentStatus::Pending => "待审核",
+            CommentStatus::Approved => "已通过",
+            CommentStatus::Spam => "垃圾评论",
+        }
+    }
+}
 
 pub struct AppState {
+    pub web_cfg: WebConfig,
     pub pool: deadpool_postgres::Pool,
     pub rdc: Client,
     pub sess_cfg: SessionConfig,
-    pub hcap_cfg: HCaptchaConfig,
-    pub recap_cfg: ReCaptchaConfig,
+    pub login_guard_cfg: LoginGuardConfig,
+    /// 受信任的反向代理，用于取得客户端的 IP
+    pub trusted_proxies: audit::TrustedProxies,
+    pub rate_limit_cfg: RateLimitConfig,
+    /// redis 不可用时使用的内存令牌桶
+    pub rate_limiter: rate_limit::MemoryLimiter,
+    /// 前台数据的缓存
+    pub cache: Cache,
+    pub captcha: Arc<dyn CaptchaProvider>,
+    pub robots_cfg: RobotsConfig,
+    /// 渲染 Markdown 后的 HTML 过滤
+    pub sanitizer: Sanitizer,
+    /// 上传文件的存储
+    pub storage: Arc<dyn Storage>,
+    pub upload_cfg: UploadConfig,
 }
 
-#[derive(PostgresMapper, Deserialize, Serialize)]
+#[derive(PostgresMapper, Deserialize, Serialize, Clone)]
 #[pg_mapper(table = "subject")]
 pub struct Subject {
     pub id: i32,
@@ -21,6 +198,13 @@ pub struct Subject {
     pub slug: String,
     pub summary: String,
     pub is_del: bool,
+    pub protect_mode: i16,
+    pub protect_count: i16,
+}
+impl Subject {
+    pub fn protect_mode(&self) -> ProtectMode {
+        ProtectMode::from_code(self.protect_mode)
+    }
 }
 #[derive(PostgresMapper)]
 #[pg_mapper(table = "subject")]
@@ -31,7 +215,7 @@ pub struct SubjectList {
     pub is_del: bool,
 }
 
-#[derive(PostgresMapper)]
+#[derive(PostgresMapper, Serialize)]
 #[pg_mapper(table = "subject")]
 pub struct SubjectID {
     pub id: i32,
@@ -51,11 +235,75 @@ pub struct Topic {
     pub dateline: i32,
     pub is_del: bool,
 }
-#[derive(PostgresMapper)]
+#[derive(PostgresMapper, Serialize)]
 #[pg_mapper(table = "topic")]
 pub struct TopicID {
     pub id: i64,
 }
+/// 文章的标题
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "topic")]
+pub struct TopicTitle {
+    pub id: i64,
+    pub title: String,
+}
+/// 文章的创建者
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "topic")]
+pub struct TopicOwner {
+    pub id: i64,
+    pub admin_id: Option<i32>,
+}
+/// 文章的固定链接、所属的专题和标签，修改文章后据此清除缓存
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "topic")]
+pub struct TopicPlacement {
+    pub id: i64,
+    pub slug: String,
+    pub subject_slug: String,
+    pub tag_names: Vec<String>,
+}
+/// 文章的浏览次数
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "topic")]
+pub struct TopicHit {
+    pub hit: i32,
+}
+/// 文章的访问路径
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "v_subject_topics")]
+pub struct TopicPath {
+    pub subject_slug: String,
+    pub slug: String,
+}
+impl TopicPath {
+    pub fn url(&self) -> String {
+        format!("/topic/{}/{}", self.subject_slug, self.slug)
+    }
+}
+#[derive(PostgresMapper, Serialize)]
+#[pg_mapper(table = "comment")]
+pub struct CommentID {
+    pub id: i64,
+}
+
+/// 生成全文搜索纯文本所需的内容及实际生效的隐藏策略
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "v_topic_search_source")]
+pub struct TopicSearchSource {
+    pub topic_id: i64,
+    pub html: String,
+    pub protect_mode: i16,
+    pub protect_count: i16,
+}
+impl TopicSearchSource {
+    pub fn protect_policy(&self) -> protect::Policy {
+        protect::Policy {
+            mode: ProtectMode::from_code(self.protect_mode),
+            count: self.protect_count,
+        }
+    }
+}
 
 #[derive(PostgresMapper)]
 #[pg_mapper(table = "topic_content")]
@@ -63,6 +311,7 @@ pub struct TopicContent {
     pub topic_id: i64,
     pub md: String,
     pub html: String,
+    pub toc: String,
 }
 
 #[derive(PostgresMapper, Deserialize, Serialize)]
@@ -72,7 +321,7 @@ pub struct Tag {
     pub name: String,
     pub is_del: bool,
 }
-#[derive(PostgresMapper)]
+#[derive(PostgresMapper, Serialize)]
 #[pg_mapper(table = "tag")]
 pub struct TagID {
     pub id: i32,
@@ -97,6 +346,25 @@ pub struct TopicSubjectListView {
     pub subject_id: i32,
     pub is_del: bool,
     pub subject_is_del: bool,
+    pub status: i16,
+    pub publish_at: i32,
+}
+impl TopicSubjectListView {
+    pub fn status(&self) -> TopicStatus {
+        TopicStatus::from_code(self.status)
+    }
+    /// 前台是否可见（不考虑删除状态）
+    pub fn is_visible(&self) -> bool {
+        match self.status() {
+            TopicStatus::Draft => false,
+            TopicStatus::Scheduled => self.publish_at <= time::now(),
+            TopicStatus::Published => true,
+        }
+    }
+    pub fn publish_at(&self) -> String {
+        let dt = Local.timestamp(self.publish_at as i64, 0);
+        dt.format("%Y/%m/%d %H:%M").to_string()
+    }
 }
 #[derive(PostgresMapper)]
 #[pg_mapper(table = "v_topic_with_md_and_tags_for_edit")]
@@ -110,22 +378,52 @@ pub struct TopicWithMdAndTagsForEdit {
     pub author: String,
     pub md: String,
     pub tag_names: Vec<String>,
+    pub status: i16,
+    pub publish_at: i32,
+    pub dateline: i32,
+    pub protect_mode: i16,
+    pub protect_count: i16,
 }
 impl TopicWithMdAndTagsForEdit {
+    pub fn protect_mode(&self) -> ProtectMode {
+        ProtectMode::from_code(self.protect_mode)
+    }
     pub fn tags(&self) -> String {
         self.tag_names.join(",").to_string()
     }
+    pub fn status(&self) -> TopicStatus {
+        TopicStatus::from_code(self.status)
+    }
+    pub fn publish_at(&self) -> String {
+        time::format_local(self.publish_at)
+    }
 }
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct AdminSession {
     pub id: i32,
     pub username: String,
-    pub password: String,
     pub is_sys: bool,
+    pub role: Role,
+    /// 过期时间
     pub dateline: i32,
+    /// 登录时间
+    pub login_at: i32,
+    /// 最后活跃的时间
+    pub active_at: i32,
+    /// 登录时的 IP 和 User-Agent
+    pub ip: String,
+    pub user_agent: String,
+}
+impl AdminSession {
+    pub fn login_time(&self) -> String {
+        time::format_local(self.login_at)
+    }
+    pub fn active_time(&self) -> String {
+        time::format_local(self.active_at)
+    }
 }
 
-#[derive(PostgresMapper)]
+#[derive(PostgresMapper, Serialize, Deserialize)]
 #[pg_mapper(table = "v_subject_topic")]
 pub struct SubjectTopicWithTagsAndTopicSummary {
     pub id: i64,
@@ -135,9 +433,13 @@ pub struct SubjectTopicWithTagsAndTopicSummary {
     pub tag_names: Vec<String>,
     pub summary: String,
     pub subject_name: String,
+    pub author: String,
+    pub dateline: i32,
+    /// 已通过审核的评论数
+    pub comment_count: i64,
 }
 
-#[derive(PostgresMapper)]
+#[derive(PostgresMapper, Serialize, Deserialize)]
 #[pg_mapper(table = "v_topic_detail")]
 pub struct TopicDetail {
     pub id: i64,
@@ -152,14 +454,34 @@ pub struct TopicDetail {
     pub dateline: i32,
     pub hit: i32,
     pub subject_name: String,
+    /// 实际生效的隐藏内容策略
+    pub protect_mode: i16,
+    pub protect_count: i16,
+    /// 文章目录
+    pub toc: String,
 }
 impl TopicDetail {
+    pub fn protect_policy(&self) -> protect::Policy {
+        protect::Policy {
+            mode: ProtectMode::from_code(self.protect_mode),
+            count: self.protect_count,
+        }
+    }
     pub fn dateline(&self) -> String {
         let dt = Local.timestamp(self.dateline as i64, 0);
         dt.format("%Y/%m/%d %H:%M:%S").to_string()
     }
 }
 
+/// API 返回的文章详情，需要隐藏的内容已替换为占位符
+#[derive(Serialize)]
+pub struct ProtectedTopicDetail {
+    #[serde(flatten)]
+    pub topic: TopicDetail,
+    /// 查看隐藏内容的令牌，连同人机验证的结果提交到`/topic/get_procted_content`
+    pub protected: Option<protect::Token>,
+}
+
 #[derive(PostgresMapper)]
 #[pg_mapper(table = "admin")]
 pub struct Admin {
@@ -168,9 +490,267 @@ pub struct Admin {
     pub password: String,
     pub is_sys: bool,
     pub is_del: bool,
+    pub role: i16,
+    /// 两步验证的密钥，未启用时为空
+    pub totp_secret: String,
+    pub totp_enabled: bool,
+}
+impl Admin {
+    pub fn role(&self) -> Role {
+        Role::from_code(self.role)
+    }
 }
 #[derive(PostgresMapper)]
 #[pg_mapper(table = "admin")]
 pub struct AdminID {
     pub id: i32,
 }
+
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "v_topic_search")]
+pub struct TopicSearchResult {
+    pub id: i64,
+    pub title: String,
+    pub slug: String,
+    pub subject_slug: String,
+    pub subject_name: String,
+    pub tag_names: Vec<String>,
+    pub headline: String,
+    pub rank: f32,
+}
+impl TopicSearchResult {
+    /// 高亮关键字之后的摘要
+    pub fn headline_html(&self) -> String {
+        search::headline_to_html(&self.headline)
+    }
+}
+
+/// 站点地图中的URL
+#[derive(PostgresMapper, Deserialize, Serialize)]
+#[pg_mapper(table = "v_subject_topics")]
+pub struct SitemapUrl {
+    /// 路径
+    pub loc: String,
+    /// 最后修改时间
+    pub lastmod: i32,
+}
+
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "admin_token")]
+pub struct AdminToken {
+    pub id: i32,
+    pub admin_id: i32,
+    pub name: String,
+    pub dateline: i32,
+    pub last_used: i32,
+    pub is_del: bool,
+}
+impl AdminToken {
+    pub fn dateline(&self) -> String {
+        let dt = Local.timestamp(self.dateline as i64, 0);
+        dt.format("%Y/%m/%d %H:%M:%S").to_string()
+    }
+    pub fn last_used(&self) -> String {
+        if self.last_used == 0 {
+            return "从未使用".to_string();
+        }
+        let dt = Local.timestamp(self.last_used as i64, 0);
+        dt.format("%Y/%m/%d %H:%M:%S").to_string()
+    }
+}
+#[derive(PostgresMapper, Serialize)]
+#[pg_mapper(table = "admin_token")]
+pub struct AdminTokenID {
+    pub id: i32,
+}
+
+/// 文章修订版本列表
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "topic_revision")]
+pub struct TopicRevisionList {
+    pub id: i64,
+    pub topic_id: i64,
+    pub title: String,
+    pub admin_name: Option<String>,
+    pub dateline: i32,
+}
+impl TopicRevisionList {
+    pub fn dateline(&self) -> String {
+        let dt = Local.timestamp(self.dateline as i64, 0);
+        dt.format("%Y/%m/%d %H:%M:%S").to_string()
+    }
+    pub fn admin_name(&self) -> String {
+        match &self.admin_name {
+            Some(name) => name.clone(),
+            None => "-".to_string(),
+        }
+    }
+}
+
+/// 文章修订版本
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "topic_revision")]
+pub struct TopicRevision {
+    pub id: i64,
+    pub topic_id: i64,
+    pub title: String,
+    pub summary: String,
+    pub md: String,
+    pub tags: String,
+    pub admin_id: Option<i32>,
+    pub dateline: i32,
+}
+impl TopicRevision {
+    pub fn dateline(&self) -> String {
+        let dt = Local.timestamp(self.dateline as i64, 0);
+        dt.format("%Y/%m/%d %H:%M:%S").to_string()
+    }
+}
+
+/// 已执行的数据库迁移
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "schema_migrations")]
+pub struct SchemaMigration {
+    pub version: i32,
+    pub name: String,
+    pub applied_at: i32,
+}
+impl SchemaMigration {
+    pub fn applied_at(&self) -> String {
+        let dt = Local.timestamp(self.applied_at as i64, 0);
+        dt.format("%Y/%m/%d %H:%M:%S").to_string()
+    }
+}
+
+/// 前台显示的评论
+#[derive(PostgresMapper, Serialize)]
+#[pg_mapper(table = "comment")]
+pub struct Comment {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+    pub nickname: String,
+    pub content: String,
+    pub dateline: i32,
+}
+impl Comment {
+    pub fn dateline(&self) -> String {
+        let dt = Local.timestamp(self.dateline as i64, 0);
+        dt.format("%Y/%m/%d %H:%M").to_string()
+    }
+}
+
+/// 按回复关系排好序的评论
+pub struct CommentNode {
+    pub comment: Comment,
+    /// 回复的层级，顶层评论为0
+    pub depth: usize,
+}
+
+/// 后台审核的评论
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "v_comment_list")]
+pub struct CommentList {
+    pub id: i64,
+    pub topic_id: i64,
+    pub parent_id: Option<i64>,
+    pub nickname: String,
+    pub email: String,
+    pub content: String,
+    pub status: i16,
+    pub dateline: i32,
+    pub is_del: bool,
+    pub topic_title: String,
+    pub topic_slug: String,
+    pub subject_slug: String,
+    pub parent_nickname: String,
+}
+impl CommentList {
+    pub fn status(&self) -> CommentStatus {
+        CommentStatus::from_code(self.status)
+    }
+    pub fn dateline(&self) -> String {
+        let dt = Local.timestamp(self.dateline as i64, 0);
+        dt.format("%Y/%m/%d %H:%M:%S").to_string()
+    }
+}
+
+/// 媒体库中的文件
+#[derive(PostgresMapper, Serialize)]
+#[pg_mapper(table = "media")]
+pub struct Media {
+    pub id: i64,
+    pub url: String,
+    pub thumb_url: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub width: i32,
+    pub height: i32,
+    pub is_image: bool,
+    pub dateline: i32,
+    pub is_del: bool,
+}
+impl Media {
+    pub fn dateline(&self) -> String {
+        let dt = Local.timestamp(self.dateline as i64, 0);
+        dt.format("%Y/%m/%d %H:%M:%S").to_string()
+    }
+    /// 插入文章的 Markdown，图片使用图片语法，其它文件使用链接
+    pub fn markdown(&self) -> String {
+        let name = self.file_name.replace(['[', ']'], "");
+        if self.is_image {
+            format!("![{}]({})", name, self.url)
+        } else {
+            format!("[{}]({})", name, self.url)
+        }
+    }
+    pub fn size_text(&self) -> String {
+        crate::media::size_text(self.size as u64)
+    }
+}
+#[derive(PostgresMapper, Serialize)]
+#[pg_mapper(table = "media")]
+pub struct MediaID {
+    pub id: i64,
+}
+
+/// 审计日志
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "v_audit_log")]
+pub struct AuditLog {
+    pub id: i64,
+    pub admin_id: i32,
+    pub admin_name: String,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: i64,
+    pub target_name: String,
+    pub before_summary: String,
+    pub after_summary: String,
+    pub ip: String,
+    pub user_agent: String,
+    pub dateline: i32,
+}
+impl AuditLog {
+    pub fn dateline(&self) -> String {
+        time::format_local(self.dateline)
+    }
+    pub fn action_name(&self) -> &str {
+        audit::Action::parse(&self.action)
+            .map(|action| action.name())
+            .unwrap_or(&self.action)
+    }
+    pub fn target_type_name(&self) -> &str {
+        audit::Target::parse(&self.target_type)
+            .map(|target| target.name())
+            .unwrap_or(&self.target_type)
+    }
+}
+
+/// 站点设置
+#[derive(PostgresMapper)]
+#[pg_mapper(table = "setting")]
+pub struct Setting {
+    pub name: String,
+    pub value: String,
+}
@@ -1,3 +1,5 @@
+use std::sync::OnceLock;
+
 use bcrypt::DEFAULT_COST;
 
 use crate::{error::AppError, Result};
@@ -8,3 +10,21 @@ pub fn hash(pwd: &str) -> Result<String> {
 pub fn verify(pwd: &str, hashed_pwd: &str) -> Result<bool> {
     bcrypt::verify(pwd, hashed_pwd).map_err(AppError::from)
 }
+/// 与一个固定的哈希进行校验，总是返回`false`。
+/// 用户名不存在时调用，使响应时间与密码错误时相同，无法据此判断用户名是否存在
+pub fn verify_dummy(pwd: &str) -> Result<bool> {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let hashed = DUMMY_HASH.get_or_init(|| hash("axum.rs dummy password").unwrap_or_default());
+    verify(pwd, hashed).map(|_| false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dummy_never_matches() {
+        assert!(!verify_dummy("axum.rs dummy password").unwrap());
+        assert!(!verify_dummy("").unwrap());
+    }
+}
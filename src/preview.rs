@@ -0,0 +1,40 @@
+//! 未发布文章的预览链接
+//!
+//! 链接使用`web.secret_key`进行 HMAC-SHA256 签名，并带有过期时间，
+//! 可以分享给未登录的人查看草稿或尚未到发布时间的文章。
+
+use serde::Deserialize;
+
+use crate::{sign, time::now};
+
+/// 预览链接的有效期（秒）
+const EXPIRES_IN: i32 = 7 * 24 * 3600;
+
+/// 预览链接中的签名
+#[derive(Deserialize)]
+pub struct Signature {
+    pub expires: i32,
+    pub sig: String,
+}
+
+/// 生成文章的预览链接
+pub fn url(secret_key: &str, topic_id: i64) -> String {
+    let expires = now() + EXPIRES_IN;
+    let sig = sign::sign(secret_key, &message(topic_id, expires));
+    format!(
+        "/preview/topic/{}?expires={}&sig={}",
+        topic_id, expires, sig
+    )
+}
+
+/// 验证预览链接的签名及有效期
+pub fn verify(secret_key: &str, topic_id: i64, expires: i32, sig: &str) -> bool {
+    if expires < now() {
+        return false;
+    }
+    sign::verify(secret_key, &message(topic_id, expires), sig)
+}
+
+fn message(topic_id: i64, expires: i32) -> String {
+    format!("topic_preview:{}:{}", topic_id, expires)
+}
@@ -0,0 +1,212 @@
+//! 文章的隐藏内容
+//!
+//! 隐藏的内容需要通过人机验证才能查看。页面中只输出占位符和一个签名令牌，
+//! 令牌中包含文章ID、被隐藏内容的序号及过期时间；查看时根据令牌从数据库中的文章重新提取，
+//! 因此不依赖 Redis，同一页面也可以多次获取。
+
+use regex::{Captures, Regex};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{model::ProtectMode, sign, time::now};
+
+/// `:::protected`块渲染成 HTML 后的开始标记
+pub const BEGIN_MARKER: &str = "<!--protected-->\n";
+/// `:::protected`块渲染成 HTML 后的结束标记
+pub const END_MARKER: &str = "<!--/protected-->\n";
+
+/// 令牌的有效期（秒）
+const EXPIRES_IN: i32 = 24 * 3600;
+
+/// 隐藏内容的策略
+#[derive(Clone, Copy)]
+pub struct Policy {
+    pub mode: ProtectMode,
+    /// 随机隐藏的段落数，0 表示根据段落数自动决定
+    pub count: i16,
+}
+
+/// 隐藏的内容
+#[derive(Serialize)]
+pub struct ProtectedContent {
+    /// 在文章中的序号
+    pub id: usize,
+    pub tag: String,
+    pub content: String,
+}
+
+/// 查看隐藏内容的令牌
+#[derive(Serialize)]
+pub struct Token {
+    pub topic_id: i64,
+    /// 以逗号分隔的序号
+    pub ids: String,
+    pub expires: i32,
+    pub sig: String,
+}
+impl Token {
+    fn new(secret_key: &str, topic_id: i64, ids: &[usize]) -> Self {
+        let ids = join_ids(ids);
+        let expires = now() + EXPIRES_IN;
+        let sig = sign::sign(secret_key, &message(topic_id, &ids, expires));
+        Self {
+            topic_id,
+            ids,
+            expires,
+            sig,
+        }
+    }
+}
+
+/// 验证令牌，返回其中的序号
+pub fn verify(
+    secret_key: &str,
+    topic_id: i64,
+    ids: &str,
+    expires: i32,
+    sig: &str,
+) -> Option<Vec<usize>> {
+    if expires < now() || !sign::verify(secret_key, &message(topic_id, ids, expires), sig) {
+        return None;
+    }
+    ids.split(',').map(|id| id.parse().ok()).collect()
+}
+
+fn message(topic_id: i64, ids: &str, expires: i32) -> String {
+    format!("protected_content:{}:{}:{}", topic_id, ids, expires)
+}
+
+fn join_ids(ids: &[usize]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// 可隐藏的内容：`:::protected`块，以及独占一行的段落和代码块
+fn block_regex() -> Regex {
+    Regex::new(r"(?sm)^<!--protected-->\n(.*?)<!--/protected-->\n|^<(p|pre)>(.+?)</(?:p|pre)>$")
+        .unwrap()
+}
+
+struct Block {
+    tag: String,
+    content: String,
+    explicit: bool,
+}
+impl Block {
+    fn from_captures(cap: &Captures) -> Self {
+        match cap.get(1) {
+            Some(content) => Self {
+                tag: "div".to_string(),
+                content: content.as_str().to_string(),
+                explicit: true,
+            },
+            None => Self {
+                tag: cap[2].to_string(),
+                content: cap[3].to_string(),
+                explicit: false,
+            },
+        }
+    }
+}
+
+/// 按文中的顺序列出所有可隐藏的内容
+fn blocks(html: &str) -> Vec<Block> {
+    block_regex()
+        .captures_iter(html)
+        .map(|cap| Block::from_captures(&cap))
+        .collect()
+}
+
+/// 根据策略选出需要隐藏的内容。同一篇文章每次选出的结果相同
+fn select(topic_id: i64, blocks: &[Block], policy: Policy) -> Vec<usize> {
+    if policy.mode == ProtectMode::None {
+        return vec![];
+    }
+    let mut ids: Vec<usize> = (0..blocks.len()).filter(|&i| blocks[i].explicit).collect();
+    if policy.mode == ProtectMode::Explicit {
+        return ids;
+    }
+    let mut candidates: Vec<usize> = (0..blocks.len()).filter(|&i| !blocks[i].explicit).collect();
+    let count = match policy.count {
+        c if c > 0 => c as usize,
+        _ => match candidates.len() {
+            0..=1 => 0,
+            2..=4 => 1,
+            5..=8 => 2,
+            _ => 3,
+        },
+    };
+    candidates.sort_by_key(|i| Sha256::digest(format!("{}:{}", topic_id, i).as_bytes()));
+    ids.extend(candidates.into_iter().take(count));
+    ids.sort_unstable();
+    ids
+}
+
+/// 隐藏文章的部分内容，返回替换成占位符后的 HTML 以及查看隐藏内容的令牌
+pub fn hide(
+    secret_key: &str,
+    topic_id: i64,
+    html: &str,
+    policy: Policy,
+) -> (String, Option<Token>) {
+    let ids = select(topic_id, &blocks(html), policy);
+    let mut idx = 0usize;
+    let out = block_regex().replace_all(html, |cap: &Captures| {
+        let id = idx;
+        idx += 1;
+        let block = Block::from_captures(cap);
+        if ids.contains(&id) {
+            format!(
+                "<div id=\"protected-{id}\" class=\"callout callout-info\">正在进行人机验证，通过后将显示隐藏的内容(大约{count}字节)</div>",
+                id = id,
+                count = block.content.len()
+            )
+        } else if block.explicit {
+            block.content
+        } else {
+            cap[0].to_string()
+        }
+    });
+    let token = if ids.is_empty() {
+        None
+    } else {
+        Some(Token::new(secret_key, topic_id, &ids))
+    };
+    (out.to_string(), token)
+}
+
+/// 去掉需要隐藏的内容后的 HTML，用于全文搜索。`:::protected`块未被隐藏时保留其内容
+pub fn public_html(topic_id: i64, html: &str, policy: Policy) -> String {
+    let ids = select(topic_id, &blocks(html), policy);
+    let mut idx = 0usize;
+    block_regex()
+        .replace_all(html, |cap: &Captures| {
+            let id = idx;
+            idx += 1;
+            let block = Block::from_captures(cap);
+            if ids.contains(&id) {
+                String::new()
+            } else if block.explicit {
+                block.content
+            } else {
+                cap[0].to_string()
+            }
+        })
+        .to_string()
+}
+
+/// 从文章中提取指定序号的隐藏内容
+pub fn reveal(html: &str, ids: &[usize]) -> Vec<ProtectedContent> {
+    blocks(html)
+        .into_iter()
+        .enumerate()
+        .filter(|(id, _)| ids.contains(id))
+        .map(|(id, block)| ProtectedContent {
+            id,
+            tag: block.tag,
+            content: block.content,
+        })
+        .collect()
+}
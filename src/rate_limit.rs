@@ -0,0 +1,119 @@
+//! 按 IP 限制请求频率
+//!
+//! 登录、获取受保护内容和发表评论每次都会调用第三方的人机验证，需要限制同一 IP 的请求频率。
+//! 每个路由组和 IP 对应一个令牌桶，保存在 redis 中以便多个实例共享；redis 不可用时改用进程内的令牌桶。
+
+use std::{collections::HashMap, sync::Mutex, time::Instant};
+
+use crate::{config::RateLimitConfig, model::AppState, rdb};
+
+const PREFIX: &str = "axum_rs:rate_limit:";
+/// 内存中最多保存的桶数，超过后清理已经补满的桶
+const MAX_MEMORY_BUCKETS: usize = 10000;
+
+/// 路由组，同一组的路由共用一个令牌桶
+#[derive(Debug, Clone, Copy)]
+pub enum Group {
+    /// 登录及输入两步验证的验证码
+    Login,
+    /// 获取文章中受保护的内容
+    ProtectedContent,
+    /// 发表评论
+    Comment,
+}
+impl Group {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Group::Login => "login",
+            Group::ProtectedContent => "protected_content",
+            Group::Comment => "comment",
+        }
+    }
+    fn rule(&self, cfg: &RateLimitConfig) -> Rule {
+        let (capacity, per_minute) = match self {
+            Group::Login => (cfg.login_capacity, cfg.login_per_minute),
+            Group::ProtectedContent => (
+                cfg.protected_content_capacity,
+                cfg.protected_content_per_minute,
+            ),
+            Group::Comment => (cfg.comment_capacity, cfg.comment_per_minute),
+        };
+        Rule {
+            capacity,
+            // 不补充时桶空后永远无法请求
+            per_minute: per_minute.max(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rule {
+    capacity: u32,
+    per_minute: u32,
+}
+impl Rule {
+    /// 每毫秒补充的令牌数
+    fn rate(&self) -> f64 {
+        self.per_minute as f64 / 60000.0
+    }
+}
+
+/// 检查并消耗一个令牌。超出限制时返回需要等待的秒数
+pub async fn check(state: &AppState, group: Group, ip: &str) -> Option<u64> {
+    let rule = group.rule(&state.rate_limit_cfg);
+    if rule.capacity == 0 {
+        return None;
+    }
+    let key = format!("{}{}:{}", PREFIX, group.as_str(), ip);
+    let wait_ms = match rdb::token_bucket(&state.rdc, &key, rule.capacity, rule.per_minute).await {
+        Ok(wait_ms) => wait_ms,
+        Err(err) => {
+            tracing::warn!("限流改用内存中的令牌桶：{:?}", err);
+            state.rate_limiter.take(&key, rule)
+        }
+    };
+    // 向上取整到秒
+    (wait_ms > 0).then(|| wait_ms.div_ceil(1000))
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// 补满的时间，之后可以从内存中清除
+    full_at: Instant,
+}
+
+/// 进程内的令牌桶，只在 redis 不可用时使用
+#[derive(Default)]
+pub struct MemoryLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+impl MemoryLimiter {
+    /// 补充令牌后取出一个，返回还需等待的毫秒数，与 redis 中的令牌桶一致
+    fn take(&self, key: &str, rule: Rule) -> u64 {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_MEMORY_BUCKETS {
+            buckets.retain(|_, bucket| bucket.full_at > now);
+        }
+        let capacity = rule.capacity as f64;
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+            full_at: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_millis() as f64;
+        let mut tokens = capacity.min(bucket.tokens + elapsed * rule.rate());
+        let mut wait = 0;
+        if tokens >= 1.0 {
+            tokens -= 1.0;
+        } else {
+            wait = ((1.0 - tokens) / rule.rate()).ceil() as u64;
+        }
+        bucket.tokens = tokens;
+        bucket.updated = now;
+        bucket.full_at = now
+            + std::time::Duration::from_millis(((capacity - tokens) / rule.rate()).ceil() as u64);
+        wait
+    }
+}
@@ -29,8 +29,174 @@ pub async fn is_exists(client: &Client, key: &str) -> Result<bool> {
     Ok(r)
 }
 
+/// 键的剩余秒数，键不存在时为 -2，没有过期时间时为 -1
+pub async fn ttl(client: &Client, key: &str) -> Result<i64> {
+    let mut conn = get_conn(client).await?;
+    conn.ttl(key).await.map_err(AppError::from)
+}
+
 /// 删除指定的键
 pub async fn del(client: &Client, key: &str) -> Result<()> {
     let mut conn = get_conn(client).await?;
     conn.del(key).await.map_err(AppError::from)
 }
+
+/// 获取匹配指定模式的所有键。使用`SCAN`遍历，不会阻塞 redis
+pub async fn keys(client: &Client, pattern: &str) -> Result<Vec<String>> {
+    let mut conn = get_conn(client).await?;
+    let mut iter = conn
+        .scan_match::<_, String>(pattern)
+        .await
+        .map_err(AppError::from)?;
+    let mut keys = Vec::new();
+    while let Some(key) = iter.next_item().await {
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+/// 设置键的过期时间
+pub async fn expire(client: &Client, key: &str, sec: usize) -> Result<()> {
+    let mut conn = get_conn(client).await?;
+    conn.expire(key, sec).await.map_err(AppError::from)
+}
+
+/// 将计数加一并设置过期时间，返回加一后的值。`INCR`是原子操作，并发时每次调用得到的值都不相同
+pub async fn incr(client: &Client, key: &str, sec: usize) -> Result<i64> {
+    let mut conn = get_conn(client).await?;
+    let (count,): (i64,) = redis::pipe()
+        .atomic()
+        .cmd("INCR")
+        .arg(key)
+        .cmd("EXPIRE")
+        .arg(key)
+        .arg(sec)
+        .ignore()
+        .query_async(&mut conn)
+        .await
+        .map_err(AppError::from)?;
+    Ok(count)
+}
+
+/// 向集合中添加成员
+pub async fn sadd(client: &Client, key: &str, member: &str) -> Result<()> {
+    let mut conn = get_conn(client).await?;
+    conn.sadd(key, member).await.map_err(AppError::from)
+}
+
+/// 从集合中移除成员
+pub async fn srem(client: &Client, key: &str, member: &str) -> Result<()> {
+    let mut conn = get_conn(client).await?;
+    conn.srem(key, member).await.map_err(AppError::from)
+}
+
+/// 集合的所有成员
+pub async fn smembers(client: &Client, key: &str) -> Result<Vec<String>> {
+    let mut conn = get_conn(client).await?;
+    conn.smembers(key).await.map_err(AppError::from)
+}
+
+/// 滑动窗口计数：清除窗口外的记录并加入一条新记录，返回窗口内的记录数。
+/// 记录保存在有序集合中，分数为毫秒时间戳
+pub async fn window_add(client: &Client, key: &str, window_sec: usize) -> Result<usize> {
+    let mut conn = get_conn(client).await?;
+    let now = chrono::Local::now().timestamp_millis();
+    let member = format!("{}-{}", now, uuid::Uuid::new_v4().to_simple());
+    let (count,): (usize,) = redis::pipe()
+        .cmd("ZREMRANGEBYSCORE")
+        .arg(key)
+        .arg("-inf")
+        .arg(now - window_sec as i64 * 1000)
+        .ignore()
+        .cmd("ZADD")
+        .arg(key)
+        .arg(now)
+        .arg(member)
+        .ignore()
+        .cmd("ZCARD")
+        .arg(key)
+        .cmd("EXPIRE")
+        .arg(key)
+        .arg(window_sec)
+        .ignore()
+        .query_async(&mut conn)
+        .await
+        .map_err(AppError::from)?;
+    Ok(count)
+}
+
+/// 滑动窗口内的记录数
+pub async fn window_count(client: &Client, key: &str, window_sec: usize) -> Result<usize> {
+    let mut conn = get_conn(client).await?;
+    let now = chrono::Local::now().timestamp_millis();
+    conn.zcount(key, now - window_sec as i64 * 1000, "+inf")
+        .await
+        .map_err(AppError::from)
+}
+
+/// 令牌桶脚本。参数依次为容量、每分钟补充的令牌数和当前的毫秒时间戳，返回需要等待的毫秒数
+const TOKEN_BUCKET_SCRIPT: &str = r"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2]) / 60000
+local now = tonumber(ARGV[3])
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated')
+local tokens = tonumber(bucket[1]) or capacity
+local updated = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - updated) * rate)
+local wait = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+else
+    wait = math.ceil((1 - tokens) / rate)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil((capacity - tokens) / rate) + 1000)
+return wait
+";
+
+/// 令牌桶：补充令牌后取出一个，返回还需等待的毫秒数，为 0 时表示已取出。
+/// 使用脚本读写，多个实例同时访问同一个桶时也不会多取
+pub async fn token_bucket(
+    client: &Client,
+    key: &str,
+    capacity: u32,
+    per_minute: u32,
+) -> Result<u64> {
+    let mut conn = get_conn(client).await?;
+    let now = chrono::Local::now().timestamp_millis();
+    redis::Script::new(TOKEN_BUCKET_SCRIPT)
+        .key(key)
+        .arg(capacity)
+        .arg(per_minute)
+        .arg(now)
+        .invoke_async(&mut conn)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 将数据写入 redis，并将键加入各个集合中。集合的过期时间为`set_sec`
+pub async fn set_tagged(
+    client: &Client,
+    key: &str,
+    value: &str,
+    sec: usize,
+    sets: &[String],
+    set_sec: usize,
+) -> Result<()> {
+    let mut conn = get_conn(client).await?;
+    let mut pipe = redis::pipe();
+    pipe.set_ex(key, value, sec).ignore();
+    for set in sets {
+        pipe.sadd(set, key).ignore().expire(set, set_sec).ignore();
+    }
+    pipe.query_async(&mut conn).await.map_err(AppError::from)
+}
+
+/// 删除多个键
+pub async fn del_all(client: &Client, keys: &[String]) -> Result<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let mut conn = get_conn(client).await?;
+    conn.del(keys).await.map_err(AppError::from)
+}
@@ -0,0 +1,147 @@
+//! 管理员角色及权限
+//!
+//! 每个角色拥有一组固定的权限，后台路由按所需的权限分组检查，
+//! 见[`crate::middleware::permission`]。
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// 权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// 撰写文章，只能修改自己的文章
+    WriteTopic,
+    /// 修改、删除所有文章
+    ManageTopic,
+    ManageSubject,
+    ManageTag,
+    /// 审核评论
+    ModerateComment,
+    /// 上传及浏览媒体库
+    UploadMedia,
+    /// 删除、恢复媒体文件
+    ManageMedia,
+    /// 导入导出专题和文章
+    ImportExport,
+    /// 管理其他管理员账号
+    ManageAdmin,
+}
+impl Permission {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Permission::WriteTopic => "撰写文章",
+            Permission::ManageTopic => "管理所有文章",
+            Permission::ManageSubject => "管理专题",
+            Permission::ManageTag => "管理标签",
+            Permission::ModerateComment => "审核评论",
+            Permission::UploadMedia => "上传文件",
+            Permission::ManageMedia => "管理媒体库",
+            Permission::ImportExport => "导入导出",
+            Permission::ManageAdmin => "管理账号",
+        }
+    }
+}
+
+const EDITOR_PERMISSIONS: &[Permission] = &[
+    Permission::WriteTopic,
+    Permission::ManageTopic,
+    Permission::ManageSubject,
+    Permission::ManageTag,
+    Permission::ModerateComment,
+    Permission::UploadMedia,
+    Permission::ManageMedia,
+    Permission::ImportExport,
+];
+const AUTHOR_PERMISSIONS: &[Permission] = &[Permission::WriteTopic, Permission::UploadMedia];
+const MODERATOR_PERMISSIONS: &[Permission] = &[Permission::ModerateComment];
+
+/// 管理员角色
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// 所有者，拥有全部权限
+    Owner,
+    /// 编辑，可以管理所有内容，但不能管理账号
+    Editor,
+    /// 作者，只能撰写和修改自己的文章
+    #[default]
+    Author,
+    /// 审核员，只能审核评论
+    Moderator,
+}
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Owner, Role::Editor, Role::Author, Role::Moderator];
+
+    /// 数据库中保存的值
+    pub fn code(&self) -> i16 {
+        match self {
+            Role::Owner => 1,
+            Role::Editor => 2,
+            Role::Author => 3,
+            Role::Moderator => 4,
+        }
+    }
+    pub fn from_code(code: i16) -> Self {
+        match code {
+            1 => Role::Owner,
+            2 => Role::Editor,
+            4 => Role::Moderator,
+            _ => Role::Author,
+        }
+    }
+    /// 用于表单的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Editor => "editor",
+            Role::Author => "author",
+            Role::Moderator => "moderator",
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Owner => "所有者",
+            Role::Editor => "编辑",
+            Role::Author => "作者",
+            Role::Moderator => "审核员",
+        }
+    }
+    /// 是否拥有指定的权限
+    pub fn can(&self, permission: Permission) -> bool {
+        match self {
+            Role::Owner => true,
+            Role::Editor => EDITOR_PERMISSIONS.contains(&permission),
+            Role::Author => AUTHOR_PERMISSIONS.contains(&permission),
+            Role::Moderator => MODERATOR_PERMISSIONS.contains(&permission),
+        }
+    }
+    /// 没有权限时返回 403 错误
+    pub fn require(&self, permission: Permission) -> Result<(), AppError> {
+        if self.can(permission) {
+            Ok(())
+        } else {
+            Err(AppError::forbidden(&format!(
+                "{}没有“{}”的权限",
+                self.name(),
+                permission.name()
+            )))
+        }
+    }
+}
+impl FromStr for Role {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Role::ALL
+            .into_iter()
+            .find(|role| role.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "无效的角色：{}，可选值为 owner、editor、author、moderator",
+                    s
+                )
+            })
+    }
+}
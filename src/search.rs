@@ -0,0 +1,153 @@
+//! 全文搜索
+
+use regex::{Captures, Regex};
+
+use crate::{html::escape, protect};
+
+/// 高亮片段的开始标记
+const HEADLINE_START: &str = "[[axum_rs_hl]]";
+/// 高亮片段的结束标记
+const HEADLINE_STOP: &str = "[[/axum_rs_hl]]";
+
+/// 生成 `ts_headline` 的选项
+pub fn headline_options() -> String {
+    format!(
+        "StartSel=\"{}\", StopSel=\"{}\", MaxFragments=3, MaxWords=30, MinWords=10, FragmentDelimiter=\" … \"",
+        HEADLINE_START, HEADLINE_STOP
+    )
+}
+
+/// 只按子串命中时，片段从关键字之前多少个字符开始
+pub const SNIPPET_BEFORE: usize = 40;
+/// 只按子串命中时，片段的字符数
+pub const SNIPPET_LEN: usize = 120;
+
+/// 按子串匹配时`ILIKE`使用的模式，关键字中的通配符按原样匹配
+pub fn like_pattern(keyword: &str) -> String {
+    let keyword = keyword
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", keyword)
+}
+
+/// 高亮片段中的关键字（不区分大小写）。
+/// 只按子串命中的文章，其片段中没有 `ts_headline` 的标记，需要在这里补上
+pub fn mark_keyword(headline: &str, keyword: &str) -> String {
+    if keyword.is_empty() || headline.contains(HEADLINE_START) {
+        return headline.to_string();
+    }
+    let re = Regex::new(&format!("(?i){}", regex::escape(keyword))).unwrap();
+    re.replace_all(headline, |cap: &Captures| {
+        format!("{}{}{}", HEADLINE_START, &cap[0], HEADLINE_STOP)
+    })
+    .into_owned()
+}
+
+/// 将 `ts_headline` 生成的片段转换成安全的HTML，命中的关键字使用`<mark>`标记
+pub fn headline_to_html(headline: &str) -> String {
+    escape(headline)
+        .replace(HEADLINE_START, "<mark>")
+        .replace(HEADLINE_STOP, "</mark>")
+}
+
+/// 用于全文搜索的纯文本：去掉按`policy`需要隐藏的内容，再去掉 HTML 标签
+pub fn text(topic_id: i64, html: &str, policy: protect::Policy) -> String {
+    html_to_text(&protect::public_html(topic_id, html, policy))
+}
+
+/// 去掉 HTML 标签，并还原渲染时转义的字符
+fn html_to_text(html: &str) -> String {
+    let text = Regex::new(r"<[^>]*>").unwrap().replace_all(html, " ");
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ProtectMode;
+
+    fn policy(mode: ProtectMode) -> protect::Policy {
+        protect::Policy { mode, count: 0 }
+    }
+
+    #[test]
+    fn text_excludes_explicit_protected() {
+        let html = format!(
+            "<h2>标题</h2>\n<p>公开的内容</p>\n{}<p>只有通过验证才能看到</p>\n{}",
+            protect::BEGIN_MARKER,
+            protect::END_MARKER
+        );
+        let text = text(1, &html, policy(ProtectMode::Explicit));
+        assert!(text.contains("标题"));
+        assert!(text.contains("公开的内容"));
+        assert!(!text.contains("只有通过验证才能看到"));
+        assert!(!text.contains('<'));
+        let text = super::text(1, &html, policy(ProtectMode::None));
+        assert!(text.contains("只有通过验证才能看到"));
+    }
+
+    #[test]
+    fn text_excludes_randomly_protected() {
+        let paragraphs: Vec<String> = (0..6).map(|i| format!("第{}段内容", i)).collect();
+        let html: String = paragraphs
+            .iter()
+            .map(|p| format!("<p>{}</p>\n", p))
+            .collect();
+        for topic_id in 1..20 {
+            let text = text(topic_id, &html, policy(ProtectMode::Random));
+            let (hidden_html, _) =
+                protect::hide("secret", topic_id, &html, policy(ProtectMode::Random));
+            let hidden: Vec<&String> = paragraphs
+                .iter()
+                .filter(|p| !hidden_html.contains(p.as_str()))
+                .collect();
+            // 6 个段落时随机隐藏 2 个
+            assert_eq!(hidden.len(), 2);
+            for p in paragraphs.iter() {
+                assert_eq!(text.contains(p.as_str()), !hidden.contains(&p));
+            }
+        }
+    }
+
+    #[test]
+    fn text_unescapes_entities() {
+        let text = text(
+            1,
+            "<pre>a &lt; b &amp;&amp; c</pre>",
+            policy(ProtectMode::None),
+        );
+        assert_eq!(text.trim(), "a < b && c");
+    }
+
+    #[test]
+    fn like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("中文搜索"), "%中文搜索%");
+        assert_eq!(like_pattern("100%_a\\b"), "%100\\%\\_a\\\\b%");
+    }
+
+    #[test]
+    fn mark_keyword_highlights_chinese_substring() {
+        let headline = mark_keyword("使用 Axum 开发中文网站，支持中文搜索", "中文");
+        assert_eq!(
+            headline_to_html(&headline),
+            "使用 Axum 开发<mark>中文</mark>网站，支持<mark>中文</mark>搜索"
+        );
+        assert_eq!(
+            headline_to_html(&mark_keyword("使用 Axum 开发", "axum")),
+            "使用 <mark>Axum</mark> 开发"
+        );
+    }
+
+    #[test]
+    fn mark_keyword_keeps_ts_headline() {
+        let headline = format!("{}axum{} 中文", HEADLINE_START, HEADLINE_STOP);
+        assert_eq!(mark_keyword(&headline, "中文"), headline);
+    }
+}
@@ -1,6 +1,17 @@
+//! 后台登录会话
+//!
+//! 会话保存在 redis 中，闲置超过`SESSION.EXPIRED`秒失效，有操作时自动延长，
+//! 但不会超过`SESSION.MAX_LIFETIME`。每个管理员的会话 ID 另外记录在一个集合中，
+//! 用于列出和注销该管理员的会话。
+
+use redis::Client;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-use crate::config::SessionConfig;
+use crate::{config::SessionConfig, model::AdminSession, rdb, time::now, Result};
+
+/// 距上次延长超过该秒数才再次延长，避免每个请求都写入 redis
+const TOUCH_INTERVAL: i32 = 60;
 
 pub struct GeneratedKey {
     pub id: String,
@@ -24,3 +35,178 @@ pub fn gen_key(cfg: &SessionConfig) -> GeneratedKey {
 pub fn gen_redis_key(cfg: &SessionConfig, id: &str) -> String {
     format!("{}{}", &cfg.prefix, id)
 }
+/// 管理员的会话 ID 集合
+fn index_key(cfg: &SessionConfig, admin_id: i32) -> String {
+    format!("{}admin:{}", &cfg.prefix, admin_id)
+}
+/// 会话 ID 是 32 位十六进制字符，其它值不会去 redis 中查找
+pub fn is_valid_id(id: &str) -> bool {
+    id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+/// 会话的标识，用于在页面中区分会话而不暴露会话 ID
+pub fn handle(id: &str) -> String {
+    Sha256::digest(id.as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 登录成功后设置的 Cookie
+pub fn cookie(cfg: &SessionConfig, id: &str) -> String {
+    cookie_with_max_age(cfg, id, cfg.max_lifetime)
+}
+/// 退出登录时清除 Cookie
+pub fn clear_cookie(cfg: &SessionConfig) -> String {
+    cookie_with_max_age(cfg, "", 0)
+}
+fn cookie_with_max_age(cfg: &SessionConfig, value: &str, max_age: usize) -> String {
+    build_cookie(cfg, &cfg.id_name, value, "/", max_age)
+}
+/// 生成与会话 Cookie 属性相同的 Cookie
+pub fn build_cookie(
+    cfg: &SessionConfig,
+    name: &str,
+    value: &str,
+    path: &str,
+    max_age: usize,
+) -> String {
+    let mut cookie = format!(
+        "{}={}; Path={}; HttpOnly; SameSite=Lax; Max-Age={}",
+        name, value, path, max_age
+    );
+    if cfg.secure {
+        cookie.push_str("; Secure");
+    }
+    cookie
+}
+
+/// 保存新的会话，返回会话 ID
+pub async fn create(client: &Client, cfg: &SessionConfig, sess: &AdminSession) -> Result<String> {
+    let GeneratedKey { id, redis_key, .. } = gen_key(cfg);
+    save(client, cfg, &redis_key, sess).await?;
+    let index_key = index_key(cfg, sess.id);
+    rdb::sadd(client, &index_key, &id).await?;
+    rdb::expire(client, &index_key, cfg.max_lifetime).await?;
+    Ok(id)
+}
+async fn save(
+    client: &Client,
+    cfg: &SessionConfig,
+    redis_key: &str,
+    sess: &AdminSession,
+) -> Result<()> {
+    let data = serde_json::to_string(sess).unwrap();
+    rdb::set(client, redis_key, &data, cfg.expired).await
+}
+async fn get(client: &Client, cfg: &SessionConfig, id: &str) -> Result<Option<AdminSession>> {
+    let data = rdb::get(client, &gen_redis_key(cfg, id)).await?;
+    Ok(data.and_then(|data| {
+        serde_json::from_str(&data)
+            .map_err(|err| tracing::error!("des admin_session failed: {:?}", err))
+            .ok()
+    }))
+}
+
+/// 读取会话并延长有效期。超过最长有效期的会话会被删除
+pub async fn load(client: &Client, cfg: &SessionConfig, id: &str) -> Result<Option<AdminSession>> {
+    if !is_valid_id(id) {
+        return Ok(None);
+    }
+    let mut sess = match get(client, cfg, id).await? {
+        Some(sess) => sess,
+        None => return Ok(None),
+    };
+    let now = now();
+    if now - sess.login_at >= cfg.max_lifetime as i32 {
+        destroy(client, cfg, id).await?;
+        return Ok(None);
+    }
+    if now - sess.active_at >= TOUCH_INTERVAL {
+        sess.active_at = now;
+        sess.dateline = (now + cfg.expired as i32).min(sess.login_at + cfg.max_lifetime as i32);
+        save(client, cfg, &gen_redis_key(cfg, id), &sess).await?;
+    }
+    Ok(Some(sess))
+}
+
+/// 删除会话
+pub async fn destroy(client: &Client, cfg: &SessionConfig, id: &str) -> Result<()> {
+    if !is_valid_id(id) {
+        return Ok(());
+    }
+    if let Some(sess) = get(client, cfg, id).await? {
+        rdb::srem(client, &index_key(cfg, sess.id), id).await?;
+    }
+    rdb::del(client, &gen_redis_key(cfg, id)).await
+}
+
+/// 管理员的一个有效会话
+pub struct ActiveSession {
+    pub handle: String,
+    pub session: AdminSession,
+}
+
+/// 管理员的所有有效会话，最近活跃的在前。顺便清理集合中已过期的会话 ID
+pub async fn list(
+    client: &Client,
+    cfg: &SessionConfig,
+    admin_id: i32,
+) -> Result<Vec<ActiveSession>> {
+    let index_key = index_key(cfg, admin_id);
+    let mut list = vec![];
+    for id in rdb::smembers(client, &index_key).await? {
+        match get(client, cfg, &id).await? {
+            Some(session) => list.push(ActiveSession {
+                handle: handle(&id),
+                session,
+            }),
+            None => rdb::srem(client, &index_key, &id).await?,
+        }
+    }
+    list.sort_by_key(|s| std::cmp::Reverse(s.session.active_at));
+    Ok(list)
+}
+
+/// 注销管理员满足条件的会话，条件的参数为会话 ID。返回注销的会话数
+async fn revoke_where(
+    client: &Client,
+    cfg: &SessionConfig,
+    admin_id: i32,
+    f: impl Fn(&str) -> bool,
+) -> Result<usize> {
+    let index_key = index_key(cfg, admin_id);
+    let mut revoked = 0;
+    for id in rdb::smembers(client, &index_key).await? {
+        if f(&id) {
+            rdb::del(client, &gen_redis_key(cfg, &id)).await?;
+            rdb::srem(client, &index_key, &id).await?;
+            revoked += 1;
+        }
+    }
+    Ok(revoked)
+}
+
+/// 注销指定管理员的所有会话，返回注销的会话数
+pub async fn revoke_all(client: &Client, cfg: &SessionConfig, admin_id: i32) -> Result<usize> {
+    revoke_where(client, cfg, admin_id, |_| true).await
+}
+/// 注销除当前会话外的所有会话
+pub async fn revoke_others(
+    client: &Client,
+    cfg: &SessionConfig,
+    admin_id: i32,
+    current_id: &str,
+) -> Result<usize> {
+    revoke_where(client, cfg, admin_id, |id| id != current_id).await
+}
+/// 根据标识注销一个会话
+pub async fn revoke(
+    client: &Client,
+    cfg: &SessionConfig,
+    admin_id: i32,
+    handle_str: &str,
+) -> Result<bool> {
+    let revoked = revoke_where(client, cfg, admin_id, |id| handle(id) == handle_str).await?;
+    Ok(revoked > 0)
+}
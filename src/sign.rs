@@ -0,0 +1,41 @@
+//! 使用`web.secret_key`进行 HMAC-SHA256 签名
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 对消息签名，返回十六进制字符串
+pub fn sign(secret_key: &str, msg: &str) -> String {
+    mac(secret_key, msg)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 验证签名
+pub fn verify(secret_key: &str, msg: &str, sig: &str) -> bool {
+    match decode_hex(sig) {
+        Some(sig) => mac(secret_key, msg).verify_slice(&sig).is_ok(),
+        None => false,
+    }
+}
+
+fn mac(secret_key: &str, msg: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret_key.as_bytes()).expect("HMAC可以使用任意长度的密钥");
+    mac.update(msg.as_bytes());
+    mac
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok().filter(|p| p.len() == 2)?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
@@ -0,0 +1,91 @@
+//! 站点地图(sitemap)及 robots.txt
+
+use chrono::{TimeZone, Utc};
+
+use crate::{
+    config::{RobotsConfig, WebConfig},
+    html::escape,
+    model::SitemapUrl,
+};
+
+/// 单个站点地图允许的最大URL数
+pub const MAX_URLS: usize = 50_000;
+
+/// 站点地图中不依赖数据库的页面
+pub const STATIC_PATHS: [&str; 5] = ["/", "/subject", "/topic", "/tag", "/about"];
+
+/// 对路径进行百分号编码，保留`/`
+fn encode_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+fn lastmod(ts: i32) -> Option<String> {
+    if ts > 0 {
+        Some(Utc.timestamp(ts as i64, 0).to_rfc3339())
+    } else {
+        None
+    }
+}
+
+/// 生成站点地图
+pub fn urlset(cfg: &WebConfig, urls: &[SitemapUrl]) -> String {
+    let mut out = String::new();
+    out.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    out.push_str(r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+    for url in urls {
+        out.push_str("<url>");
+        out.push_str(&format!(
+            "<loc>{}</loc>",
+            escape(&cfg.url(&encode_path(&url.loc)))
+        ));
+        if let Some(lastmod) = lastmod(url.lastmod) {
+            out.push_str(&format!("<lastmod>{}</lastmod>", lastmod));
+        }
+        out.push_str("</url>");
+    }
+    out.push_str("</urlset>");
+    out
+}
+
+/// 生成站点地图索引
+pub fn index(cfg: &WebConfig, sitemaps: &[SitemapUrl]) -> String {
+    let mut out = String::new();
+    out.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    out.push_str(r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+    for sitemap in sitemaps {
+        out.push_str("<sitemap>");
+        out.push_str(&format!(
+            "<loc>{}</loc>",
+            escape(&cfg.url(&encode_path(&sitemap.loc)))
+        ));
+        if let Some(lastmod) = lastmod(sitemap.lastmod) {
+            out.push_str(&format!("<lastmod>{}</lastmod>", lastmod));
+        }
+        out.push_str("</sitemap>");
+    }
+    out.push_str("</sitemapindex>");
+    out
+}
+
+/// 生成 robots.txt
+pub fn robots(web_cfg: &WebConfig, cfg: &RobotsConfig) -> String {
+    let mut out = String::from("User-agent: *\n");
+    let disallow = ["/admin", "/login"]
+        .into_iter()
+        .chain(cfg.disallow.split(',').map(|s| s.trim()))
+        .filter(|s| !s.is_empty());
+    for path in disallow {
+        out.push_str(&format!("Disallow: {}\n", path));
+    }
+    out.push_str(&format!("\nSitemap: {}\n", web_cfg.url("/sitemap.xml")));
+    out
+}
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+
+use super::Storage;
+use crate::{
+    error::{AppError, AppErrorType},
+    Result,
+};
+
+/// 本地磁盘
+pub struct Local {
+    dir: PathBuf,
+    base_url: String,
+}
+
+impl Local {
+    pub fn new(dir: &str, base_url: &str) -> Self {
+        Self {
+            dir: PathBuf::from(dir),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for Local {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, data: &[u8], _content_type: &str) -> Result<()> {
+        let path = self.dir.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|err| AppError::from_err(err, AppErrorType::Common))?;
+        }
+        tokio::fs::write(&path, data)
+            .await
+            .map_err(|err| AppError::from_err(err, AppErrorType::Common))
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+}
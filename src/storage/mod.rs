@@ -0,0 +1,43 @@
+//! 上传文件的存储
+//!
+//! 通过[`Storage`]屏蔽具体的存储方式，目前实现了本地磁盘，
+//! 之后可以按同样的方式增加 S3 兼容的对象存储（如 MinIO）。
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{
+    config::UploadConfig,
+    error::{AppError, AppErrorType},
+    Result,
+};
+
+mod local;
+
+pub use self::local::Local;
+
+/// 存储后端
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// 存储名称，记录在`media.storage`中
+    fn name(&self) -> &'static str;
+    /// 保存文件，`key`为相对路径，如`ab/abcdef.png`
+    async fn put(&self, key: &str, data: &[u8], content_type: &str) -> Result<()>;
+    /// 文件的访问地址
+    fn url(&self, key: &str) -> String;
+}
+
+/// 根据配置创建存储后端
+pub fn from_config(cfg: &UploadConfig) -> Result<Arc<dyn Storage>> {
+    let storage: Arc<dyn Storage> = match cfg.storage.as_str() {
+        "local" => Arc::new(Local::new(&cfg.dir, &cfg.base_url)),
+        other => {
+            return Err(AppError::from_str(
+                &format!("不支持的存储方式：{}", other),
+                AppErrorType::Common,
+            ))
+        }
+    };
+    Ok(storage)
+}
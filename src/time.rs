@@ -1,3 +1,32 @@
+use chrono::{Local, NaiveDateTime, TimeZone};
+
+/// `<input type="datetime-local">`使用的时间格式
+const DATETIME_LOCAL_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
 pub fn now() -> i32 {
     chrono::Local::now().timestamp() as i32
 }
+
+/// 将本地时间（如`2022-01-01T08:00`）解析为时间戳
+pub fn parse_local(s: &str) -> Option<i32> {
+    let s = s.trim();
+    let dt = NaiveDateTime::parse_from_str(s, DATETIME_LOCAL_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S"))
+        .ok()?;
+    Local
+        .from_local_datetime(&dt)
+        .earliest()
+        .map(|dt| dt.timestamp() as i32)
+}
+
+/// 将时间戳格式化为本地时间，用于`<input type="datetime-local">`。为0时返回空字符串
+pub fn format_local(ts: i32) -> String {
+    if ts == 0 {
+        return "".to_string();
+    }
+    Local
+        .timestamp(ts as i64, 0)
+        .format(DATETIME_LOCAL_FORMAT)
+        .to_string()
+}
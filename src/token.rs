@@ -0,0 +1,19 @@
+//! API令牌
+
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// 令牌前缀，便于识别泄露的令牌
+const PREFIX: &str = "axumrs_";
+
+/// 生成新的令牌
+pub fn generate() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", PREFIX, hex)
+}
+
+/// 计算令牌的哈希值。数据库中只保存哈希值
+pub fn hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,216 @@
+//! 基于时间的一次性密码（RFC 6238），用于管理员的两步验证
+//!
+//! 使用验证器应用的默认参数：HMAC-SHA1、6 位数字、30 秒一个时间步。
+
+use data_encoding::{BASE32_NOPAD, BASE64};
+use hmac::{Hmac, Mac};
+use qrcode::{render::svg, QrCode};
+use rand::Rng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// 验证器中显示的发行方
+const ISSUER: &str = "AXUM.RS";
+/// 时间步长（秒）
+const STEP: i64 = 30;
+const DIGITS: usize = 6;
+/// 允许前后各偏差一个时间步，容忍手机与服务器的时钟误差
+const SKEW: i64 = 1;
+/// 每次生成的恢复码数量
+pub const RECOVERY_CODE_COUNT: usize = 10;
+/// 恢复码使用的字符，去掉了容易混淆的 0、1、i、l、o
+const RECOVERY_CODE_CHARS: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// 生成新的密钥，返回 Base32 编码的字符串
+pub fn generate_secret() -> String {
+    let bytes: [u8; 20] = rand::thread_rng().gen();
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// HOTP（RFC 4226）动态截断得到的 31 位整数，取其末尾若干位即为验证码
+fn truncate(key: &[u8], step: i64) -> u32 {
+    let mut mac = HmacSha1::new_from_slice(key).expect("HMAC可以使用任意长度的密钥");
+    mac.update(&(step as u64).to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ])
+}
+
+/// 指定时间步的验证码
+fn code_at(key: &[u8], step: i64) -> u32 {
+    truncate(key, step) % 10u32.pow(DIGITS as u32)
+}
+
+/// 指定时间的验证码，测试中模拟验证器
+#[cfg(test)]
+pub(crate) fn code(secret: &str, timestamp: i64) -> String {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+    format!(
+        "{:0width$}",
+        code_at(&key, timestamp / STEP),
+        width = DIGITS
+    )
+}
+
+/// 是否为验证码的格式，用于区分验证码和恢复码
+pub fn is_code(code: &str) -> bool {
+    code.len() == DIGITS && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// 验证验证码，通过时返回其所在的时间步。调用方需要记录该时间步，防止同一个验证码被重复使用
+pub fn verify(secret: &str, code: &str, timestamp: i64) -> Option<i64> {
+    if !is_code(code) {
+        return None;
+    }
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let code: u32 = code.parse().ok()?;
+    let current = timestamp / STEP;
+    (current - SKEW..=current + SKEW).find(|&step| code_at(&key, step) == code)
+}
+
+/// 验证器扫码添加账号使用的 URI
+pub fn provisioning_uri(username: &str, secret: &str) -> String {
+    let label = url_encode(&format!("{}:{}", ISSUER, username));
+    format!(
+        "otpauth://totp/{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        label,
+        secret,
+        url_encode(ISSUER),
+        DIGITS,
+        STEP
+    )
+}
+
+fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// 将 URI 生成二维码，返回可直接用于`<img src>`的 SVG data URI
+pub fn qr_code(uri: &str) -> String {
+    let svg = QrCode::new(uri.as_bytes())
+        .map(|code| code.render::<svg::Color>().min_dimensions(200, 200).build())
+        .unwrap_or_default();
+    format!(
+        "data:image/svg+xml;base64,{}",
+        BASE64.encode(svg.as_bytes())
+    )
+}
+
+/// 生成一组恢复码，格式如`abcde-fghjk`
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..10)
+                .map(|_| RECOVERY_CODE_CHARS[rng.gen_range(0..RECOVERY_CODE_CHARS.len())] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+/// 恢复码的哈希值，数据库中只保存哈希值。忽略大小写、空白和连字符
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 附录 B 使用的 SHA-1 密钥
+    const RFC_KEY: &[u8] = b"12345678901234567890";
+
+    fn rfc_secret() -> String {
+        BASE32_NOPAD.encode(RFC_KEY)
+    }
+
+    #[test]
+    fn rfc6238_sha1_vectors() {
+        let vectors = [
+            (59, 94287082),
+            (1111111109, 7081804),
+            (1111111111, 14050471),
+            (1234567890, 89005924),
+            (2000000000, 69279037),
+            (20000000000, 65353130),
+        ];
+        for (timestamp, expected) in vectors {
+            // 附录 B 中是 8 位数字
+            assert_eq!(truncate(RFC_KEY, timestamp / STEP) % 100_000_000, expected);
+            assert_eq!(code_at(RFC_KEY, timestamp / STEP), expected % 1_000_000);
+        }
+    }
+
+    #[test]
+    fn verify_returns_step() {
+        let secret = rfc_secret();
+        assert_eq!(verify(&secret, "287082", 59), Some(1));
+        assert_eq!(verify(&secret, "005924", 1234567890), Some(41152263));
+    }
+
+    #[test]
+    fn verify_window() {
+        let secret = rfc_secret();
+        let now = 1234567890;
+        let current = now / STEP;
+        // 前后各一个时间步
+        assert_eq!(
+            verify(&secret, &code(&secret, now - STEP), now),
+            Some(current - 1)
+        );
+        assert_eq!(
+            verify(&secret, &code(&secret, now + STEP), now),
+            Some(current + 1)
+        );
+        // 超出范围
+        assert_eq!(verify(&secret, &code(&secret, now - 2 * STEP), now), None);
+        assert_eq!(verify(&secret, &code(&secret, now + 2 * STEP), now), None);
+    }
+
+    #[test]
+    fn verify_rejects_malformed() {
+        let secret = rfc_secret();
+        assert_eq!(verify(&secret, "28708", 59), None);
+        assert_eq!(verify(&secret, "2870820", 59), None);
+        assert_eq!(verify(&secret, "28708a", 59), None);
+        assert_eq!(verify("不是Base32", "287082", 59), None);
+    }
+
+    #[test]
+    fn recovery_codes() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        for code in codes.iter() {
+            assert_eq!(code.len(), 11);
+            assert!(!is_code(code));
+        }
+        assert_eq!(
+            hash_recovery_code("abcde-fghjk"),
+            hash_recovery_code(" ABCDE fghjk ")
+        );
+        assert_ne!(
+            hash_recovery_code("abcde-fghjk"),
+            hash_recovery_code("abcde-fghjm")
+        );
+    }
+}
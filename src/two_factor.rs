@@ -0,0 +1,271 @@
+//! 管理员的两步验证
+//!
+//! 密码验证通过后，启用了两步验证的管理员还需要输入验证器中的验证码或一个恢复码才会创建登录会话。
+//! 系统管理员要求所有人启用两步验证后，尚未启用的管理员在登录时先完成启用。
+//! 两步之间的状态保存在 redis 中，浏览器只持有一个随机 ID。
+
+use async_trait::async_trait;
+use redis::Client;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::GenericClient;
+
+use crate::{
+    config::SessionConfig,
+    db::{admin, setting},
+    model::Admin,
+    rdb, session,
+    time::now,
+    totp, Result,
+};
+
+const PREFIX: &str = "axum_rs:two_factor:";
+/// 输入验证码的时限（秒）
+const PENDING_TTL: usize = 300;
+/// 一次登录中最多可以输错的次数，超过后需要重新输入密码
+const MAX_ATTEMPTS: u8 = 5;
+/// 在后台启用时，新密钥的保存时间（秒）
+const SETUP_TTL: usize = 600;
+/// 是否要求所有管理员启用两步验证的设置项
+pub const SETTING_REQUIRED: &str = "require_two_factor";
+
+/// 是否要求所有管理员启用两步验证
+pub async fn is_required(client: &impl GenericClient) -> Result<bool> {
+    Ok(setting::get(client, SETTING_REQUIRED).await?.as_deref() == Some("1"))
+}
+
+/// 记录已经使用过的验证码和恢复码，防止重复使用
+#[async_trait]
+pub trait UsedCodes {
+    /// 记录通过验证的时间步。不大于上次记录的时间步时返回`false`
+    async fn use_totp_step(&self, admin_id: i32, step: i64) -> Result<bool>;
+    /// 使用恢复码，使用后作废。不存在或已经使用过时返回`false`
+    async fn use_recovery_code(&self, admin_id: i32, code_hash: &str) -> Result<bool>;
+}
+#[async_trait]
+impl UsedCodes for tokio_postgres::Client {
+    async fn use_totp_step(&self, admin_id: i32, step: i64) -> Result<bool> {
+        admin::use_totp_step(self, admin_id, step).await
+    }
+    async fn use_recovery_code(&self, admin_id: i32, code_hash: &str) -> Result<bool> {
+        admin::use_recovery_code(self, admin_id, code_hash).await
+    }
+}
+
+/// 验证验证码或恢复码，恢复码使用后作废
+pub async fn verify(client: &tokio_postgres::Client, item: &Admin, code: &str) -> Result<bool> {
+    verify_at(client, item, code, now() as i64).await
+}
+
+async fn verify_at(
+    used: &(impl UsedCodes + Sync),
+    item: &Admin,
+    code: &str,
+    timestamp: i64,
+) -> Result<bool> {
+    let code = code.trim();
+    if totp::is_code(code) {
+        return match totp::verify(&item.totp_secret, code, timestamp) {
+            Some(step) => used.use_totp_step(item.id, step).await,
+            None => Ok(false),
+        };
+    }
+    if code.is_empty() {
+        return Ok(false);
+    }
+    used.use_recovery_code(item.id, &totp::hash_recovery_code(code))
+        .await
+}
+
+/// 密码验证通过、等待输入验证码的登录
+#[derive(Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub admin_id: i32,
+    /// 登录时启用两步验证所用的新密钥，已启用时为空
+    pub secret: String,
+}
+impl PendingLogin {
+    pub fn is_enroll(&self) -> bool {
+        !self.secret.is_empty()
+    }
+}
+
+fn pending_key(id: &str) -> String {
+    format!("{}pending:{}", PREFIX, id)
+}
+fn attempts_key(id: &str) -> String {
+    format!("{}attempts:{}", PREFIX, id)
+}
+fn pending_cookie_name(cfg: &SessionConfig) -> String {
+    format!("{}_2fa", &cfg.id_name)
+}
+/// 保存待验证的登录的 Cookie
+pub fn pending_cookie(cfg: &SessionConfig, id: &str) -> String {
+    session::build_cookie(cfg, &pending_cookie_name(cfg), id, "/login", PENDING_TTL)
+}
+pub fn clear_pending_cookie(cfg: &SessionConfig) -> String {
+    session::build_cookie(cfg, &pending_cookie_name(cfg), "", "/login", 0)
+}
+/// 从请求的 Cookie 中取出待验证的登录 ID
+pub fn pending_id(cfg: &SessionConfig, headers: &axum::http::HeaderMap) -> Option<String> {
+    crate::handler::helper::get_cookie(headers, &pending_cookie_name(cfg))
+}
+
+/// 保存新的待验证的登录，返回其 ID
+pub async fn create_pending(client: &Client, pending: &PendingLogin) -> Result<String> {
+    let id = session::id();
+    save_pending(client, &id, pending).await?;
+    Ok(id)
+}
+async fn save_pending(client: &Client, id: &str, pending: &PendingLogin) -> Result<()> {
+    let data = serde_json::to_string(pending).unwrap();
+    rdb::set(client, &pending_key(id), &data, PENDING_TTL).await
+}
+pub async fn load_pending(client: &Client, id: &str) -> Result<Option<PendingLogin>> {
+    if !session::is_valid_id(id) {
+        return Ok(None);
+    }
+    let data = rdb::get(client, &pending_key(id)).await?;
+    Ok(data.and_then(|data| serde_json::from_str(&data).ok()))
+}
+pub async fn destroy_pending(client: &Client, id: &str) -> Result<()> {
+    rdb::del(client, &pending_key(id)).await?;
+    rdb::del(client, &attempts_key(id)).await
+}
+/// 验证前先占用一次尝试机会，返回这是第几次尝试。次数用`INCR`计数，
+/// 同时提交的多个验证码也会各自占用一次机会。超过上限时作废该登录并返回`None`
+pub async fn take_attempt(client: &Client, id: &str) -> Result<Option<u8>> {
+    let attempt = rdb::incr(client, &attempts_key(id), PENDING_TTL).await?;
+    if attempt > MAX_ATTEMPTS as i64 {
+        destroy_pending(client, id).await?;
+        return Ok(None);
+    }
+    Ok(Some(attempt as u8))
+}
+/// 第`attempt`次尝试输错，用完所有机会时作废该登录。返回是否已作废
+pub async fn pending_failed(client: &Client, id: &str, attempt: u8) -> Result<bool> {
+    if attempt >= MAX_ATTEMPTS {
+        destroy_pending(client, id).await?;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+fn setup_key(admin_id: i32) -> String {
+    format!("{}setup:{}", PREFIX, admin_id)
+}
+/// 在后台启用时生成的新密钥，确认前保存在 redis 中。已有未过期的密钥时沿用，避免刷新页面后二维码失效
+pub async fn setup_secret(client: &Client, admin_id: i32) -> Result<String> {
+    let key = setup_key(admin_id);
+    if let Some(secret) = rdb::get(client, &key).await? {
+        return Ok(secret);
+    }
+    let secret = totp::generate_secret();
+    rdb::set(client, &key, &secret, SETUP_TTL).await?;
+    Ok(secret)
+}
+/// 待确认的密钥，已过期时返回`None`
+pub async fn load_setup_secret(client: &Client, admin_id: i32) -> Result<Option<String>> {
+    rdb::get(client, &setup_key(admin_id)).await
+}
+pub async fn clear_setup_secret(client: &Client, admin_id: i32) -> Result<()> {
+    rdb::del(client, &setup_key(admin_id)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use super::*;
+
+    /// 与数据库中的实现规则相同的内存实现
+    #[derive(Default)]
+    struct MemoryUsedCodes {
+        last_step: Mutex<i64>,
+        /// 恢复码的哈希值，值为是否已经使用
+        recovery_codes: Mutex<HashMap<String, bool>>,
+    }
+    #[async_trait]
+    impl UsedCodes for MemoryUsedCodes {
+        async fn use_totp_step(&self, _admin_id: i32, step: i64) -> Result<bool> {
+            let mut last_step = self.last_step.lock().unwrap();
+            if step <= *last_step {
+                return Ok(false);
+            }
+            *last_step = step;
+            Ok(true)
+        }
+        async fn use_recovery_code(&self, _admin_id: i32, code_hash: &str) -> Result<bool> {
+            let mut codes = self.recovery_codes.lock().unwrap();
+            match codes.get_mut(code_hash) {
+                Some(used) if !*used => {
+                    *used = true;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+    }
+
+    /// RFC 6238 附录 B 的密钥
+    fn admin() -> Admin {
+        Admin {
+            id: 1,
+            username: "axum.rs".to_string(),
+            password: "".to_string(),
+            is_sys: true,
+            is_del: false,
+            role: 0,
+            totp_secret: data_encoding::BASE32_NOPAD.encode(b"12345678901234567890"),
+            totp_enabled: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn totp_code_cannot_be_reused() {
+        let used = MemoryUsedCodes::default();
+        let item = admin();
+        // T=1234567890 的验证码
+        assert!(verify_at(&used, &item, "005924", 1234567890).await.unwrap());
+        assert!(!verify_at(&used, &item, "005924", 1234567890).await.unwrap());
+        // 仍在允许的时间窗口内，但时间步已经使用过
+        assert!(!verify_at(&used, &item, "005924", 1234567890 + 30)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn older_totp_code_rejected_after_newer() {
+        let used = MemoryUsedCodes::default();
+        let item = admin();
+        let now = 1234567890;
+        let previous = totp::code(&item.totp_secret, now - 30);
+        let current = totp::code(&item.totp_secret, now);
+        assert!(verify_at(&used, &item, &current, now).await.unwrap());
+        assert!(!verify_at(&used, &item, &previous, now).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn recovery_code_used_once() {
+        let used = MemoryUsedCodes::default();
+        let codes = totp::generate_recovery_codes();
+        for code in codes.iter() {
+            used.recovery_codes
+                .lock()
+                .unwrap()
+                .insert(totp::hash_recovery_code(code), false);
+        }
+        let item = admin();
+        let code = &codes[0];
+        assert!(verify_at(&used, &item, code, 0).await.unwrap());
+        assert!(!verify_at(&used, &item, code, 0).await.unwrap());
+        // 大小写和连字符不同也视为同一个恢复码
+        let variant = code.to_uppercase().replace('-', "");
+        assert!(!verify_at(&used, &item, &variant, 0).await.unwrap());
+        // 其它恢复码不受影响
+        assert!(verify_at(&used, &item, &codes[1].to_uppercase(), 0)
+            .await
+            .unwrap());
+        assert!(!verify_at(&used, &item, "unknown-code", 0).await.unwrap());
+        assert!(!verify_at(&used, &item, "  ", 0).await.unwrap());
+    }
+}
@@ -0,0 +1,83 @@
+// 文章编辑器：实时预览及自动保存草稿
+$(function () {
+  const $md = $('#md');
+  if (!$md.length) {
+    return;
+  }
+  const topicId = $md.data('topic-id') || 0;
+  const $preview = $('#md-preview');
+  const $status = $('#draft-status');
+  // 自动保存的间隔（毫秒）
+  const autosaveInterval = 30 * 1000;
+  let lastSaved = $md.val();
+  let previewTimer = null;
+
+  function timeText(dateline) {
+    return new Date(dateline * 1000).toLocaleString();
+  }
+
+  function renderPreview() {
+    $.ajax({
+      url: '/admin/topic/preview',
+      method: 'POST',
+      data: { md: $md.val() },
+      dataType: 'html',
+      success: function (html) {
+        $preview.html(html);
+        $preview.find('pre > code').not('.hl').each(function (_, code) {
+          hljs.highlightElement(code);
+        });
+      },
+      error: function () {
+        $status.text('预览失败，登录可能已过期，请在新窗口登录后继续').addClass('text-danger');
+      },
+    });
+  }
+
+  function saveDraft() {
+    const md = $md.val();
+    if (md === lastSaved) {
+      return;
+    }
+    $.ajax({
+      url: '/admin/topic/draft',
+      method: 'POST',
+      data: { topic_id: topicId, md: md },
+      dataType: 'json',
+      success: function (dateline) {
+        lastSaved = md;
+        $status.text('草稿已于' + timeText(dateline) + '自动保存').removeClass('text-danger');
+      },
+      error: function () {
+        $status.text('自动保存失败，请检查登录状态').addClass('text-danger');
+      },
+    });
+  }
+
+  $md.on('input', function () {
+    clearTimeout(previewTimer);
+    previewTimer = setTimeout(renderPreview, 500);
+  });
+  setInterval(saveDraft, autosaveInterval);
+
+  $.getJSON('/admin/topic/draft/' + topicId, function (draft) {
+    if (!draft || draft.md === $md.val()) {
+      return;
+    }
+    $('#draft-time').text(timeText(draft.dateline));
+    $('#draft-restore').show();
+    $('#draft-restore-btn').on('click', function () {
+      $md.val(draft.md);
+      lastSaved = draft.md;
+      $('#draft-restore').hide();
+      renderPreview();
+    });
+    $('#draft-discard-btn').on('click', function () {
+      $.post('/admin/topic/draft/discard/' + topicId, function () {
+        $('#draft-restore').hide();
+      });
+    });
+  });
+
+  renderPreview();
+});
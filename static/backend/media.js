@@ -0,0 +1,138 @@
+// 媒体库：上传文件，以及在编辑器中插入图片和附件
+const axumMedia = {
+  upload: function (files, done) {
+    const data = new FormData();
+    for (const file of files) {
+      data.append('file', file);
+    }
+    $.ajax({
+      url: '/admin/media/upload',
+      method: 'POST',
+      data: data,
+      processData: false,
+      contentType: false,
+      dataType: 'json',
+      success: done,
+      error: function () {
+        alert('上传失败，请检查登录状态后重试');
+      },
+    });
+  },
+  // 与 Media::markdown 保持一致
+  markdown: function (m) {
+    const name = m.file_name.replace(/[\[\]]/g, '');
+    return (m.is_image ? '!' : '') + '[' + name + '](' + m.url + ')';
+  },
+  // 上传失败的文件及原因
+  failures: function (results) {
+    return results
+      .filter(function (r) { return !r.media; })
+      .map(function (r) { return r.file_name + '：' + r.msg; });
+  },
+};
+
+$(function () {
+  // 媒体库页面
+  $('#media-upload-form').on('submit', function (e) {
+    e.preventDefault();
+    axumMedia.upload(this.file.files, function (results) {
+      const failed = axumMedia.failures(results);
+      if (failed.length) {
+        alert('以下文件上传失败：\n' + failed.join('\n'));
+      }
+      const msg = '成功上传' + (results.length - failed.length) + '个文件';
+      location.href = '/admin/media?msg=' + encodeURIComponent(msg);
+    });
+  });
+  $('.media-copy').on('click', function () {
+    navigator.clipboard.writeText($(this).data('markdown'));
+    $(this).html('<i class="fa fa-check"></i> 已复制');
+  });
+
+  // 编辑器中的媒体库
+  const $modal = $('#media-modal');
+  if (!$modal.length) {
+    return;
+  }
+  let page = 0;
+  let totalPages = 0;
+
+  function insert(text) {
+    const ta = document.getElementById('md');
+    const start = ta.selectionStart;
+    const end = ta.selectionEnd;
+    ta.value = ta.value.slice(0, start) + text + ta.value.slice(end);
+    ta.selectionStart = ta.selectionEnd = start + text.length;
+    ta.focus();
+    // 刷新预览
+    $(ta).trigger('input');
+  }
+
+  function item(m) {
+    const $preview = $('<div class="text-center bg-light" style="height:8em;line-height:8em;overflow:hidden"></div>');
+    if (m.is_image) {
+      $preview.append($('<img class="img-fluid" style="max-height:8em">').attr('src', m.thumb_url).attr('alt', m.file_name));
+    } else {
+      $preview.append('<i class="far fa-file-alt fa-3x text-muted align-middle"></i>');
+    }
+    const $card = $('<a href="javascript:;" class="card d-block text-body"></a>')
+      .append($preview)
+      .append($('<div class="card-body p-1 text-sm text-truncate"></div>').text(m.file_name).attr('title', m.file_name))
+      .on('click', function () {
+        insert(axumMedia.markdown(m));
+        $modal.modal('hide');
+      });
+    return $('<div class="col-6 col-md-3 col-lg-2"></div>').append($card);
+  }
+
+  function load(p) {
+    $.getJSON('/admin/media/list', { page: p, keyword: $('#media-keyword').val() }, function (list) {
+      page = list.page;
+      totalPages = list.total_pages;
+      const $list = $('#media-list').empty();
+      for (const m of list.data) {
+        $list.append(item(m));
+      }
+      if (!list.data.length) {
+        $list.append('<div class="col text-muted">暂无文件</div>');
+      }
+      $('#media-page').text(totalPages ? page + 1 + ' / ' + totalPages : '');
+      $('#media-prev').prop('disabled', page <= 0);
+      $('#media-next').prop('disabled', page + 1 >= totalPages);
+    });
+  }
+
+  $modal.on('show.bs.modal', function () {
+    load(0);
+  });
+  $('#media-prev').on('click', function () {
+    load(page - 1);
+  });
+  $('#media-next').on('click', function () {
+    load(page + 1);
+  });
+  $('#media-search-btn').on('click', function () {
+    load(0);
+  });
+  $('#media-upload-btn').on('click', function () {
+    const files = document.getElementById('media-file').files;
+    if (!files.length) {
+      alert('请选择文件');
+      return;
+    }
+    axumMedia.upload(files, function (results) {
+      const failed = axumMedia.failures(results);
+      if (failed.length) {
+        alert('以下文件上传失败：\n' + failed.join('\n'));
+      }
+      const inserted = results
+        .filter(function (r) { return r.media; })
+        .map(function (r) { return axumMedia.markdown(r.media); });
+      if (inserted.length) {
+        insert(inserted.join('\n\n'));
+        $modal.modal('hide');
+      }
+      document.getElementById('media-file').value = '';
+    });
+  });
+});
@@ -0,0 +1,104 @@
+/**
+ * 人机验证。服务商由 #axum-captcha 的 data-provider 指定，
+ * 调用 axumCaptcha.execute(action, callback, onError) 获取令牌。
+ */
+(function (window, document) {
+  "use strict";
+  const el = document.getElementById("axum-captcha");
+  const provider = el ? el.dataset.provider : "noop";
+  const sitekey = el ? el.dataset.sitekey : "";
+  // 脚本加载超时时间
+  const timeout = 10000;
+  let loaded = provider === "noop";
+  let queue = [];
+  let widget = null;
+  let pending = null;
+
+  window.axumCaptchaOnload = function () {
+    loaded = true;
+    const fns = queue;
+    queue = [];
+    fns.forEach(function (fn) {
+      fn();
+    });
+  };
+
+  function ready(fn, onError) {
+    if (loaded) {
+      fn();
+      return;
+    }
+    queue.push(fn);
+    setTimeout(function () {
+      if (!loaded && queue.indexOf(fn) >= 0) {
+        queue.splice(queue.indexOf(fn), 1);
+        onError && onError(new Error("人机验证加载失败"));
+      }
+    }, timeout);
+  }
+
+  function container() {
+    let c = document.getElementById("axum-captcha-widget");
+    if (!c) {
+      c = document.createElement("div");
+      c.id = "axum-captcha-widget";
+      document.body.appendChild(c);
+    }
+    return c;
+  }
+
+  function onToken(token) {
+    const cb = pending;
+    pending = null;
+    cb && cb(token);
+  }
+
+  function run(action) {
+    switch (provider) {
+      case "hcaptcha":
+      case "recaptcha": {
+        const api = provider === "hcaptcha" ? window.hcaptcha : window.grecaptcha;
+        if (widget === null) {
+          widget = api.render(container(), { sitekey: sitekey, size: "invisible", callback: onToken });
+        } else {
+          api.reset(widget);
+        }
+        api.execute(widget);
+        break;
+      }
+      case "recaptcha_v3":
+        window.grecaptcha.execute(sitekey, { action: action }).then(onToken);
+        break;
+      case "turnstile":
+        if (widget === null) {
+          widget = window.turnstile.render(container(), {
+            sitekey: sitekey,
+            action: action,
+            execution: "execute",
+            appearance: "interaction-only",
+            callback: onToken,
+          });
+        } else {
+          window.turnstile.reset(widget);
+        }
+        window.turnstile.execute(widget);
+        break;
+      default:
+        onToken("noop");
+    }
+  }
+
+  function execute(action, callback, onError) {
+    ready(function () {
+      pending = callback;
+      try {
+        run(action);
+      } catch (e) {
+        pending = null;
+        onError && onError(e);
+      }
+    }, onError);
+  }
+
+  window.axumCaptcha = { provider: provider, execute: execute };
+})(window, document);